@@ -0,0 +1,182 @@
+//! Achievements earned from progress and typing sessions.
+//!
+//! Achievements are checked after each keystroke and review. Earned ones are
+//! saved with the learner's `Progress`, so each is awarded only once.
+
+use crate::jamo;
+use crate::metrics::TypingSession;
+use crate::progress::{MIN_KEYSTROKES, Progress};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Event emitted with an `AchievementUnlocked` payload when one is earned
+pub const EVENT_ACHIEVEMENT: &str = "achievement";
+
+/// Reviews of one syllable, all correct, needed for `PerfectSyllable`
+const PERFECT_SYLLABLE_REVIEWS: u32 = 5;
+
+/// Session speed needed for `Wpm50`
+const FAST_WPM: f32 = 50.0;
+
+/// The 14 basic consonants of the alphabet
+const BASIC_CONSONANTS: [char; 14] = [
+    'ㄱ', 'ㄴ', 'ㄷ', 'ㄹ', 'ㅁ', 'ㅂ', 'ㅅ', 'ㅇ', 'ㅈ', 'ㅊ', 'ㅋ', 'ㅌ', 'ㅍ', 'ㅎ',
+];
+
+/// Something a learner can earn once
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Achievement {
+    /// A syllable reviewed several times without a single miss
+    PerfectSyllable,
+    /// 50 WPM in one session
+    Wpm50,
+    /// Every basic consonant typed correctly at least once
+    AllBasicConsonants,
+}
+
+impl Achievement {
+    /// Every achievement, in the order they are checked
+    pub const ALL: [Achievement; 3] = [
+        Achievement::PerfectSyllable,
+        Achievement::Wpm50,
+        Achievement::AllBasicConsonants,
+    ];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Self::PerfectSyllable => "First 100% syllable",
+            Self::Wpm50 => "50 WPM",
+            Self::AllBasicConsonants => "All basic consonants",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::PerfectSyllable => "Typed a syllable correctly 5 times without a miss",
+            Self::Wpm50 => "Reached 50 words per minute in a session",
+            Self::AllBasicConsonants => "Typed each of the 14 basic consonants correctly",
+        }
+    }
+
+    /// Whether this has been earned, ignoring whether it was awarded before
+    fn is_met(self, progress: &Progress, session: &TypingSession) -> bool {
+        match self {
+            Self::PerfectSyllable => progress.per_char_attempts.iter().any(|(c, &attempts)| {
+                jamo::decompose(*c).is_some()
+                    && attempts >= PERFECT_SYLLABLE_REVIEWS
+                    && progress.per_char_mistakes.get(c).copied().unwrap_or(0) == 0
+            }),
+            Self::Wpm50 => session.keystrokes.len() >= MIN_KEYSTROKES && session.wpm() >= FAST_WPM,
+            Self::AllBasicConsonants => {
+                let typed = consonants_typed(progress, session);
+                BASIC_CONSONANTS.iter().all(|c| typed.contains(c))
+            }
+        }
+    }
+}
+
+/// Payload of `achievement` events
+#[derive(Debug, Clone, Serialize)]
+pub struct AchievementUnlocked {
+    pub achievement: Achievement,
+    pub title: &'static str,
+    pub description: &'static str,
+}
+
+impl From<Achievement> for AchievementUnlocked {
+    fn from(achievement: Achievement) -> Self {
+        Self {
+            achievement,
+            title: achievement.title(),
+            description: achievement.description(),
+        }
+    }
+}
+
+/// Consonants typed correctly at least once, as standalone jamo or as the
+/// initial of a syllable, in saved reviews or the current session
+fn consonants_typed(progress: &Progress, session: &TypingSession) -> HashSet<char> {
+    let reviewed = progress
+        .per_char_attempts
+        .iter()
+        .filter_map(|(c, &attempts)| {
+            let mistakes = progress.per_char_mistakes.get(c).copied().unwrap_or(0);
+            (attempts > mistakes).then_some(*c)
+        });
+    let session = session
+        .keystrokes
+        .iter()
+        .filter(|k| k.is_correct())
+        .map(|k| k.expected);
+
+    reviewed
+        .chain(session)
+        .filter_map(|c| match jamo::decompose(c) {
+            Some((cho, _, _)) => Some(cho),
+            None => jamo::is_consonant(c).then_some(c),
+        })
+        .collect()
+}
+
+/// Achievements earned by `progress` and `session` that haven't been
+/// awarded yet
+pub fn evaluate(progress: &Progress, session: &TypingSession) -> Vec<Achievement> {
+    Achievement::ALL
+        .into_iter()
+        .filter(|a| !progress.achievements.contains(a))
+        .filter(|a| a.is_met(progress, session))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn fast_session_earns_wpm_achievement_once() {
+        let start = Instant::now();
+        let mut session = TypingSession::starting_at(start);
+        // 20 syllables = 50 keystrokes = 10 words in 12 seconds
+        for i in 0..20 {
+            session.record_at('가', '가', start + Duration::from_millis(600 * (i + 1)));
+        }
+
+        let mut progress = Progress::default();
+        assert_eq!(evaluate(&progress, &session), vec![Achievement::Wpm50]);
+
+        progress.achievements.push(Achievement::Wpm50);
+        assert!(evaluate(&progress, &session).is_empty());
+    }
+
+    #[test]
+    fn short_burst_does_not_count_as_fast() {
+        let start = Instant::now();
+        let mut session = TypingSession::starting_at(start);
+        session.record_at('가', '가', start + Duration::from_millis(100));
+
+        assert!(evaluate(&Progress::default(), &session).is_empty());
+    }
+
+    #[test]
+    fn all_basic_consonants_across_reviews_and_session() {
+        let start = Instant::now();
+        let mut progress = Progress::default();
+        for c in "가나다라마바사아자차카타".chars() {
+            progress.record_review(c, true, chrono::Utc::now());
+        }
+        // A missed review doesn't count
+        progress.record_review('파', false, chrono::Utc::now());
+
+        let mut session = TypingSession::starting_at(start);
+        session.record_at('ㅎ', 'ㅎ', start);
+        assert!(evaluate(&progress, &session).is_empty());
+
+        session.record_at('퍼', '퍼', start);
+        assert_eq!(
+            evaluate(&progress, &session),
+            vec![Achievement::AllBasicConsonants]
+        );
+    }
+}
@@ -0,0 +1,161 @@
+//! Local learning analytics.
+//!
+//! Summarizes saved progress and the current session for a dashboard,
+//! computed entirely on the learner's machine; nothing is sent anywhere.
+
+use crate::lesson;
+use crate::metrics::{Keystroke, TypingSession};
+use crate::progress::Progress;
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+
+/// Weakest jamo listed in a summary
+const WEAK_JAMO_LIMIT: usize = 5;
+
+/// Attempts at a jamo before its accuracy counts in full towards mastery;
+/// fewer attempts scale mastery down, so a lucky first try isn't mastery
+const MASTERY_ATTEMPTS: u32 = 10;
+
+/// A jamo and how often it has been mistyped
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WeakJamo {
+    pub jamo: char,
+    pub mistakes: u32,
+}
+
+/// Everything a progress dashboard shows, in one call
+#[derive(Debug, Clone, Serialize)]
+pub struct AnalyticsSummary {
+    /// Most-missed jamo, worst first
+    pub weak_jamo: Vec<WeakJamo>,
+    /// Speed in the current session, if one is in progress
+    pub wpm: Option<f32>,
+    /// Accuracy in the current session (0.0 - 1.0)
+    pub accuracy: Option<f32>,
+    /// Accuracy in the second half of the session minus the first half;
+    /// positive when the learner is warming up
+    pub accuracy_trend: Option<f32>,
+    /// Syllable to practise next, if anything is left to work on
+    pub recommended: Option<char>,
+}
+
+/// Summarize `progress` and, if one is in progress, `session`
+pub fn summary(progress: &Progress, session: Option<&TypingSession>) -> AnalyticsSummary {
+    let mut weak_jamo: Vec<WeakJamo> = lesson::jamo_mistakes(progress)
+        .into_iter()
+        .filter(|&(_, mistakes)| mistakes > 0)
+        .map(|(jamo, mistakes)| WeakJamo { jamo, mistakes })
+        .collect();
+    // Ties in jamo order, so the list is stable between calls
+    weak_jamo.sort_by_key(|w| (Reverse(w.mistakes), w.jamo));
+    weak_jamo.truncate(WEAK_JAMO_LIMIT);
+
+    AnalyticsSummary {
+        weak_jamo,
+        wpm: session.map(TypingSession::wpm),
+        accuracy: session.map(TypingSession::accuracy),
+        accuracy_trend: session.and_then(accuracy_trend),
+        recommended: lesson::recommend_next(progress),
+    }
+}
+
+/// Mastery of each practised jamo as a percentage (0.0 - 100.0).
+///
+/// Each syllable's attempts and mistakes count towards all its jamo, so a
+/// jamo that is missed in several syllables shows up as weak even if no
+/// single syllable stands out. Mastery is the jamo's accuracy, scaled down
+/// until it has been attempted [`MASTERY_ATTEMPTS`] times.
+pub fn mastery_map(progress: &Progress) -> HashMap<char, f32> {
+    let attempts = lesson::jamo_attempts(progress);
+    let mistakes = lesson::jamo_mistakes(progress);
+    let practised: HashSet<char> = attempts.keys().chain(mistakes.keys()).copied().collect();
+
+    practised
+        .into_iter()
+        .map(|jamo| {
+            let missed = mistakes.get(&jamo).copied().unwrap_or(0);
+            // Old saves may have counted mistakes without attempts
+            let tried = attempts.get(&jamo).copied().unwrap_or(0).max(missed);
+            let accuracy = 1.0 - missed as f32 / tried as f32;
+            let confidence = tried.min(MASTERY_ATTEMPTS) as f32 / MASTERY_ATTEMPTS as f32;
+            (jamo, accuracy * confidence * 100.0)
+        })
+        .collect()
+}
+
+/// Change in accuracy from the first half of a session to the second, or
+/// `None` until there are keystrokes in both halves
+fn accuracy_trend(session: &TypingSession) -> Option<f32> {
+    let (earlier, later) = session.keystrokes.split_at(session.keystrokes.len() / 2);
+    if earlier.is_empty() {
+        return None;
+    }
+    let accuracy = |keystrokes: &[Keystroke]| {
+        let correct = keystrokes.iter().filter(|k| k.is_correct()).count();
+        correct as f32 / keystrokes.len() as f32
+    };
+    Some(accuracy(later) - accuracy(earlier))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[test]
+    fn weak_jamo_are_the_most_missed() {
+        let mut progress = Progress::default();
+        // 가 twice and 나 once: ㅏ 3, ㄱ 2, ㄴ 1
+        progress.per_char_mistakes.insert('가', 2);
+        progress.per_char_mistakes.insert('나', 1);
+
+        let summary = summary(&progress, None);
+        let weak: Vec<(char, u32)> = summary
+            .weak_jamo
+            .iter()
+            .map(|w| (w.jamo, w.mistakes))
+            .collect();
+        assert_eq!(weak, [('ㅏ', 3), ('ㄱ', 2), ('ㄴ', 1)]);
+        assert_eq!(summary.wpm, None);
+        assert!(summary.recommended.is_some());
+    }
+
+    #[test]
+    fn jamo_missed_in_two_syllables_has_low_mastery() {
+        let mut progress = Progress::default();
+        // ㄱ is missed in both 가 and 고; ㅏ only with ㄱ, never in 나
+        for (syllable, correct) in [('가', 5), ('고', 5), ('나', 10)] {
+            for i in 0..10 {
+                progress.record_typed(syllable, i < correct);
+            }
+        }
+
+        let mastery = mastery_map(&progress);
+        assert_eq!(mastery[&'ㄱ'], 50.0);
+        assert_eq!(mastery[&'ㄴ'], 100.0);
+        assert!(mastery[&'ㄱ'] < mastery[&'ㅏ']);
+        assert!(mastery[&'ㅏ'] < mastery[&'ㄴ']);
+    }
+
+    #[test]
+    fn mastery_needs_enough_attempts() {
+        let mut progress = Progress::default();
+        progress.record_typed('가', true);
+        progress.record_typed('가', true);
+
+        assert_eq!(mastery_map(&progress)[&'ㄱ'], 20.0);
+    }
+
+    #[test]
+    fn accuracy_trend_compares_session_halves() {
+        let mut session = TypingSession::starting_at(Instant::now());
+        for (expected, actual) in [('가', '거'), ('나', '나'), ('다', '다'), ('라', '라')] {
+            session.record(expected, actual);
+        }
+
+        let summary = summary(&Progress::default(), Some(&session));
+        assert_eq!(summary.accuracy, Some(0.75));
+        assert_eq!(summary.accuracy_trend, Some(0.5));
+    }
+}
@@ -0,0 +1,112 @@
+//! Flashcard export for Anki.
+//!
+//! Produces tab-separated rows that Anki's basic importer reads directly,
+//! one note per character: the character, its romanization, the keys that
+//! type it and a memory aid for those keys.
+
+use crate::jamo;
+use crate::layout::{self, Layout, PhysicalLayout};
+use crate::romanize;
+
+/// Make a field safe for a TSV row. Anki fields are HTML, so line breaks
+/// become `<br>` and tabs a character reference.
+fn escape_field(field: &str) -> String {
+    field
+        .replace('\r', "")
+        .replace('\n', "<br>")
+        .replace('\t', "&#9;")
+}
+
+/// Memory aids for the jamo that make up `c`, with compound jamo split
+/// into their parts. Only 2-Bulsik has mnemonics.
+fn mnemonics(c: char) -> Vec<String> {
+    let parts = match jamo::decompose(c) {
+        Some((cho, jung, jong)) => vec![Some(cho), Some(jung), jong],
+        None => vec![Some(c)],
+    };
+    parts
+        .into_iter()
+        .flatten()
+        .flat_map(|j| match jamo::split_compound(j) {
+            Some(parts) => parts.to_vec(),
+            None => vec![j],
+        })
+        .filter_map(layout::mnemonic)
+        .collect()
+}
+
+/// One Anki note per character, in order, skipping repeats.
+///
+/// Fields are the character, its Revised Romanization, its keys on
+/// `layout` labelled for `physical`, and mnemonics for those keys (empty
+/// on layouts other than 2-Bulsik).
+pub fn to_tsv(chars: &[char], layout: Layout, physical: PhysicalLayout) -> String {
+    let mut seen = Vec::new();
+    let mut tsv = String::new();
+
+    for &c in chars {
+        if seen.contains(&c) || c.is_whitespace() {
+            continue;
+        }
+        seen.push(c);
+
+        let text = c.to_string();
+        let keys: Vec<String> = physical
+            .translate_all(&layout.keystrokes_for_text(&text))
+            .iter()
+            .map(|k| k.to_string())
+            .collect();
+        let mnemonic = if layout == Layout::TwoBulsik {
+            mnemonics(c).join(" ")
+        } else {
+            String::new()
+        };
+
+        let fields = [
+            text.clone(),
+            romanize::revised(&text),
+            keys.join(", "),
+            mnemonic,
+        ];
+        let row: Vec<String> = fields.iter().map(|f| escape_field(f)).collect();
+        tsv.push_str(&row.join("\t"));
+        tsv.push('\n');
+    }
+    tsv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn row_for_ga_has_four_fields() {
+        let tsv = to_tsv(&['가'], Layout::TwoBulsik, PhysicalLayout::Qwerty);
+        let fields: Vec<&str> = tsv.trim_end_matches('\n').split('\t').collect();
+
+        assert_eq!(tsv.lines().count(), 1);
+        assert_eq!(fields.len(), 4);
+        assert_eq!(fields[..3], ["가", "ga", "r, k"]);
+        assert_eq!(
+            fields[3],
+            format!(
+                "{} {}",
+                layout::mnemonic('ㄱ').unwrap(),
+                layout::mnemonic('ㅏ').unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn fields_never_contain_tabs_or_newlines() {
+        assert_eq!(escape_field("a\tb\r\nc"), "a&#9;b<br>c");
+
+        let tsv = to_tsv(
+            &['과', '과', '값'],
+            Layout::TwoBulsik,
+            PhysicalLayout::Qwerty,
+        );
+        assert_eq!(tsv.lines().count(), 2);
+        assert!(tsv.lines().all(|row| row.split('\t').count() == 4));
+    }
+}
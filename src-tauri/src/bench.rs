@@ -0,0 +1,99 @@
+//! Composition throughput smoke test.
+//!
+//! Runs random modern syllables through the jamo tables and the IME
+//! composer, timing them and checking each one comes back unchanged, so a
+//! performance or correctness regression in either shows up from inside the
+//! app without a profiler.
+
+use crate::ime::Composer;
+use crate::jamo;
+use crate::layout;
+use rand::Rng;
+use serde::Serialize;
+use std::time::Instant;
+
+/// First and last modern precomposed syllables (가 and 힣)
+const FIRST_SYLLABLE: u32 = 0xAC00;
+const LAST_SYLLABLE: u32 = 0xD7A3;
+
+/// Largest run accepted, so a typo can't hang the app
+pub const MAX_ITERATIONS: usize = 1_000_000;
+
+/// Timing and correctness of one benchmark run
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchResult {
+    pub iterations: usize,
+    pub elapsed_ms: f64,
+    pub ops_per_sec: f64,
+    /// Syllables that didn't survive the round trip; should always be 0
+    pub failures: usize,
+}
+
+/// Whether `syllable` decomposes and composes back to itself, and typing
+/// its keys into a [`Composer`] produces it too
+pub fn round_trips(syllable: char) -> bool {
+    let Some((cho, jung, jong)) = jamo::decompose(syllable) else {
+        return false;
+    };
+    if jamo::compose(cho, jung, jong) != Some(syllable) {
+        return false;
+    }
+
+    let mut composer = Composer::new();
+    let mut typed: Vec<char> = layout::keystrokes_for(syllable)
+        .unwrap_or_default()
+        .into_iter()
+        .flat_map(|key| composer.feed(key))
+        .collect();
+    typed.extend(composer.flush());
+    typed == [syllable]
+}
+
+/// Round-trip `iterations` random syllables (at most [`MAX_ITERATIONS`])
+pub fn run(iterations: usize) -> BenchResult {
+    let iterations = iterations.min(MAX_ITERATIONS);
+    let mut rng = rand::thread_rng();
+    // Picked up front so the timing covers composition only
+    let syllables: Vec<char> = (0..iterations)
+        .filter_map(|_| char::from_u32(rng.gen_range(FIRST_SYLLABLE..=LAST_SYLLABLE)))
+        .collect();
+
+    let start = Instant::now();
+    let failures = syllables.iter().filter(|&&s| !round_trips(s)).count();
+    let elapsed = start.elapsed();
+
+    let secs = elapsed.as_secs_f64();
+    BenchResult {
+        iterations,
+        elapsed_ms: secs * 1000.0,
+        ops_per_sec: if secs > 0.0 {
+            iterations as f64 / secs
+        } else {
+            0.0
+        },
+        failures,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_modern_syllable_round_trips() {
+        let syllables: Vec<char> = (FIRST_SYLLABLE..=LAST_SYLLABLE)
+            .filter_map(char::from_u32)
+            .collect();
+        assert_eq!(syllables.len(), 11_172);
+
+        let broken: Vec<char> = syllables.into_iter().filter(|&s| !round_trips(s)).collect();
+        assert!(broken.is_empty(), "{:?}", broken);
+    }
+
+    #[test]
+    fn run_reports_no_failures() {
+        let result = run(1_000);
+        assert_eq!(result.iterations, 1_000);
+        assert_eq!(result.failures, 0);
+    }
+}
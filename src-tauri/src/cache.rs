@@ -0,0 +1,161 @@
+//! A small least-recently-used cache.
+//!
+//! Used to remember recent Copilot answers so drills that ask about the
+//! same syllable again don't repeat the API call. Capacities are small
+//! (a few hundred entries), so recency is tracked with a simple queue.
+
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+
+/// Fixed-capacity map that evicts the least recently used entry
+#[derive(Debug, Clone)]
+pub struct LruCache<K, V> {
+    capacity: usize,
+    entries: HashMap<K, V>,
+    /// Keys from least to most recently used
+    order: VecDeque<K>,
+}
+
+impl<K: Clone + Eq + Hash, V> LruCache<K, V> {
+    /// Create an empty cache. A capacity of 0 disables caching.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// Look up `key`, marking it as most recently used
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        if !self.entries.contains_key(key) {
+            return None;
+        }
+        self.touch(key);
+        self.entries.get(key)
+    }
+
+    /// Look up `key` for changing in place, marking it as most recently used
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        if !self.entries.contains_key(key) {
+            return None;
+        }
+        self.touch(key);
+        self.entries.get_mut(key)
+    }
+
+    /// Remove `key`, returning its value if it was present
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let value = self.entries.remove(key)?;
+        self.order.retain(|k| k != key);
+        Some(value)
+    }
+
+    /// Store `value` under `key`, evicting the oldest entries if full
+    pub fn insert(&mut self, key: K, value: V) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.insert(key.clone(), value).is_some() {
+            self.touch(&key);
+        } else {
+            self.order.push_back(key);
+        }
+        self.evict();
+    }
+
+    /// Change the capacity, evicting the oldest entries if it shrank
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.evict();
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Remove every entry
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+
+    /// Move `key` to the most recently used end
+    fn touch(&mut self, key: &K) {
+        if let Some(i) = self.order.iter().position(|k| k == key) {
+            let key = self.order.remove(i).expect("index from position");
+            self.order.push_back(key);
+        }
+    }
+
+    fn evict(&mut self) {
+        while self.entries.len() > self.capacity {
+            match self.order.pop_front() {
+                Some(oldest) => {
+                    self.entries.remove(&oldest);
+                }
+                None => break,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let mut cache = LruCache::new(2);
+        cache.insert("가", 1);
+        cache.insert("나", 2);
+        // Reading 가 makes 나 the oldest
+        assert_eq!(cache.get(&"가"), Some(&1));
+        cache.insert("다", 3);
+
+        assert_eq!(cache.get(&"나"), None);
+        assert_eq!(cache.get(&"가"), Some(&1));
+        assert_eq!(cache.get(&"다"), Some(&3));
+    }
+
+    #[test]
+    fn zero_capacity_stores_nothing() {
+        let mut cache = LruCache::new(0);
+        cache.insert("가", 1);
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn shrinking_capacity_evicts_oldest() {
+        let mut cache = LruCache::new(3);
+        for (i, key) in ["가", "나", "다"].into_iter().enumerate() {
+            cache.insert(key, i);
+        }
+        cache.set_capacity(1);
+
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.get(&"다"), Some(&2));
+    }
+
+    #[test]
+    fn removed_entries_are_not_evicted_again() {
+        let mut cache = LruCache::new(2);
+        cache.insert("가", 1);
+        cache.insert("나", 2);
+
+        assert_eq!(cache.remove(&"가"), Some(1));
+        assert_eq!(cache.remove(&"가"), None);
+        cache.insert("다", 3);
+
+        assert_eq!(cache.get(&"나"), Some(&2));
+        assert_eq!(cache.get(&"다"), Some(&3));
+    }
+}
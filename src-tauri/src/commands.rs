@@ -2,9 +2,55 @@
 //!
 //! These commands are invoked from the frontend via `invoke()`.
 
-use crate::copilot::{self, AssistantResponse, CopilotError, LearningContext};
+use crate::achievements::{self, AchievementUnlocked, EVENT_ACHIEVEMENT};
+use crate::analytics::{self, AnalyticsSummary};
+use crate::anki;
+use crate::bench::{self, BenchResult};
+use crate::copilot::{
+    self, AssistantResponse, CopilotError, CopilotService, ErrorInfo, LearningContext, Persona,
+    PingResult, ScenarioTurn, StartOutcome, TranscriptTurn, UsageStats,
+};
+use crate::curriculum::{self, Curriculum, WordValidation};
+use crate::explain::{self, SyllableExplanation};
+use crate::frequency;
+use crate::grade::{self, GradeResult};
+use crate::hint::{self, NextKeysResult};
+use crate::ime::{self, Composition, LiveComposer};
+use crate::jamo::{self, HanjaError};
+use crate::language;
+use crate::layout::{self, Finger, KeyMapping, KeyPress, Layout, PhysicalLayout};
+use crate::lesson;
+use crate::logging;
+use crate::metrics::{
+    self, KeyStats, ReplayFrame, SavedSession, SessionDelta, SessionStats, TypingSession,
+};
+use crate::ngram::{self, BigramModel};
+use crate::profiles::{self, PROFILES_FILE, ProfileError, ProfileSummary, Profiles};
+use crate::progress::{
+    self, Autosave, EVENT_PROGRESS, EVENT_PROGRESS_RESET, MIN_KEYSTROKES, Progress,
+};
+use crate::quiz::{QuizQuestion, QuizResult};
+use crate::romanize::{self, Style};
+use crate::strokes::{self, Stroke};
+use chrono::Utc;
 use serde::Serialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
 use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+/// Event carrying one chunk of a streamed response
+pub const EVENT_DELTA: &str = "copilot-delta";
+/// Event emitted once a streamed response has finished
+pub const EVENT_COMPLETE: &str = "copilot-complete";
+/// Event emitted when a streamed response fails
+pub const EVENT_ERROR: &str = "copilot-error";
+/// Most "did you mean" suggestions offered for one input
+const SUGGESTION_LIMIT: usize = 5;
+/// Error for a comparison that isn't between two syllable blocks
+const NOT_TWO_SYLLABLES: &str = "Compare two single Hangul syllables";
 
 /// Response wrapper for frontend
 #[derive(Debug, Serialize)]
@@ -12,6 +58,9 @@ pub struct CommandResponse<T> {
     pub success: bool,
     pub data: Option<T>,
     pub error: Option<String>,
+    /// Machine-readable kind of failure, so the UI can offer the right fix
+    /// (e.g. "cli_not_found"). `None` for errors that don't come from Copilot.
+    pub error_code: Option<String>,
 }
 
 impl<T> CommandResponse<T> {
@@ -20,6 +69,7 @@ impl<T> CommandResponse<T> {
             success: true,
             data: Some(data),
             error: None,
+            error_code: None,
         }
     }
 
@@ -28,10 +78,69 @@ impl<T> CommandResponse<T> {
             success: false,
             data: None,
             error: Some(message),
+            error_code: None,
+        }
+    }
+
+    /// Failure from Copilot, coded by its [`CopilotError::category`]
+    pub fn copilot_err(error: &CopilotError) -> Self {
+        Self {
+            error_code: Some(error.category().to_string()),
+            ..Self::err(error.to_string())
+        }
+    }
+
+    /// Failure because the Copilot service isn't running, coded by why:
+    /// a missing or signed-out CLI, or a service that was never started
+    fn not_running(message: &str) -> Self {
+        let error = copilot::check_availability()
+            .error()
+            .unwrap_or(CopilotError::NotInitialized);
+        Self {
+            error_code: Some(error.category().to_string()),
+            ..Self::err(message.to_string())
         }
     }
 }
 
+/// Payload for `copilot-delta` events
+#[derive(Debug, Clone, Serialize)]
+pub struct StreamDelta {
+    pub request_id: String,
+    pub delta: String,
+}
+
+/// Payload for `copilot-complete` events
+#[derive(Debug, Clone, Serialize)]
+pub struct StreamComplete {
+    pub request_id: String,
+    pub response: AssistantResponse,
+}
+
+/// Payload for `copilot-error` events
+#[derive(Debug, Clone, Serialize)]
+pub struct StreamError {
+    pub request_id: String,
+    pub error: String,
+    /// Machine-readable kind of failure; see [`CopilotError::category`]
+    pub error_code: String,
+}
+
+/// Per-syllable breakdown of a word plus an overall pronunciation note
+#[derive(Debug, Serialize)]
+pub struct ExplanationBreakdown {
+    pub syllables: Vec<SyllableExplanation>,
+    /// Only present when Copilot is running and answered
+    pub pronunciation_note: Option<String>,
+}
+
+/// A practice sentence with the keys for each syllable
+#[derive(Debug, Serialize)]
+pub struct GeneratedSentence {
+    pub sentence: String,
+    pub syllables: Vec<SyllableExplanation>,
+}
+
 /// Status of the Copilot service
 #[derive(Debug, Serialize)]
 pub struct CopilotStatus {
@@ -40,6 +149,11 @@ pub struct CopilotStatus {
     pub cli_installed: bool,
     pub cli_authenticated: bool,
     pub message: String,
+    /// True only when this call started the client, so warmup work is
+    /// needed; false if it was already running
+    pub newly_started: bool,
+    /// The client died and the next request restarts it
+    pub restartable: bool,
 }
 
 /// Check Copilot availability without starting the service
@@ -55,10 +169,16 @@ pub async fn copilot_check() -> CommandResponse<CopilotStatus> {
         cli_installed: availability.cli_installed,
         cli_authenticated: availability.cli_authenticated,
         message: availability.message,
+        newly_started: false,
+        restartable: false,
     })
 }
 
 /// Initialize the Copilot service
+///
+/// When the CLI is missing or signed out the response fails with an
+/// `error_code` ("cli_not_found", "not_authenticated") but still carries
+/// the status.
 #[tauri::command]
 pub async fn copilot_init() -> CommandResponse<CopilotStatus> {
     debug!("Initializing Copilot service...");
@@ -66,25 +186,37 @@ pub async fn copilot_init() -> CommandResponse<CopilotStatus> {
     // First check availability
     let availability = copilot::check_availability();
 
-    if !availability.available {
+    // Failures still carry the status, so the UI can say what to install
+    if let Some(e) = availability.error() {
         info!("Copilot not available: {}", availability.message);
-        return CommandResponse::ok(CopilotStatus {
-            available: false,
-            running: false,
-            cli_installed: availability.cli_installed,
-            cli_authenticated: availability.cli_authenticated,
-            message: availability.message,
-        });
+        return CommandResponse {
+            data: Some(CopilotStatus {
+                available: false,
+                running: false,
+                cli_installed: availability.cli_installed,
+                cli_authenticated: availability.cli_authenticated,
+                message: availability.message,
+                newly_started: false,
+                restartable: false,
+            }),
+            ..CommandResponse::copilot_err(&e)
+        };
     }
 
     // Try to initialize
     match copilot::init().await {
-        Ok(()) => CommandResponse::ok(CopilotStatus {
+        Ok(outcome) => CommandResponse::ok(CopilotStatus {
             available: true,
             running: true,
             cli_installed: true,
             cli_authenticated: true,
-            message: "AI assistant ready".to_string(),
+            message: match outcome {
+                StartOutcome::Started => "AI assistant ready",
+                StartOutcome::AlreadyRunning => "AI assistant already running",
+            }
+            .to_string(),
+            newly_started: outcome == StartOutcome::Started,
+            restartable: false,
         }),
         Err(e) => {
             let (cli_installed, cli_authenticated, message) = match &e {
@@ -102,13 +234,18 @@ pub async fn copilot_init() -> CommandResponse<CopilotStatus> {
             };
 
             warn!("Copilot init failed: {}", message);
-            CommandResponse::ok(CopilotStatus {
-                available: false,
-                running: false,
-                cli_installed,
-                cli_authenticated,
-                message,
-            })
+            CommandResponse {
+                data: Some(CopilotStatus {
+                    available: false,
+                    running: false,
+                    cli_installed,
+                    cli_authenticated,
+                    message,
+                    newly_started: false,
+                    restartable: false,
+                }),
+                ..CommandResponse::copilot_err(&e)
+            }
         }
     }
 }
@@ -118,15 +255,18 @@ pub async fn copilot_init() -> CommandResponse<CopilotStatus> {
 pub async fn copilot_status() -> CommandResponse<CopilotStatus> {
     let service = copilot::get_service();
     let running = service.is_running().await;
+    let restartable = service.is_restartable();
     let availability = copilot::check_availability();
 
     CommandResponse::ok(CopilotStatus {
-        available: availability.available && running,
+        available: availability.available && (running || restartable),
         running,
         cli_installed: availability.cli_installed,
         cli_authenticated: availability.cli_authenticated,
         message: if running {
             "AI assistant ready".to_string()
+        } else if restartable {
+            "AI assistant stopped unexpectedly; it restarts on the next request".to_string()
         } else if !availability.cli_installed {
             "GitHub Copilot CLI not installed".to_string()
         } else if !availability.cli_authenticated {
@@ -134,74 +274,606 @@ pub async fn copilot_status() -> CommandResponse<CopilotStatus> {
         } else {
             "AI assistant not running".to_string()
         },
+        newly_started: false,
+        restartable,
     })
 }
 
+/// Check the backend actually answers by sending a trivial prompt
+///
+/// Reports round-trip latency on success. Unlike `copilot_status`, this
+/// catches a session that is running but unresponsive.
+#[tauri::command]
+pub async fn copilot_ping() -> CommandResponse<PingResult> {
+    debug!("Pinging Copilot...");
+
+    CommandResponse::ok(copilot::get_service().ping().await)
+}
+
 /// Ask a general question to the Copilot assistant
+///
+/// Pass a `request_id` to be able to cancel the request with `copilot_cancel`.
+/// Pass a `conversation_id` from `copilot_new_conversation` to continue a
+/// multi-turn conversation.
 #[tauri::command]
 pub async fn copilot_ask(
     prompt: String,
     context: Option<LearningContext>,
+    request_id: Option<Uuid>,
+    conversation_id: Option<Uuid>,
 ) -> CommandResponse<AssistantResponse> {
     debug!("Copilot ask: {}", prompt);
 
     let service = copilot::get_service();
 
-    if !service.is_running().await {
-        return CommandResponse::err(
-            "AI assistant not running. Copilot CLI may not be installed.".to_string(),
+    if !service.can_serve().await {
+        return CommandResponse::not_running(
+            "AI assistant not running. Copilot CLI may not be installed.",
         );
     }
 
-    match service.ask(&prompt, context).await {
+    let id = request_id.unwrap_or_else(Uuid::new_v4);
+    let cancel = service.begin_request(id).await;
+    let result = match conversation_id {
+        Some(conversation) => {
+            service
+                .ask_conversation(conversation, &prompt, context, &cancel)
+                .await
+        }
+        None => service.ask(&prompt, context, &cancel).await,
+    };
+    service.finish_request(id).await;
+
+    match result {
         Ok(response) => CommandResponse::ok(response),
         Err(e) => {
             error!("Copilot ask failed: {}", e);
-            CommandResponse::err(e.to_string())
+            CommandResponse::copilot_err(&e)
+        }
+    }
+}
+
+/// Ask Copilot and wait for the complete answer, without emitting events
+///
+/// For scripts and headless tests. `timeout_secs` overrides the response
+/// timeout for this call only (clamped to the allowed range). Pass a
+/// `request_id` to be able to cancel it with `copilot_cancel`. Failures carry
+/// an `error_code` like every Copilot command.
+#[tauri::command]
+pub async fn copilot_ask_blocking(
+    prompt: String,
+    context: Option<LearningContext>,
+    timeout_secs: Option<u64>,
+    request_id: Option<Uuid>,
+) -> CommandResponse<AssistantResponse> {
+    debug!("Copilot blocking ask: {}", prompt);
+
+    let service = copilot::get_service();
+    if !service.can_serve().await {
+        return CommandResponse::not_running(
+            "AI assistant not running. Copilot CLI may not be installed.",
+        );
+    }
+    let timeout_secs = match timeout_secs {
+        Some(secs) => secs,
+        None => service.timeout().await.as_secs(),
+    };
+
+    let id = request_id.unwrap_or_else(Uuid::new_v4);
+    let cancel = service.begin_request(id).await;
+    let result = service
+        .ask_blocking(&prompt, context, timeout_secs, &cancel)
+        .await;
+    service.finish_request(id).await;
+
+    match result {
+        Ok(response) => CommandResponse::ok(response),
+        Err(e) => {
+            error!("Copilot blocking ask failed: {}", e);
+            CommandResponse::copilot_err(&e)
         }
     }
 }
 
+/// Start a new conversation whose turns are remembered by the assistant
+#[tauri::command]
+pub async fn copilot_new_conversation() -> CommandResponse<String> {
+    let id = copilot::get_service().new_conversation().await;
+    CommandResponse::ok(id.to_string())
+}
+
+/// End a conversation and free its session. Returns false if it had already
+/// ended or expired.
+#[tauri::command]
+pub async fn copilot_end_conversation(id: Uuid) -> CommandResponse<bool> {
+    debug!("Ending conversation {}", id);
+
+    let ended = copilot::get_service().end_conversation(id).await;
+    CommandResponse::ok(ended)
+}
+
+/// Messages exchanged so far in a conversation, oldest first
+#[tauri::command]
+pub async fn copilot_transcript(conversation_id: Uuid) -> CommandResponse<Vec<TranscriptTurn>> {
+    match copilot::get_service().transcript(conversation_id).await {
+        Ok(transcript) => CommandResponse::ok(transcript),
+        Err(e) => CommandResponse::copilot_err(&e),
+    }
+}
+
+/// Set how long an idle conversation is kept before it is evicted.
+/// Returns the TTL actually applied, which is at least a minute.
+#[tauri::command]
+pub async fn copilot_set_conversation_ttl(secs: u64) -> CommandResponse<u64> {
+    debug!("Setting conversation TTL to {}s", secs);
+
+    let applied = copilot::get_service().set_conversation_ttl(secs).await;
+    CommandResponse::ok(applied)
+}
+
+/// Ask a question and stream the answer back as Tauri events
+///
+/// Returns a request id immediately. Each chunk of the answer is emitted in
+/// order as a `copilot-delta` event, followed by a single `copilot-complete`
+/// event, or a `copilot-error` event if the request fails.
+#[tauri::command]
+pub async fn copilot_ask_stream(
+    app: AppHandle,
+    prompt: String,
+    context: Option<LearningContext>,
+) -> CommandResponse<String> {
+    debug!("Copilot ask (stream): {}", prompt);
+
+    let service = copilot::get_service();
+
+    if !service.can_serve().await {
+        return CommandResponse::not_running(
+            "AI assistant not running. Copilot CLI may not be installed.",
+        );
+    }
+
+    let request_id = Uuid::new_v4();
+    let cancel = service.begin_request(request_id).await;
+    let id = request_id.to_string();
+
+    tauri::async_runtime::spawn(async move {
+        let delta_app = app.clone();
+        let delta_id = id.clone();
+        let result = service
+            .ask_streaming(&prompt, context, &cancel, |delta| {
+                let payload = StreamDelta {
+                    request_id: delta_id.clone(),
+                    delta: delta.to_string(),
+                };
+                if let Err(e) = delta_app.emit(EVENT_DELTA, payload) {
+                    warn!("Failed to emit {}: {}", EVENT_DELTA, e);
+                }
+            })
+            .await;
+        service.finish_request(request_id).await;
+
+        let emitted = match result {
+            Ok(response) => app.emit(
+                EVENT_COMPLETE,
+                StreamComplete {
+                    request_id: id,
+                    response,
+                },
+            ),
+            Err(e) => {
+                error!("Copilot ask (stream) failed: {}", e);
+                app.emit(
+                    EVENT_ERROR,
+                    StreamError {
+                        request_id: id,
+                        error: e.to_string(),
+                        error_code: e.category().to_string(),
+                    },
+                )
+            }
+        };
+        if let Err(e) = emitted {
+            warn!("Failed to emit stream result: {}", e);
+        }
+    });
+
+    CommandResponse::ok(request_id.to_string())
+}
+
 /// Get a hint for the current typing target
+///
+/// Falls back to the offline hint engine when Copilot is not running.
 #[tauri::command]
 pub async fn copilot_hint(
     target: String,
     user_input: String,
     level: u32,
+    request_id: Option<Uuid>,
 ) -> CommandResponse<AssistantResponse> {
     debug!("Copilot hint: target='{}', input='{}'", target, user_input);
 
     let service = copilot::get_service();
 
-    if !service.is_running().await {
-        return CommandResponse::err("AI assistant not available".to_string());
+    if !service.can_serve().await {
+        debug!("Copilot not running, using local hint");
+        let layout = service.layout().await;
+        let physical = service.physical_layout().await;
+        return CommandResponse::ok(hint::local_hint(layout, physical, &target, &user_input));
     }
 
-    match service.get_hint(&target, &user_input, level).await {
+    let id = request_id.unwrap_or_else(Uuid::new_v4);
+    let cancel = service.begin_request(id).await;
+    let result = service.get_hint(&target, &user_input, level, &cancel).await;
+    service.finish_request(id).await;
+
+    match result {
         Ok(response) => CommandResponse::ok(response),
         Err(e) => {
             error!("Copilot hint failed: {}", e);
-            CommandResponse::err(e.to_string())
+            CommandResponse::copilot_err(&e)
         }
     }
 }
 
+/// Fetch opening hints for upcoming lesson targets in the background
+///
+/// Returns at once with the number of targets queued; the hints land in
+/// the response cache so later `copilot_hint` calls answer instantly.
+#[tauri::command]
+pub async fn copilot_prefetch_hints(targets: Vec<String>, level: u32) -> CommandResponse<usize> {
+    debug!("Copilot prefetch hints: {} targets", targets.len());
+    let targets: Vec<String> = targets.iter().map(|t| jamo::normalize(t)).collect();
+
+    let service = copilot::get_service();
+
+    if !service.can_serve().await {
+        return CommandResponse::not_running("AI assistant not available");
+    }
+
+    let request_id = Uuid::new_v4();
+    let cancel = service.begin_request(request_id).await;
+    let queued = targets.len().min(copilot::PREFETCH_LIMIT);
+
+    tauri::async_runtime::spawn(async move {
+        let warmed = service.prefetch_hints(&targets, level, &cancel).await;
+        service.finish_request(request_id).await;
+        debug!("Prefetched {} of {} hints", warmed, queued);
+    });
+
+    CommandResponse::ok(queued)
+}
+
 /// Explain a Korean character or word
+///
+/// Falls back to a plain local explanation when Copilot is not running.
 #[tauri::command]
-pub async fn copilot_explain(text: String) -> CommandResponse<AssistantResponse> {
+pub async fn copilot_explain(
+    text: String,
+    request_id: Option<Uuid>,
+) -> CommandResponse<AssistantResponse> {
     debug!("Copilot explain: '{}'", text);
+    let text = jamo::normalize(&text);
 
     let service = copilot::get_service();
 
-    if !service.is_running().await {
-        return CommandResponse::err("AI assistant not available".to_string());
+    if !service.can_serve().await {
+        debug!("Copilot not running, using local explanation");
+        let layout = service.layout().await;
+        let physical = service.physical_layout().await;
+        return match explain::local_explanation(layout, physical, &text) {
+            Ok(response) => CommandResponse::ok(response),
+            Err(e) => CommandResponse::err(e.to_string()),
+        };
     }
 
-    match service.explain(&text).await {
+    let id = request_id.unwrap_or_else(Uuid::new_v4);
+    let cancel = service.begin_request(id).await;
+    let result = service.explain(&text, &cancel).await;
+    service.finish_request(id).await;
+
+    match result {
         Ok(response) => CommandResponse::ok(response),
         Err(e) => {
             error!("Copilot explain failed: {}", e);
-            CommandResponse::err(e.to_string())
+            CommandResponse::copilot_err(&e)
+        }
+    }
+}
+
+/// `text` as one syllable block, if that's all it is
+fn single_syllable(text: &str) -> Option<char> {
+    let text = jamo::normalize(text.trim());
+    let mut chars = text.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if jamo::decompose(c).is_some() => Some(c),
+        _ => None,
+    }
+}
+
+/// Explain the difference between two easily confused syllables (사/자)
+///
+/// Copilot is told exactly which jamo differ; without Copilot, the local
+/// comparison is returned instead.
+#[tauri::command]
+pub async fn copilot_compare(
+    a: String,
+    b: String,
+    request_id: Option<Uuid>,
+) -> CommandResponse<AssistantResponse> {
+    debug!("Copilot compare: '{}' vs '{}'", a, b);
+    let (Some(a), Some(b)) = (single_syllable(&a), single_syllable(&b)) else {
+        return CommandResponse::err(NOT_TWO_SYLLABLES.to_string());
+    };
+
+    let service = copilot::get_service();
+    if !service.can_serve().await {
+        debug!("Copilot not running, using local comparison");
+        let layout = service.layout().await;
+        let physical = service.physical_layout().await;
+        return match explain::local_comparison(layout, physical, a, b) {
+            Some(response) => CommandResponse::ok(response),
+            None => CommandResponse::err(NOT_TWO_SYLLABLES.to_string()),
+        };
+    }
+
+    let id = request_id.unwrap_or_else(Uuid::new_v4);
+    let cancel = service.begin_request(id).await;
+    let result = service.compare(a, b, &cancel).await;
+    service.finish_request(id).await;
+
+    match result {
+        Some(Ok(response)) => CommandResponse::ok(response),
+        Some(Err(e)) => {
+            error!("Copilot compare failed: {}", e);
+            CommandResponse::copilot_err(&e)
+        }
+        None => CommandResponse::err(NOT_TWO_SYLLABLES.to_string()),
+    }
+}
+
+/// Common syllables a mistyped explain target was probably meant to be
+///
+/// Helps when the input has an orphan jamo or a typo; at most five.
+#[tauri::command]
+pub async fn suggest_corrections(input: String) -> CommandResponse<Vec<char>> {
+    CommandResponse::ok(frequency::suggestions(&input, SUGGESTION_LIMIT))
+}
+
+/// Explain several characters or words with one Copilot request
+#[tauri::command]
+pub async fn copilot_explain_batch(
+    texts: Vec<String>,
+    request_id: Option<Uuid>,
+) -> CommandResponse<Vec<AssistantResponse>> {
+    debug!("Copilot explain batch: {} texts", texts.len());
+    let texts: Vec<String> = texts.iter().map(|t| jamo::normalize(t)).collect();
+
+    let service = copilot::get_service();
+
+    if !service.can_serve().await {
+        return CommandResponse::not_running("AI assistant not available");
+    }
+
+    let id = request_id.unwrap_or_else(Uuid::new_v4);
+    let cancel = service.begin_request(id).await;
+    let result = service.explain_batch(&texts, &cancel).await;
+    service.finish_request(id).await;
+
+    match result {
+        Ok(responses) => CommandResponse::ok(responses),
+        Err(e) => {
+            error!("Copilot explain batch failed: {}", e);
+            CommandResponse::copilot_err(&e)
+        }
+    }
+}
+
+/// Per-syllable breakdown with keys labelled for the learner's keyboard
+async fn breakdown_for_keyboard(
+    service: &CopilotService,
+    text: &str,
+) -> Result<Vec<SyllableExplanation>, HanjaError> {
+    let physical = service.physical_layout().await;
+    Ok(explain::breakdown(text, service.layout().await)?
+        .into_iter()
+        .map(|s| SyllableExplanation {
+            keystrokes: physical.translate_all(&s.keystrokes),
+            ..s
+        })
+        .collect())
+}
+
+/// Explain a word one syllable at a time
+///
+/// Romanization and keystrokes are computed locally; Copilot is only asked
+/// for an overall pronunciation note, which is omitted if it is unavailable.
+#[tauri::command]
+pub async fn copilot_explain_breakdown(
+    text: String,
+    request_id: Option<Uuid>,
+) -> CommandResponse<ExplanationBreakdown> {
+    debug!("Copilot explain breakdown: '{}'", text);
+    let text = jamo::normalize(&text);
+
+    let service = copilot::get_service();
+    let syllables = match breakdown_for_keyboard(service, &text).await {
+        Ok(syllables) => syllables,
+        Err(e) => {
+            warn!("Explain breakdown rejected: {}", e);
+            return CommandResponse::err(e.to_string());
+        }
+    };
+
+    let pronunciation_note = if service.can_serve().await {
+        let id = request_id.unwrap_or_else(Uuid::new_v4);
+        let cancel = service.begin_request(id).await;
+        let result = service.pronunciation_note(&text, &cancel).await;
+        service.finish_request(id).await;
+
+        match result {
+            Ok(response) => Some(response.content),
+            Err(e) => {
+                warn!("Pronunciation note failed: {}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    CommandResponse::ok(ExplanationBreakdown {
+        syllables,
+        pronunciation_note,
+    })
+}
+
+/// Ask Copilot for a practice sentence about `topic` for a level
+///
+/// The sentence only uses jamo unlocked at `level`; keystrokes for each
+/// syllable are computed locally.
+#[tauri::command]
+pub async fn copilot_generate_sentence(
+    topic: String,
+    level: u32,
+    request_id: Option<Uuid>,
+) -> CommandResponse<GeneratedSentence> {
+    debug!("Copilot generate sentence: '{}' at level {}", topic, level);
+
+    let service = copilot::get_service();
+
+    if !service.can_serve().await {
+        return CommandResponse::not_running("AI assistant not available");
+    }
+
+    let id = request_id.unwrap_or_else(Uuid::new_v4);
+    let cancel = service.begin_request(id).await;
+    let result = service.generate_sentence(&topic, level, &cancel).await;
+    service.finish_request(id).await;
+
+    match result {
+        Ok(sentence) => match breakdown_for_keyboard(service, &sentence).await {
+            Ok(syllables) => CommandResponse::ok(GeneratedSentence {
+                sentence,
+                syllables,
+            }),
+            Err(e) => CommandResponse::err(e.to_string()),
+        },
+        Err(e) => {
+            error!("Copilot generate sentence failed: {}", e);
+            CommandResponse::copilot_err(&e)
+        }
+    }
+}
+
+/// Start a roleplay of `scenario` (e.g. "ordering coffee") in Korean,
+/// using only the jamo unlocked at `level`
+///
+/// Returns the assistant's opening line and a reply for the learner to
+/// type. Continue with `copilot_scenario_reply`.
+#[tauri::command]
+pub async fn copilot_scenario(
+    scenario: String,
+    level: u32,
+    request_id: Option<Uuid>,
+) -> CommandResponse<ScenarioTurn> {
+    debug!("Copilot scenario: '{}' at level {}", scenario, level);
+
+    let service = copilot::get_service();
+
+    if !service.can_serve().await {
+        return CommandResponse::not_running("AI assistant not available");
+    }
+
+    let id = request_id.unwrap_or_else(Uuid::new_v4);
+    let cancel = service.begin_request(id).await;
+    let result = service.start_scenario(&scenario, level, &cancel).await;
+    service.finish_request(id).await;
+
+    match result {
+        Ok(turn) => CommandResponse::ok(turn),
+        Err(e) => {
+            error!("Copilot scenario failed: {}", e);
+            CommandResponse::copilot_err(&e)
+        }
+    }
+}
+
+/// Continue a roleplay scenario with what the learner typed
+#[tauri::command]
+pub async fn copilot_scenario_reply(
+    conversation_id: Uuid,
+    reply: String,
+    request_id: Option<Uuid>,
+) -> CommandResponse<ScenarioTurn> {
+    debug!("Copilot scenario reply in {}: '{}'", conversation_id, reply);
+
+    let service = copilot::get_service();
+
+    if !service.can_serve().await {
+        return CommandResponse::not_running("AI assistant not available");
+    }
+
+    let id = request_id.unwrap_or_else(Uuid::new_v4);
+    let cancel = service.begin_request(id).await;
+    let result = service
+        .scenario_reply(conversation_id, &reply, &cancel)
+        .await;
+    service.finish_request(id).await;
+
+    match result {
+        Ok(turn) => CommandResponse::ok(turn),
+        Err(e) => {
+            error!("Copilot scenario reply failed: {}", e);
+            CommandResponse::copilot_err(&e)
+        }
+    }
+}
+
+/// Ask the learner which keys type a syllable from `level`
+///
+/// Works without Copilot, using a plain local wording of the question.
+#[tauri::command]
+pub async fn copilot_quiz(level: u32, request_id: Option<Uuid>) -> CommandResponse<QuizQuestion> {
+    debug!("Copilot quiz at level {}", level);
+
+    let service = copilot::get_service();
+    let id = request_id.unwrap_or_else(Uuid::new_v4);
+    let cancel = service.begin_request(id).await;
+    let result = service.quiz(level, &cancel).await;
+    service.finish_request(id).await;
+
+    match result {
+        Ok(question) => CommandResponse::ok(question),
+        Err(e) => {
+            error!("Copilot quiz failed: {}", e);
+            CommandResponse::copilot_err(&e)
+        }
+    }
+}
+
+/// Grade an answer to a quiz question
+///
+/// Key sequences and typed Hangul are graded locally; Copilot only judges
+/// free-form answers.
+#[tauri::command]
+pub async fn copilot_check_answer(
+    question_id: Uuid,
+    answer: String,
+    request_id: Option<Uuid>,
+) -> CommandResponse<QuizResult> {
+    debug!("Copilot check answer for {}: '{}'", question_id, answer);
+
+    let service = copilot::get_service();
+    let id = request_id.unwrap_or_else(Uuid::new_v4);
+    let cancel = service.begin_request(id).await;
+    let result = service.check_answer(question_id, &answer, &cancel).await;
+    service.finish_request(id).await;
+
+    match result {
+        Ok(result) => CommandResponse::ok(result),
+        Err(e) => {
+            error!("Copilot check answer failed: {}", e);
+            CommandResponse::copilot_err(&e)
         }
     }
 }
@@ -211,40 +883,1475 @@ pub async fn copilot_explain(text: String) -> CommandResponse<AssistantResponse>
 pub async fn copilot_analyze_mistake(
     expected: String,
     actual: String,
+    request_id: Option<Uuid>,
 ) -> CommandResponse<AssistantResponse> {
     debug!(
         "Copilot analyze: expected='{}', actual='{}'",
         expected, actual
     );
+    let (expected, actual) = (jamo::normalize(&expected), jamo::normalize(&actual));
 
     let service = copilot::get_service();
 
-    if !service.is_running().await {
-        return CommandResponse::err("AI assistant not available".to_string());
+    if !service.can_serve().await {
+        return CommandResponse::not_running("AI assistant not available");
     }
 
-    match service.analyze_mistake(&expected, &actual).await {
+    let id = request_id.unwrap_or_else(Uuid::new_v4);
+    let cancel = service.begin_request(id).await;
+    let result = service.analyze_mistake(&expected, &actual, &cancel).await;
+    service.finish_request(id).await;
+
+    match result {
         Ok(response) => CommandResponse::ok(response),
         Err(e) => {
             error!("Copilot analyze failed: {}", e);
-            CommandResponse::err(e.to_string())
+            CommandResponse::copilot_err(&e)
         }
     }
 }
 
-/// Shutdown the Copilot service
+/// Cancel an in-flight request. Returns false if it had already finished.
 #[tauri::command]
-pub async fn copilot_shutdown() -> CommandResponse<()> {
-    debug!("Shutting down Copilot service...");
+pub async fn copilot_cancel(request_id: Uuid) -> CommandResponse<bool> {
+    debug!("Cancelling Copilot request {}", request_id);
 
-    match copilot::shutdown().await {
-        Ok(()) => {
-            info!("Copilot service stopped");
-            CommandResponse::ok(())
+    let cancelled = copilot::get_service().cancel_request(request_id).await;
+    CommandResponse::ok(cancelled)
+}
+
+/// Set how long to wait for an answer or explanation (clamped to 5-300 seconds)
+#[tauri::command]
+pub async fn copilot_set_timeout(secs: u64) -> CommandResponse<u64> {
+    debug!("Setting Copilot timeout to {}s", secs);
+
+    let applied = copilot::get_service().set_timeout(secs).await;
+    CommandResponse::ok(applied)
+}
+
+/// Set how long to wait for a hint (clamped to 5-300 seconds)
+///
+/// Kept shorter than the general timeout, since a late hint is no help.
+#[tauri::command]
+pub async fn copilot_set_hint_timeout(secs: u64) -> CommandResponse<u64> {
+    debug!("Setting Copilot hint timeout to {}s", secs);
+
+    let applied = copilot::get_service().set_hint_timeout(secs).await;
+    CommandResponse::ok(applied)
+}
+
+/// Turn mock mode on or off
+///
+/// In mock mode the AI commands return canned, deterministic responses built
+/// from the local tables, so the frontend can be developed and tested
+/// without the Copilot CLI. Can also be enabled at start-up with the
+/// `HANGUL_TYPING_COPILOT_MOCK=1` environment variable.
+#[tauri::command]
+pub async fn copilot_enable_mock(enabled: bool) -> CommandResponse<()> {
+    copilot::get_service().set_mock(enabled);
+    CommandResponse::ok(())
+}
+
+/// Stop the Copilot client after `secs` without requests (0 disables)
+///
+/// The client restarts transparently on the next request.
+#[tauri::command]
+pub async fn copilot_set_idle_timeout(secs: u64) -> CommandResponse<()> {
+    debug!("Setting Copilot idle timeout to {}s", secs);
+
+    copilot::get_service().set_idle_timeout(secs).await;
+    CommandResponse::ok(())
+}
+
+/// Cap how many Copilot requests can be sent per `window_secs`
+///
+/// Protects the learner's quota from runaway request loops; zero for
+/// either value disables the limit.
+#[tauri::command]
+pub async fn copilot_set_rate_limit(requests: u32, window_secs: u64) -> CommandResponse<()> {
+    debug!(
+        "Setting Copilot rate limit to {} per {}s",
+        requests, window_secs
+    );
+
+    copilot::get_service()
+        .set_rate_limit(requests, window_secs)
+        .await;
+    CommandResponse::ok(())
+}
+
+/// Set how many requests may be sent to Copilot at once
+///
+/// Keeps prefetching and other background work from saturating the CLI.
+/// Returns the cap in effect, which is at least one.
+#[tauri::command]
+pub async fn copilot_set_max_concurrency(max: usize) -> CommandResponse<usize> {
+    debug!("Setting Copilot concurrency cap to {}", max);
+
+    let max = copilot::get_service().set_max_concurrency(max).await;
+    CommandResponse::ok(max)
+}
+
+/// Check the tutor's key map against the active layout
+///
+/// Returns one message per open session whose system prompt is out of
+/// date, and per mapping in it the layout disagrees with; empty when every
+/// prompt is accurate.
+#[tauri::command]
+pub async fn copilot_validate_prompt() -> CommandResponse<Vec<String>> {
+    let problems = copilot::get_service().validate_prompt().await;
+    for problem in &problems {
+        warn!("System prompt: {}", problem);
+    }
+    CommandResponse::ok(problems)
+}
+
+/// Choose whether the Copilot session is created in the background on start
+#[tauri::command]
+pub async fn copilot_set_prewarm(enabled: bool) -> CommandResponse<()> {
+    copilot::get_service().set_prewarm(enabled);
+    CommandResponse::ok(())
+}
+
+/// Change the log level while the app is running
+///
+/// Accepts trace, debug, info, warn, error or off, so a user can capture
+/// debug logs of a failure without restarting.
+#[tauri::command]
+pub async fn set_log_level(level: String) -> CommandResponse<String> {
+    match logging::set_level(&level) {
+        Ok(applied) => {
+            info!("Log level set to {}", applied);
+            CommandResponse::ok(applied.to_string())
         }
         Err(e) => {
-            error!("Copilot shutdown failed: {}", e);
+            warn!("Set log level failed: {}", e);
             CommandResponse::err(e.to_string())
         }
     }
 }
+
+/// Select the keyboard layout used for hints and explanations
+#[tauri::command]
+pub async fn set_layout(layout: Layout) -> CommandResponse<Layout> {
+    debug!("Setting keyboard layout to {:?}", layout);
+
+    copilot::get_service().set_layout(layout).await;
+    CommandResponse::ok(layout)
+}
+
+/// Select the physical keyboard, so local hints name the keys printed on it
+#[tauri::command]
+pub async fn set_physical_layout(physical: PhysicalLayout) -> CommandResponse<PhysicalLayout> {
+    debug!("Setting physical keyboard to {:?}", physical);
+
+    copilot::get_service().set_physical_layout(physical).await;
+    CommandResponse::ok(physical)
+}
+
+/// Change the tutor's persona
+///
+/// Pass a `custom` prompt to replace the persona entirely, or a built-in
+/// `preset` ("encouraging", "drill-sergeant", "minimal"). With neither, the
+/// default encouraging persona is restored.
+#[tauri::command]
+pub async fn copilot_set_persona(
+    preset: Option<Persona>,
+    custom: Option<String>,
+) -> CommandResponse<()> {
+    let service = copilot::get_service();
+
+    match custom {
+        Some(prompt) => match service.set_system_prompt(prompt).await {
+            Ok(()) => CommandResponse::ok(()),
+            Err(e) => {
+                warn!("Copilot set persona failed: {}", e);
+                CommandResponse::copilot_err(&e)
+            }
+        },
+        None => {
+            service.set_persona(preset.unwrap_or_default()).await;
+            CommandResponse::ok(())
+        }
+    }
+}
+
+/// Models the assistant can use, and the one currently selected
+#[derive(Debug, Serialize)]
+pub struct ModelList {
+    pub available: Vec<String>,
+    /// `None` means the Copilot CLI default
+    pub current: Option<String>,
+}
+
+/// Change how many explain/hint answers are cached (0 disables caching)
+#[tauri::command]
+pub async fn copilot_set_cache_capacity(capacity: usize) -> CommandResponse<()> {
+    debug!("Setting response cache capacity to {}", capacity);
+
+    copilot::get_service().set_cache_capacity(capacity).await;
+    CommandResponse::ok(())
+}
+
+/// The most recent Copilot failure, for bug reports
+///
+/// `None` if nothing has failed, or a request has succeeded since.
+#[tauri::command]
+pub async fn copilot_last_error() -> CommandResponse<Option<ErrorInfo>> {
+    CommandResponse::ok(copilot::get_service().last_error().await)
+}
+
+/// Features that work in the current environment
+///
+/// Local fallbacks ("local:hint") are always listed; Copilot features
+/// ("copilot:explain") only while the assistant can serve requests, along
+/// with the tools its sessions can call ("tool:hangul_lookup").
+#[tauri::command]
+pub async fn copilot_capabilities() -> CommandResponse<Vec<String>> {
+    CommandResponse::ok(copilot::get_service().capabilities().await)
+}
+
+/// Tokens consumed by Copilot requests since start-up or the last reset
+#[tauri::command]
+pub async fn copilot_usage() -> CommandResponse<UsageStats> {
+    CommandResponse::ok(copilot::get_service().usage().await)
+}
+
+/// Clear the Copilot usage counters
+#[tauri::command]
+pub async fn copilot_reset_usage() -> CommandResponse<()> {
+    debug!("Resetting Copilot usage");
+
+    copilot::get_service().reset_usage().await;
+    CommandResponse::ok(())
+}
+
+/// List the models the Copilot backend offers
+#[tauri::command]
+pub async fn copilot_list_models() -> CommandResponse<ModelList> {
+    let service = copilot::get_service();
+
+    match service.list_models().await {
+        Ok(available) => CommandResponse::ok(ModelList {
+            available,
+            current: service.model().await,
+        }),
+        Err(e) => {
+            error!("Copilot list models failed: {}", e);
+            CommandResponse::copilot_err(&e)
+        }
+    }
+}
+
+/// Switch the model used for new sessions
+#[tauri::command]
+pub async fn copilot_set_model(name: String) -> CommandResponse<String> {
+    debug!("Setting Copilot model to {}", name);
+
+    match copilot::get_service().set_model(&name).await {
+        Ok(()) => CommandResponse::ok(name),
+        Err(e) => {
+            warn!("Copilot set model failed: {}", e);
+            CommandResponse::copilot_err(&e)
+        }
+    }
+}
+
+/// Discard the cached sessions so the assistant starts fresh; open
+/// conversations keep their transcripts
+#[tauri::command]
+pub async fn copilot_reset_session() -> CommandResponse<()> {
+    debug!("Resetting Copilot session...");
+
+    copilot::get_service().reset_session().await;
+    CommandResponse::ok(())
+}
+
+/// Shutdown the Copilot service
+///
+/// New requests are rejected straight away; requests already running get
+/// `grace_secs` (default 5) to finish before the client is stopped.
+#[tauri::command]
+pub async fn copilot_shutdown(grace_secs: Option<u64>) -> CommandResponse<()> {
+    debug!("Shutting down Copilot service...");
+
+    let grace = grace_secs.map_or(copilot::DEFAULT_SHUTDOWN_GRACE, Duration::from_secs);
+    match copilot::shutdown(grace).await {
+        Ok(()) => {
+            info!("Copilot service stopped");
+            CommandResponse::ok(())
+        }
+        Err(e) => {
+            error!("Copilot shutdown failed: {}", e);
+            CommandResponse::copilot_err(&e)
+        }
+    }
+}
+
+/// Grade typed text against the target with a per-jamo diff
+#[tauri::command]
+pub async fn grade(target: String, typed: String) -> CommandResponse<GradeResult> {
+    match grade::grade(&target, &typed) {
+        Ok(result) => CommandResponse::ok(result),
+        Err(e) => CommandResponse::err(e.to_string()),
+    }
+}
+
+/// Recognise old Hangul syllables with archaic jamo such as ㅿ and ㆁ in
+/// `compose_syllable` and `decompose_syllable`
+#[tauri::command]
+pub async fn set_archaic(enabled: bool) -> CommandResponse<bool> {
+    info!(
+        "Archaic jamo {}",
+        if enabled { "enabled" } else { "disabled" }
+    );
+    jamo::set_archaic(enabled);
+    CommandResponse::ok(enabled)
+}
+
+/// Build one syllable from its jamo
+///
+/// Old Hangul syllables come back as conjoining jamo, since they have no
+/// precomposed form, and only once `set_archaic` has enabled them.
+#[tauri::command]
+pub async fn compose_syllable(
+    cho: char,
+    jung: char,
+    jong: Option<char>,
+) -> CommandResponse<String> {
+    match jamo::compose_old(cho, jung, jong) {
+        Some(syllable) => CommandResponse::ok(syllable),
+        None => CommandResponse::err(format!(
+            "{}{}{} don't make a syllable",
+            cho,
+            jung,
+            jong.map(String::from).unwrap_or_default()
+        )),
+    }
+}
+
+/// Split one syllable, old Hangul included once `set_archaic` has enabled
+/// it, into its jamo
+#[tauri::command]
+pub async fn decompose_syllable(syllable: String) -> CommandResponse<Vec<char>> {
+    match jamo::decompose_old(&syllable) {
+        Some((cho, jung, jong)) => {
+            CommandResponse::ok([cho, jung].into_iter().chain(jong).collect())
+        }
+        None => CommandResponse::err(format!("Not a single syllable: {}", syllable)),
+    }
+}
+
+/// Partial-credit score for typed text, 0 to 100, from the jamo edit distance
+#[tauri::command]
+pub async fn score(target: String, typed: String) -> CommandResponse<u8> {
+    CommandResponse::ok(grade::score(&target, &typed))
+}
+
+/// Keys left to type `target` on the active layout, for key highlighting
+///
+/// Computed locally from the layout tables; Copilot is not involved.
+#[tauri::command]
+pub async fn next_keys(target: String, typed: String) -> CommandResponse<NextKeysResult> {
+    let service = copilot::get_service();
+    let layout = service.layout().await;
+    let physical = service.physical_layout().await;
+    CommandResponse::ok(hint::next_keys(layout, physical, &target, &typed))
+}
+
+/// Guess the language of `text` as a BCP-47 tag, for localizing help text
+#[tauri::command]
+pub async fn detect_prompt_language(text: String) -> CommandResponse<String> {
+    CommandResponse::ok(language::detect(&text).to_string())
+}
+
+/// Romanize Hangul text in the requested style
+#[tauri::command]
+pub async fn romanize(text: String, style: Style) -> CommandResponse<String> {
+    CommandResponse::ok(romanize::romanize(&text, style))
+}
+
+/// Romanize what the learner actually typed, half-typed syllables included
+///
+/// Lets mistake feedback say "you typed 'gan' but meant 'gam'".
+#[tauri::command]
+pub async fn romanize_input(typed: String) -> CommandResponse<String> {
+    CommandResponse::ok(romanize::romanize_lenient(&typed, Style::Revised))
+}
+
+/// Hangul read from romaja, with the keys to type it
+#[derive(Debug, Serialize)]
+pub struct RomajaConversion {
+    pub hangul: String,
+    pub keystrokes: Vec<KeyPress>,
+}
+
+/// Convert revised romanization ("hanguk") into Hangul and its keystrokes
+///
+/// Keys are for the active layout, labelled for the learner's keyboard.
+#[tauri::command]
+pub async fn romaja_to_hangul(input: String) -> CommandResponse<RomajaConversion> {
+    let Some(hangul) = romanize::romaja_to_hangul(&input) else {
+        return CommandResponse::err(format!("\"{}\" isn't valid romanized Korean", input));
+    };
+
+    let service = copilot::get_service();
+    let keys = service.layout().await.keystrokes_for_text(&hangul);
+    let keystrokes = service.physical_layout().await.translate_all(&keys);
+    CommandResponse::ok(RomajaConversion { hangul, keystrokes })
+}
+
+/// Generate distinct practice syllables from the jamo introduced up to `level`
+///
+/// Pass a `seed` to get the same targets every time.
+#[tauri::command]
+pub async fn generate_targets(
+    level: u32,
+    count: usize,
+    seed: Option<u64>,
+) -> CommandResponse<Vec<String>> {
+    debug!("Generating {} targets for level {}", count, level);
+
+    CommandResponse::ok(lesson::generate_targets(level, count, seed))
+}
+
+/// Wrong answers for a multiple-choice drill, each one jamo away from
+/// `target` and built from jamo unlocked at `level`
+///
+/// Pass a `seed` to get the same options every time.
+#[tauri::command]
+pub async fn distractors(
+    target: String,
+    level: u32,
+    n: usize,
+    seed: Option<u64>,
+) -> CommandResponse<Vec<String>> {
+    debug!("Generating {} distractors for '{}'", n, target);
+
+    CommandResponse::ok(lesson::distractors(&target, level, n, seed))
+}
+
+/// Load a custom curriculum file so lessons follow a teacher's order
+///
+/// Pass no path to go back to the bundled curriculum. Returns the number of
+/// levels.
+#[tauri::command]
+pub async fn curriculum_load(path: Option<PathBuf>) -> CommandResponse<usize> {
+    let curriculum = match &path {
+        Some(path) => match Curriculum::load(path) {
+            Ok(curriculum) => curriculum,
+            Err(e) => {
+                error!("Failed to load curriculum {}: {}", path.display(), e);
+                return CommandResponse::err(e.to_string());
+            }
+        },
+        None => Curriculum::bundled(),
+    };
+
+    info!("Loaded curriculum with {} levels", curriculum.len());
+    let levels = curriculum.len();
+    curriculum::set_active(curriculum);
+    CommandResponse::ok(levels)
+}
+
+/// Train next-syllable prediction on a teacher's text file
+///
+/// Pass no path to go back to the bundled model. Returns the number of
+/// syllables the model can predict a follower for.
+#[tauri::command]
+pub async fn ngram_load_corpus(path: Option<PathBuf>) -> CommandResponse<usize> {
+    let model = match &path {
+        Some(path) => match BigramModel::load_corpus(path) {
+            Ok(model) => model,
+            Err(e) => {
+                error!("Failed to load corpus {}: {}", path.display(), e);
+                return CommandResponse::err(e.to_string());
+            }
+        },
+        None => BigramModel::bundled(),
+    };
+
+    info!("Loaded bigram model for {} syllables", model.len());
+    let syllables = model.len();
+    ngram::set_active(model);
+    CommandResponse::ok(syllables)
+}
+
+/// Syllables most likely to follow `prev`, for a predictive highlight
+#[tauri::command]
+pub async fn likely_next(prev: char) -> CommandResponse<Vec<char>> {
+    CommandResponse::ok(ngram::likely_next(prev))
+}
+
+/// Check which words of a teacher's list fit a level
+///
+/// Each word reports any jamo the learner hasn't been taught by `level`.
+#[tauri::command]
+pub async fn validate_wordlist(
+    words: Vec<String>,
+    level: u32,
+) -> CommandResponse<Vec<WordValidation>> {
+    debug!("Validating {} words against level {}", words.len(), level);
+
+    let curriculum = curriculum::current();
+    CommandResponse::ok(
+        words
+            .iter()
+            .map(|word| curriculum.validate_word(word, level))
+            .collect(),
+    )
+}
+
+/// Recommend the next syllable to practise from the learner's progress
+#[tauri::command]
+pub async fn recommend_next(progress: Progress) -> CommandResponse<char> {
+    match lesson::recommend_next(&progress) {
+        Some(syllable) => CommandResponse::ok(syllable),
+        None => CommandResponse::err(
+            "Every unlocked jamo is mastered; unlock a new level to continue".to_string(),
+        ),
+    }
+}
+
+/// Dashboard summary of weak jamo, current speed and accuracy, and what to
+/// practise next
+///
+/// Computed locally from `progress` and the session in progress, if any;
+/// nothing leaves the learner's machine.
+#[tauri::command]
+pub async fn analytics_summary(progress: Progress) -> CommandResponse<AnalyticsSummary> {
+    let session = metrics::current_session().lock().await;
+    CommandResponse::ok(analytics::summary(&progress, session.as_ref()))
+}
+
+/// Mastery of each practised jamo as a percentage, for a dashboard
+///
+/// Aggregated from the syllables each jamo appeared in, so weak jamo show
+/// up regardless of which syllables they were practised in.
+#[tauri::command]
+pub async fn mastery_map(progress: Progress) -> CommandResponse<HashMap<char, f32>> {
+    CommandResponse::ok(analytics::mastery_map(&progress))
+}
+
+/// Syllable pairs contrasting `jamo` with the jamo learners confuse it with
+#[tauri::command]
+pub async fn minimal_pairs(jamo: char) -> CommandResponse<Vec<(String, String)>> {
+    debug!("Generating minimal pairs for {}", jamo);
+
+    CommandResponse::ok(lesson::minimal_pairs(jamo))
+}
+
+/// Stroke order of a basic jamo, for animating how it is written
+#[tauri::command]
+pub async fn stroke_order(jamo: char) -> CommandResponse<Vec<Stroke>> {
+    match strokes::stroke_order(jamo) {
+        Some(strokes) => CommandResponse::ok(strokes),
+        None => CommandResponse::err(format!(
+            "No stroke order for '{}'; only the 24 basic jamo are covered",
+            jamo
+        )),
+    }
+}
+
+/// A memory aid for where a jamo is on the 2-Bulsik keyboard
+///
+/// Answers "why is ㅂ on q?" instantly, without asking Copilot.
+#[tauri::command]
+pub async fn jamo_mnemonic(jamo: char) -> CommandResponse<String> {
+    match layout::mnemonic(jamo) {
+        Some(aid) => CommandResponse::ok(aid),
+        None => CommandResponse::err(format!(
+            "No mnemonic for '{}'; compound jamo are typed as their parts",
+            jamo
+        )),
+    }
+}
+
+/// Start a live composer and return its id
+#[tauri::command]
+pub async fn composer_new() -> CommandResponse<Uuid> {
+    let id = Uuid::new_v4();
+    ime::composers()
+        .lock()
+        .await
+        .insert(id, LiveComposer::new());
+    debug!("Started composer {}", id);
+    CommandResponse::ok(id)
+}
+
+/// Feed one key press to a composer
+#[tauri::command]
+pub async fn composer_feed(id: Uuid, key: KeyPress) -> CommandResponse<Composition> {
+    match ime::composers().lock().await.get_mut(&id) {
+        Some(composer) => CommandResponse::ok(composer.step(key)),
+        None => CommandResponse::err(format!("Unknown composer: {}", id)),
+    }
+}
+
+/// Discard whatever a composer is assembling and start over
+#[tauri::command]
+pub async fn composer_reset(id: Uuid) -> CommandResponse<()> {
+    match ime::composers().lock().await.get_mut(&id) {
+        Some(composer) => {
+            *composer = LiveComposer::new();
+            CommandResponse::ok(())
+        }
+        None => CommandResponse::err(format!("Unknown composer: {}", id)),
+    }
+}
+
+/// Free a composer once the UI is done with it
+#[tauri::command]
+pub async fn composer_close(id: Uuid) -> CommandResponse<()> {
+    match ime::composers().lock().await.remove(&id) {
+        Some(_) => {
+            debug!("Closed composer {}", id);
+            CommandResponse::ok(())
+        }
+        None => CommandResponse::err(format!("Unknown composer: {}", id)),
+    }
+}
+
+/// The next key to press for `target`, given what a live composer shows
+///
+/// Understands half-built syllables, so after ㄱ ㅏ for 간 the answer is
+/// the ㄴ key. `None` once the target is typed or the learner has gone off
+/// it. Labelled for the learner's keyboard.
+#[tauri::command]
+pub async fn live_next_key(composer_id: Uuid, target: String) -> CommandResponse<Option<KeyPress>> {
+    let Some(typed) = ime::composers()
+        .lock()
+        .await
+        .get(&composer_id)
+        .map(LiveComposer::text)
+    else {
+        return CommandResponse::err(format!("Unknown composer: {}", composer_id));
+    };
+
+    let target = jamo::normalize(&target);
+    let physical = copilot::get_service().physical_layout().await;
+    let next = hint::live_next_key(&target, &typed).map(|key| physical.translate(key));
+    CommandResponse::ok(next)
+}
+
+/// Time composing and decomposing random syllables, as a performance smoke
+/// test. A developer tool, not shown to learners.
+#[tauri::command]
+pub async fn bench_compose(iterations: usize) -> CommandResponse<BenchResult> {
+    // Runs for as long as it is asked to, so keep it off the async workers
+    let result = match tokio::task::spawn_blocking(move || bench::run(iterations)).await {
+        Ok(result) => result,
+        Err(e) => {
+            error!("Benchmark task failed: {}", e);
+            return CommandResponse::err(e.to_string());
+        }
+    };
+    info!(
+        "Composed {} syllables in {:.1}ms ({:.0}/s, {} failures)",
+        result.iterations, result.elapsed_ms, result.ops_per_sec, result.failures
+    );
+    CommandResponse::ok(result)
+}
+
+/// Start a new typing session, replacing any session in progress
+///
+/// Returns the new attempt's id for `replay`. The replaced session's
+/// unsaved keystrokes are saved to progress, its speed counts towards the
+/// active profile's best WPM if it was long enough, and it is kept among
+/// the recent attempts. The new session is autosaved on a timer as well as
+/// every few keystrokes.
+#[tauri::command]
+pub async fn session_start(app: AppHandle, level: Option<u32>) -> CommandResponse<Uuid> {
+    let level = level.unwrap_or(1);
+    debug!("Starting typing session for level {}", level);
+
+    let session = TypingSession::for_level(level, Instant::now());
+    let id = session.id;
+    {
+        let mut autosave = progress::autosave().lock().await;
+        let finished = metrics::current_session().lock().await.replace(session);
+        if let Some(finished) = finished {
+            if let Err(e) = flush_session(&app, &finished).await {
+                error!("Failed to save finished session: {}", e);
+            }
+            if finished.keystrokes.len() >= MIN_KEYSTROKES
+                && let Err(e) = record_best_wpm(&app, finished.stats().wpm).await
+            {
+                error!("Failed to update best WPM: {}", e);
+            }
+            metrics::attempt_history().lock().await.push(&finished);
+        }
+        autosave.flushed_at(Instant::now());
+    }
+    spawn_autosave_timer(app, id);
+    CommandResponse::ok(id)
+}
+
+/// Autosave session `id` once its keystrokes have waited the autosave
+/// interval, so they're saved even if the learner stops typing. Ends once
+/// another session replaces it.
+fn spawn_autosave_timer(app: AppHandle, id: Uuid) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let wait = progress::autosave()
+                .lock()
+                .await
+                .time_until_due(Instant::now());
+            tokio::time::sleep(wait).await;
+
+            let mut autosave = progress::autosave().lock().await;
+            match metrics::current_session().lock().await.as_ref() {
+                Some(session) if session.id == id => {}
+                _ => return,
+            }
+            if autosave.is_due(Instant::now()) {
+                autosave_session(&app, &mut autosave).await;
+            }
+        }
+    });
+}
+
+/// Every key press of an attempt, timed from its start, for the UI to
+/// animate
+///
+/// Works for the session in progress and recent finished ones. Keys are
+/// labelled for the learner's keyboard.
+#[tauri::command]
+pub async fn replay(attempt_id: Uuid) -> CommandResponse<Vec<ReplayFrame>> {
+    let saved = match metrics::current_session().lock().await.as_ref() {
+        Some(session) if session.id == attempt_id => Some(session.to_saved()),
+        _ => metrics::attempt_history()
+            .lock()
+            .await
+            .get(attempt_id)
+            .cloned(),
+    };
+    let Some(saved) = saved else {
+        return CommandResponse::err(format!("No recent attempt {}", attempt_id));
+    };
+
+    let service = copilot::get_service();
+    let physical = service.physical_layout().await;
+    let frames = metrics::replay(&saved, service.layout().await)
+        .into_iter()
+        .map(|frame| ReplayFrame {
+            key: physical.translate(frame.key),
+            ..frame
+        })
+        .collect();
+    CommandResponse::ok(frames)
+}
+
+/// Record one typed character against the expected character
+///
+/// Emits a `progress` event for each milestone the keystroke reaches
+/// (level-up, unlocked character, accuracy threshold). A level is only
+/// unlocked, and saved straight away, once its jamo have been practised as
+/// [`progress_try_advance`] requires. Newly earned achievements are saved and
+/// emitted as `achievement` events. Typed characters are autosaved to
+/// progress every few keystrokes, so a crash loses little.
+#[tauri::command]
+pub async fn session_record(
+    app: AppHandle,
+    expected: char,
+    actual: char,
+) -> CommandResponse<SessionStats> {
+    let (stats, events, snapshot) = {
+        let mut session = metrics::current_session().lock().await;
+        let Some(session) = session.as_mut() else {
+            return CommandResponse::err("No typing session in progress".to_string());
+        };
+        session.record(expected, actual);
+        (session.stats(), session.check_milestones(), session.clone())
+    };
+
+    for event in events {
+        if let Err(e) = app.emit(EVENT_PROGRESS, event) {
+            warn!("Failed to emit {}: {}", EVENT_PROGRESS, e);
+        }
+    }
+
+    // Progress stays in memory between autosaves; only a level-up or a new
+    // achievement is saved straight away
+    match progress_path(&app).await {
+        Ok(path) => {
+            let mut store = progress::store().lock().await;
+            let result = match store.get(&path) {
+                Ok(progress) => {
+                    let level =
+                        progress.level_up_after(&snapshot.milestones, &stats, snapshot.unsaved());
+                    let earned = award_achievements(progress, &snapshot);
+                    if level.is_none() && earned.is_empty() {
+                        Ok((level, earned))
+                    } else {
+                        store.save().map(|()| (level, earned))
+                    }
+                }
+                Err(e) => Err(e),
+            };
+            drop(store);
+            match result {
+                Ok((level, earned)) => {
+                    if let Some(level) = level {
+                        level_up(&app, &snapshot, level).await;
+                    }
+                    emit_achievements(&app, earned);
+                }
+                Err(e) => error!("Failed to update progress: {}", e),
+            }
+        }
+        Err(e) => warn!("Skipping progress update: {}", e),
+    }
+
+    let mut autosave = progress::autosave().lock().await;
+    if autosave.record_at(Instant::now()) {
+        autosave_session(&app, &mut autosave).await;
+    }
+    drop(autosave);
+
+    CommandResponse::ok(stats)
+}
+
+/// Count `session`'s keystrokes that haven't been saved yet in `progress`
+fn add_unsaved_keystrokes(progress: &mut Progress, session: &TypingSession) {
+    for keystroke in session.unsaved() {
+        progress.record_typed(keystroke.expected, keystroke.is_correct());
+    }
+}
+
+/// Save a session's unsaved keystrokes to progress
+async fn flush_session(app: &AppHandle, session: &TypingSession) -> Result<(), String> {
+    if session.unsaved().is_empty() {
+        return Ok(());
+    }
+    let path = progress_path(app).await?;
+    let mut store = progress::store().lock().await;
+    let progress = store.get(&path).map_err(|e| e.to_string())?;
+    add_unsaved_keystrokes(progress, session);
+    store.save().map_err(|e| e.to_string())?;
+    debug!("Saved {} keystrokes", session.unsaved().len());
+    Ok(())
+}
+
+/// Save the current session's unsaved keystrokes.
+///
+/// Every autosave goes through the held `autosave` lock, so the keystroke
+/// and timer triggers can't both save the same keystrokes. A failed save
+/// is retried at the next trigger, since the keystrokes stay unsaved.
+async fn autosave_session(app: &AppHandle, autosave: &mut Autosave) {
+    let Some(snapshot) = metrics::current_session().lock().await.clone() else {
+        return;
+    };
+    match flush_session(app, &snapshot).await {
+        Ok(()) => mark_session_saved(&snapshot).await,
+        Err(e) => error!("Failed to autosave progress: {}", e),
+    }
+    autosave.flushed_at(Instant::now());
+}
+
+/// Record that `snapshot`'s keystrokes were saved, unless a new session
+/// has started since it was taken
+async fn mark_session_saved(snapshot: &TypingSession) {
+    if let Some(session) = metrics::current_session().lock().await.as_mut()
+        && session.started == snapshot.started
+    {
+        session.mark_saved(snapshot.keystrokes.len());
+    }
+}
+
+/// Turn mid-session autosave on or off
+///
+/// Typed characters are flushed to saved progress every `keystrokes`
+/// keystrokes (default 20) or `interval_secs` seconds (default 30),
+/// whichever comes first.
+#[tauri::command]
+pub async fn progress_set_autosave(
+    enabled: bool,
+    interval_secs: Option<u64>,
+    keystrokes: Option<u32>,
+) -> CommandResponse<()> {
+    let interval = interval_secs.map_or(progress::DEFAULT_AUTOSAVE_INTERVAL, Duration::from_secs);
+    let keystrokes = keystrokes.unwrap_or(progress::DEFAULT_AUTOSAVE_KEYSTROKES);
+    debug!(
+        "Autosave enabled={} every {} keystrokes or {:?}",
+        enabled, keystrokes, interval
+    );
+
+    progress::autosave()
+        .lock()
+        .await
+        .configure(enabled, keystrokes, interval);
+    CommandResponse::ok(())
+}
+
+/// Add newly earned achievements to `progress`, returning them
+fn award_achievements(
+    progress: &mut Progress,
+    session: &TypingSession,
+) -> Vec<AchievementUnlocked> {
+    let earned = achievements::evaluate(progress, session);
+    progress.achievements.extend(&earned);
+    earned.into_iter().map(AchievementUnlocked::from).collect()
+}
+
+/// Tell the frontend about each newly earned achievement
+fn emit_achievements(app: &AppHandle, earned: Vec<AchievementUnlocked>) {
+    for unlocked in earned {
+        info!("Achievement unlocked: {}", unlocked.title);
+        if let Err(e) = app.emit(EVENT_ACHIEVEMENT, unlocked) {
+            warn!("Failed to emit {}: {}", EVENT_ACHIEVEMENT, e);
+        }
+    }
+}
+
+/// Unlock the next level if the learner has mastered the current one
+///
+/// `recent_accuracy` must reach `threshold` (default 0.9) and each jamo the
+/// current level introduced must have been practised a few times. On
+/// success the level is saved and `progress` events are emitted for the
+/// level-up and each newly unlocked character.
+#[tauri::command]
+pub async fn progress_try_advance(
+    app: AppHandle,
+    recent_accuracy: f32,
+    threshold: Option<f32>,
+) -> CommandResponse<bool> {
+    let threshold = threshold.unwrap_or(progress::DEFAULT_ADVANCE_THRESHOLD);
+    let path = match progress_path(&app).await {
+        Ok(path) => path,
+        Err(e) => return CommandResponse::err(e),
+    };
+
+    let result = {
+        let mut store = progress::store().lock().await;
+        match store.get(&path) {
+            Ok(progress) => {
+                if progress.try_advance_level(recent_accuracy, threshold) {
+                    let level = progress.level();
+                    store.save().map(|()| Some(level))
+                } else {
+                    Ok(None)
+                }
+            }
+            Err(e) => Err(e),
+        }
+    };
+
+    match result {
+        Ok(Some(level)) => {
+            info!("Advanced to level {}", level);
+            for event in progress::level_up_events(level) {
+                if let Err(e) = app.emit(EVENT_PROGRESS, event) {
+                    warn!("Failed to emit {}: {}", EVENT_PROGRESS, e);
+                }
+            }
+            CommandResponse::ok(true)
+        }
+        Ok(None) => CommandResponse::ok(false),
+        Err(e) => {
+            error!("Failed to advance level: {}", e);
+            CommandResponse::err(e.to_string())
+        }
+    }
+}
+
+/// Announce a level `snapshot`'s session unlocked, and stop that session
+/// from unlocking another
+async fn level_up(app: &AppHandle, snapshot: &TypingSession, level: u32) {
+    info!("Advanced to level {}", level);
+    if let Some(session) = metrics::current_session().lock().await.as_mut()
+        && session.started == snapshot.started
+    {
+        session.milestones.record_level_up();
+    }
+    for event in progress::level_up_events(level) {
+        if let Err(e) = app.emit(EVENT_PROGRESS, event) {
+            warn!("Failed to emit {}: {}", EVENT_PROGRESS, e);
+        }
+    }
+}
+
+/// Get live statistics for the current typing session
+#[tauri::command]
+pub async fn session_stats() -> CommandResponse<SessionStats> {
+    match metrics::current_session().lock().await.as_ref() {
+        Some(session) => CommandResponse::ok(session.stats()),
+        None => CommandResponse::err("No typing session in progress".to_string()),
+    }
+}
+
+/// Save the current session so it can be compared with later ones
+#[tauri::command]
+pub async fn session_snapshot() -> CommandResponse<SavedSession> {
+    match metrics::current_session().lock().await.as_ref() {
+        Some(session) => CommandResponse::ok(session.to_saved()),
+        None => CommandResponse::err("No typing session in progress".to_string()),
+    }
+}
+
+/// How many milliseconds typing `target` on the active layout should take
+/// at `target_wpm`, for a pacing bar or countdown
+#[tauri::command]
+pub async fn expected_duration(target: String, target_wpm: f32) -> CommandResponse<u64> {
+    let layout = copilot::get_service().layout().await;
+    let duration = metrics::expected_duration(&target, layout, target_wpm);
+    CommandResponse::ok(duration.as_millis().min(u64::MAX as u128) as u64)
+}
+
+/// Compare a saved session with an earlier one, for a "you improved!" screen
+#[tauri::command]
+pub async fn session_compare(
+    before: SavedSession,
+    after: SavedSession,
+) -> CommandResponse<SessionDelta> {
+    let started = Instant::now();
+    let before = TypingSession::from_saved(&before, started);
+    let after = TypingSession::from_saved(&after, started);
+    CommandResponse::ok(metrics::compare(&before, &after))
+}
+
+/// Pause the current session's clock, e.g. when the learner steps away
+///
+/// Pausing an already paused session does nothing.
+#[tauri::command]
+pub async fn session_pause() -> CommandResponse<()> {
+    match metrics::current_session().lock().await.as_mut() {
+        Some(session) => {
+            debug!("Pausing typing session");
+            session.pause();
+            CommandResponse::ok(())
+        }
+        None => CommandResponse::err("No typing session in progress".to_string()),
+    }
+}
+
+/// Restart the current session's clock after a pause
+///
+/// Resuming a session that isn't paused does nothing.
+#[tauri::command]
+pub async fn session_resume() -> CommandResponse<()> {
+    match metrics::current_session().lock().await.as_mut() {
+        Some(session) => {
+            debug!("Resuming typing session");
+            session.resume();
+            CommandResponse::ok(())
+        }
+        None => CommandResponse::err("No typing session in progress".to_string()),
+    }
+}
+
+/// Presses and errors for one key of the heat map
+#[derive(Debug, Serialize)]
+pub struct KeyHeat {
+    pub key: KeyPress,
+    #[serde(flatten)]
+    pub stats: KeyStats,
+}
+
+/// A key-to-key movement and how long it takes on average
+#[derive(Debug, Serialize)]
+pub struct KeyTransition {
+    pub from: KeyPress,
+    pub to: KeyPress,
+    pub mean_ms: u64,
+}
+
+/// The slowest key-to-key transitions in the current session, slowest first
+///
+/// Points at awkward finger movements, such as reaching for ㅖ. Keys are
+/// labelled for the learner's physical keyboard.
+#[tauri::command]
+pub async fn session_slow_transitions(top_n: usize) -> CommandResponse<Vec<KeyTransition>> {
+    let service = copilot::get_service();
+    let layout = service.layout().await;
+    let physical = service.physical_layout().await;
+
+    let slow = match metrics::current_session().lock().await.as_ref() {
+        Some(session) => metrics::slow_transitions(session, layout, top_n),
+        None => return CommandResponse::err("No typing session in progress".to_string()),
+    };
+
+    CommandResponse::ok(
+        slow.into_iter()
+            .map(|(from, to, mean)| KeyTransition {
+                from: physical.translate(from),
+                to: physical.translate(to),
+                mean_ms: mean.as_millis() as u64,
+            })
+            .collect(),
+    )
+}
+
+/// Per-key presses and errors for the current session, for a keyboard overlay
+///
+/// Keys are labelled for the learner's physical keyboard and sorted so the
+/// list is stable between calls.
+#[tauri::command]
+pub async fn session_key_heatmap() -> CommandResponse<Vec<KeyHeat>> {
+    let service = copilot::get_service();
+    let layout = service.layout().await;
+    let physical = service.physical_layout().await;
+
+    let heatmap = match metrics::current_session().lock().await.as_ref() {
+        Some(session) => metrics::key_heatmap(session, layout),
+        None => return CommandResponse::err("No typing session in progress".to_string()),
+    };
+
+    let mut keys: Vec<KeyHeat> = heatmap
+        .into_iter()
+        .map(|(key, stats)| KeyHeat {
+            key: physical.translate(key),
+            stats,
+        })
+        .collect();
+    keys.sort_by_key(|k| (k.key.key, k.key.shift));
+    CommandResponse::ok(keys)
+}
+
+/// Key presses per finger in the current session, to help balance hands
+#[tauri::command]
+pub async fn session_finger_load() -> CommandResponse<HashMap<Finger, u32>> {
+    let layout = copilot::get_service().layout().await;
+    match metrics::current_session().lock().await.as_ref() {
+        Some(session) => CommandResponse::ok(metrics::finger_load(session, layout)),
+        None => CommandResponse::err("No typing session in progress".to_string()),
+    }
+}
+
+/// What each key types on the active layout, for a printable cheat sheet
+///
+/// Keys are labelled for the learner's physical keyboard.
+#[tauri::command]
+pub async fn layout_cheatsheet() -> CommandResponse<Vec<KeyMapping>> {
+    let service = copilot::get_service();
+    let physical = service.physical_layout().await;
+    let sheet = service
+        .layout()
+        .await
+        .cheatsheet()
+        .into_iter()
+        .map(|mapping| KeyMapping {
+            key: physical.translate(KeyPress::plain(mapping.key)).key,
+            ..mapping
+        })
+        .collect();
+    CommandResponse::ok(sheet)
+}
+
+/// Whether the learner seems to be typing with the OS keyboard on English
+///
+/// Lets the UI suggest switching to Korean when "rkstk" arrives for 간사.
+#[tauri::command]
+pub async fn looks_like_wrong_layout(typed: String, target: String) -> CommandResponse<bool> {
+    let target = jamo::normalize(&target);
+    let physical = copilot::get_service().physical_layout().await;
+    CommandResponse::ok(layout::looks_like_wrong_layout(&typed, &target, physical))
+}
+
+/// The app data directory
+fn data_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map_err(|e| format!("No app data directory: {}", e))
+}
+
+/// Run `f` on the profile list kept in memory, reading it from disk the
+/// first time
+async fn with_profiles<T>(
+    app: &AppHandle,
+    f: impl FnOnce(&mut Profiles) -> Result<T, ProfileError>,
+) -> Result<T, String> {
+    let path = data_dir(app)?.join(PROFILES_FILE);
+    let mut loaded = profiles::loaded().lock().await;
+    if loaded.is_none() {
+        *loaded = Some(Profiles::load(&path).map_err(|e| e.to_string())?);
+    }
+    f(loaded.as_mut().expect("loaded above")).map_err(|e| e.to_string())
+}
+
+/// Location of the active profile's saved progress file
+async fn progress_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = data_dir(app)?;
+    with_profiles(app, |profiles| {
+        if profiles.active.is_some() && profiles.active().is_none() {
+            warn!("Active profile is missing; using shared progress");
+        }
+        Ok(profiles.progress_path(&dir))
+    })
+    .await
+}
+
+/// Apply `change` to the profile list and save it, under the progress
+/// store lock so the active profile can't change mid-save. The list in
+/// memory is left alone if either fails.
+async fn update_profiles<T>(
+    app: &AppHandle,
+    change: impl FnOnce(&mut Profiles) -> Result<T, ProfileError>,
+) -> Result<T, String> {
+    let path = data_dir(app)?.join(PROFILES_FILE);
+    let _guard = progress::store().lock().await;
+    with_profiles(app, |profiles| {
+        let mut updated = profiles.clone();
+        let result = change(&mut updated)?;
+        updated.save(&path)?;
+        *profiles = updated;
+        Ok(result)
+    })
+    .await
+}
+
+/// Add a learner profile; names must be unique, ignoring case
+#[tauri::command]
+pub async fn profile_create(app: AppHandle, name: String) -> CommandResponse<()> {
+    match update_profiles(&app, |profiles| profiles.create(&name).map(|_| ())).await {
+        Ok(()) => {
+            info!("Created profile \"{}\"", name.trim());
+            CommandResponse::ok(())
+        }
+        Err(e) => CommandResponse::err(e),
+    }
+}
+
+/// Switch to another learner's profile
+///
+/// Progress, reviews and achievements are read from and saved to that
+/// profile from now on.
+#[tauri::command]
+pub async fn profile_switch(app: AppHandle, name: String) -> CommandResponse<()> {
+    match update_profiles(&app, |profiles| profiles.switch(&name)).await {
+        Ok(()) => {
+            info!("Switched to profile \"{}\"", name.trim());
+            CommandResponse::ok(())
+        }
+        Err(e) => CommandResponse::err(e),
+    }
+}
+
+/// Every profile with its best WPM, fastest first
+#[tauri::command]
+pub async fn profile_list(app: AppHandle) -> CommandResponse<Vec<ProfileSummary>> {
+    match with_profiles(&app, |profiles| Ok(profiles.leaderboard())).await {
+        Ok(leaderboard) => CommandResponse::ok(leaderboard),
+        Err(e) => CommandResponse::err(e),
+    }
+}
+
+/// Best WPM of the active profile, or `None` before it has a long enough
+/// session
+#[tauri::command]
+pub async fn profile_best_wpm(app: AppHandle) -> CommandResponse<Option<f32>> {
+    match with_profiles(&app, |profiles| Ok(profiles.active().map(|p| p.best_wpm))).await {
+        Ok(Some(best_wpm)) => CommandResponse::ok(best_wpm),
+        Ok(None) => CommandResponse::err("No active profile".to_string()),
+        Err(e) => CommandResponse::err(e),
+    }
+}
+
+/// Count a finished session's speed towards the active profile's best
+/// WPM, saving the profile list only when it is a new best
+async fn record_best_wpm(app: &AppHandle, wpm: f32) -> Result<(), String> {
+    let path = data_dir(app)?.join(PROFILES_FILE);
+    with_profiles(app, |profiles| {
+        if profiles.record_wpm(wpm) {
+            debug!("New best WPM: {:.1}", wpm);
+            profiles.save(&path)?;
+        }
+        Ok(())
+    })
+    .await
+}
+
+/// Load the learner's saved progress, or defaults if nothing is saved
+#[tauri::command]
+pub async fn progress_load(app: AppHandle) -> CommandResponse<Progress> {
+    let path = match progress_path(&app).await {
+        Ok(path) => path,
+        Err(e) => return CommandResponse::err(e),
+    };
+
+    match progress::store().lock().await.get(&path) {
+        Ok(progress) => CommandResponse::ok(progress.clone()),
+        Err(e) => {
+            error!("Failed to load progress: {}", e);
+            CommandResponse::err(e.to_string())
+        }
+    }
+}
+
+/// Erase all saved progress, review schedules and achievements
+///
+/// `confirm` must be true, so a stray call can't wipe a learner's history.
+/// Keystrokes already typed in the current session are not saved back
+/// afterwards. Emits a `progress-reset` event once the progress is gone.
+#[tauri::command]
+pub async fn progress_reset(app: AppHandle, confirm: bool) -> CommandResponse<()> {
+    if !confirm {
+        return CommandResponse::err("Pass confirm=true to erase all progress".to_string());
+    }
+    let path = match progress_path(&app).await {
+        Ok(path) => path,
+        Err(e) => return CommandResponse::err(e),
+    };
+
+    {
+        let mut store = progress::store().lock().await;
+        store.clear();
+        if let Err(e) = Progress::reset(&path) {
+            error!("Failed to reset progress: {}", e);
+            return CommandResponse::err(e.to_string());
+        }
+    }
+    {
+        let mut autosave = progress::autosave().lock().await;
+        if let Some(session) = metrics::current_session().lock().await.as_mut() {
+            session.mark_saved(session.keystrokes.len());
+        }
+        autosave.flushed_at(Instant::now());
+    }
+    info!("Progress reset");
+
+    if let Err(e) = app.emit(EVENT_PROGRESS_RESET, ()) {
+        warn!("Failed to emit {}: {}", EVENT_PROGRESS_RESET, e);
+    }
+    CommandResponse::ok(())
+}
+
+/// Characters due for spaced-repetition review, most overdue first
+#[tauri::command]
+pub async fn srs_due(app: AppHandle) -> CommandResponse<Vec<char>> {
+    let path = match progress_path(&app).await {
+        Ok(path) => path,
+        Err(e) => return CommandResponse::err(e),
+    };
+
+    match progress::store().lock().await.get(&path) {
+        Ok(progress) => CommandResponse::ok(progress.srs.due_characters(Utc::now())),
+        Err(e) => {
+            error!("Failed to load progress: {}", e);
+            CommandResponse::err(e.to_string())
+        }
+    }
+}
+
+/// Export flashcards for Anki as tab-separated rows
+///
+/// Each row is a character, its romanization, its keys on the learner's
+/// keyboard and a memory aid, ready for Anki's basic importer.
+#[tauri::command]
+pub async fn export_anki_tsv(chars: Vec<char>) -> CommandResponse<String> {
+    debug!("Exporting {} characters for Anki", chars.len());
+
+    let service = copilot::get_service();
+    let layout = service.layout().await;
+    let physical = service.physical_layout().await;
+    CommandResponse::ok(anki::to_tsv(&chars, layout, physical))
+}
+
+/// Export saved per-character results as CSV, for spreadsheets or a teacher
+#[tauri::command]
+pub async fn export_progress_csv(app: AppHandle) -> CommandResponse<String> {
+    let path = match progress_path(&app).await {
+        Ok(path) => path,
+        Err(e) => return CommandResponse::err(e),
+    };
+
+    match progress::store().lock().await.get(&path) {
+        Ok(progress) => CommandResponse::ok(progress.to_csv()),
+        Err(e) => {
+            error!("Failed to load progress: {}", e);
+            CommandResponse::err(e.to_string())
+        }
+    }
+}
+
+/// Record a review of one character and reschedule it
+#[tauri::command]
+pub async fn srs_record(app: AppHandle, character: char, correct: bool) -> CommandResponse<()> {
+    debug!("SRS review: '{}' correct={}", character, correct);
+
+    let path = match progress_path(&app).await {
+        Ok(path) => path,
+        Err(e) => return CommandResponse::err(e),
+    };
+
+    // Snapshot the session so its lock isn't held during file IO
+    let session = metrics::current_session()
+        .lock()
+        .await
+        .clone()
+        .unwrap_or_default();
+
+    let mut store = progress::store().lock().await;
+    let result = match store.get(&path) {
+        Ok(progress) => {
+            progress.record_review(character, correct, Utc::now());
+            let earned = award_achievements(progress, &session);
+            store.save().map(|()| earned)
+        }
+        Err(e) => Err(e),
+    };
+    drop(store);
+
+    match result {
+        Ok(earned) => {
+            emit_achievements(&app, earned);
+            CommandResponse::ok(())
+        }
+        Err(e) => {
+            error!("Failed to record review: {}", e);
+            CommandResponse::err(e.to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn copilot_errors_carry_their_code() {
+        let response = CommandResponse::<()>::copilot_err(&CopilotError::CliNotFound);
+        assert!(!response.success);
+        assert_eq!(response.error_code.as_deref(), Some("cli_not_found"));
+        assert_eq!(
+            response.error.as_deref(),
+            Some(CopilotError::CliNotFound.to_string().as_str())
+        );
+
+        assert_eq!(
+            CommandResponse::<()>::err("oops".to_string()).error_code,
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn archaic_syllables_need_the_flag() {
+        let old = "\u{1107}\u{1161}\u{11F0}";
+        assert!(!compose_syllable('ㅂ', 'ㅏ', Some('ㆁ')).await.success);
+        assert_eq!(
+            compose_syllable('ㅂ', 'ㅏ', Some('ㅇ'))
+                .await
+                .data
+                .as_deref(),
+            Some("방")
+        );
+
+        set_archaic(true).await;
+        let composed = compose_syllable('ㅂ', 'ㅏ', Some('ㆁ')).await;
+        let decomposed = decompose_syllable(old.to_string()).await;
+        set_archaic(false).await;
+
+        assert_eq!(composed.data.as_deref(), Some(old));
+        assert_eq!(decomposed.data, Some(vec!['ㅂ', 'ㅏ', 'ㆁ']));
+        assert!(!decompose_syllable(old.to_string()).await.success);
+    }
+
+    #[tokio::test]
+    async fn closed_composers_are_forgotten() {
+        let id = composer_new().await.data.unwrap();
+
+        assert!(composer_close(id).await.success);
+        assert!(!composer_close(id).await.success);
+        assert!(!composer_reset(id).await.success);
+    }
+}
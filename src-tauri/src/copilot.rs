@@ -1,25 +1,138 @@
 //! Copilot SDK integration for AI-powered learning assistance.
 //!
 //! This module provides a managed Copilot client with session pooling
-//! and Hangul-specific tools for the typing trainer.
+//! and Hangul-specific prompts for the typing trainer. Each session gets a
+//! `hangul_lookup` tool backed by the local layout and romanization tables,
+//! so the model can ask for a syllable's keys instead of guessing them.
 //!
 //! The feature is conditionally enabled based on whether GitHub Copilot CLI
 //! is installed and authenticated on the user's machine.
 
+use crate::cache::LruCache;
+use crate::explain;
+use crate::hint;
+use crate::jamo;
+use crate::keycheck;
+use crate::layout::{KeyPress, Layout, PhysicalLayout, Position};
+use crate::lesson::{self, UnlockedJamo};
+use crate::quiz::{self, QuizQuestion, QuizResult};
+use crate::rate_limit::TokenBucket;
+use crate::romanize;
+use chrono::{DateTime, Utc};
 use copilot_sdk::{
-    Client, SessionConfig, SessionEventData, SystemMessageConfig, SystemMessageMode,
+    Client, Session, SessionConfig, SessionEvent, SessionEventData, SystemMessageConfig,
+    SystemMessageMode, Tool, ToolHandler, ToolResultObject,
 };
 use once_cell::sync::OnceCell;
 use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::pin::Pin;
 use std::process::Command;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
 use thiserror::Error;
-use tokio::sync::{Mutex, RwLock};
+use tokio::sync::{Mutex, Notify, OwnedSemaphorePermit, RwLock, Semaphore, broadcast};
+use tokio::task::JoinSet;
+use tokio_util::sync::CancellationToken;
 use tracing::{debug, error, info, warn};
+use uuid::Uuid;
 
 /// Global Copilot service instance
 static COPILOT_SERVICE: OnceCell<CopilotService> = OnceCell::new();
 
+/// Default time to wait for an answer or explanation before giving up
+pub const DEFAULT_TIMEOUT_SECS: u64 = 60;
+/// Default time to wait for a hint; a late hint is no help mid-word
+pub const DEFAULT_HINT_TIMEOUT_SECS: u64 = 15;
+/// Shortest allowed response timeout
+pub const MIN_TIMEOUT_SECS: u64 = 5;
+/// Longest allowed response timeout
+pub const MAX_TIMEOUT_SECS: u64 = 300;
+/// How long a health-check ping may take before it counts as failed
+const PING_TIMEOUT: Duration = Duration::from_secs(10);
+/// Trivial prompt used to check the backend is responsive
+const PING_PROMPT: &str = "Reply with just OK.";
+/// Default time shutdown waits for in-flight requests to finish
+pub const DEFAULT_SHUTDOWN_GRACE: Duration = Duration::from_secs(5);
+/// Environment variable that starts the service in mock mode when set to
+/// "1" or "true"
+pub const MOCK_ENV_VAR: &str = "HANGUL_TYPING_COPILOT_MOCK";
+/// Tool name reported for canned mock-mode responses
+pub const MOCK_TOOL: &str = "mock";
+/// Seed for the practice text made up in mock mode, so it is the same
+/// on every run
+const MOCK_SEED: u64 = 0;
+/// Line separating entries in a batch explain reply
+const BATCH_DELIMITER: &str = "---";
+/// Default number of explain/hint answers remembered
+pub const DEFAULT_CACHE_CAPACITY: usize = 128;
+/// Tool name reported for answers served from the response cache
+pub const CACHE_TOOL: &str = "cache";
+/// Session tool the model calls for a syllable's keys and romanization
+pub const LOOKUP_TOOL: &str = "hangul_lookup";
+/// Average characters per token for non-Hangul text, for usage estimates
+const CHARS_PER_TOKEN: u64 = 4;
+/// Longest custom system prompt accepted, in characters
+pub const MAX_SYSTEM_PROMPT_CHARS: usize = 8_000;
+/// Attempts made for a request that keeps failing transiently
+const MAX_ATTEMPTS: u32 = 3;
+/// Delay before the first retry; doubles on each later retry
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(250);
+/// Default time a conversation may sit idle before its session is freed
+pub const DEFAULT_CONVERSATION_TTL_SECS: u64 = 30 * 60;
+/// Shortest allowed conversation TTL, so a stray 0 can't evict every
+/// conversation on the next request
+pub const MIN_CONVERSATION_TTL_SECS: u64 = 60;
+/// Default time without requests before the client is stopped
+pub const DEFAULT_IDLE_TIMEOUT_SECS: u64 = 15 * 60;
+/// Longest the idle watchdog sleeps while a request is in flight
+const IDLE_RECHECK_INTERVAL: Duration = Duration::from_secs(1);
+/// Default number of requests allowed per rate-limit window
+pub const DEFAULT_RATE_LIMIT_REQUESTS: u32 = 10;
+/// Reason given when the CLI process dies mid-response
+const CLIENT_TERMINATED: &str = "client terminated";
+/// Default rate-limit window
+pub const DEFAULT_RATE_LIMIT_WINDOW_SECS: u64 = 10;
+/// Requests sent to the CLI at once by default
+pub const DEFAULT_MAX_CONCURRENCY: usize = 2;
+/// Features that work without Copilot, always available
+pub const LOCAL_CAPABILITIES: [&str; 5] = [
+    "local:hint",
+    "local:explain",
+    "local:romanize",
+    "local:quiz_grading",
+    "local:next_keys",
+];
+/// Features that need a running Copilot client (or mock mode)
+pub const COPILOT_CAPABILITIES: [&str; 5] = [
+    "copilot:chat",
+    "copilot:hint",
+    "copilot:explain",
+    "copilot:quiz",
+    "copilot:scenario",
+];
+/// Quiz questions remembered for grading; older ones are forgotten
+const QUIZ_CAPACITY: usize = 64;
+/// Most upcoming hints prefetched at once, leaving rate-limit headroom
+/// for the learner's own requests
+pub const PREFETCH_LIMIT: usize = 5;
+/// Transcript entries kept per conversation; the oldest are dropped first
+pub const TRANSCRIPT_CAPACITY: usize = 200;
+/// Attempts at a practice sentence that only uses unlocked jamo
+const SENTENCE_ATTEMPTS: u32 = 2;
+
+/// What a call to [`CopilotService::start`] did
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StartOutcome {
+    /// The client was started by this call
+    Started,
+    /// The client was already running, so nothing was done
+    AlreadyRunning,
+}
+
 /// Errors that can occur during Copilot operations
 #[derive(Debug, Error)]
 pub enum CopilotError {
@@ -41,6 +154,114 @@ pub enum CopilotError {
     SendFailed(String),
     #[error("Session timeout")]
     Timeout,
+    #[error("Request cancelled")]
+    Cancelled,
+    #[error("Unknown or expired conversation: {0}")]
+    UnknownConversation(Uuid),
+    #[error("Unknown or expired quiz question: {0}")]
+    UnknownQuestion(Uuid),
+    #[error("AI assistant is shutting down")]
+    ShuttingDown,
+    #[error("Invalid system prompt: {0}")]
+    InvalidPrompt(String),
+    #[error("Unknown model: {0}")]
+    UnknownModel(String),
+    #[error("Failed to list models: {0}")]
+    ListModelsFailed(String),
+    #[error("Generated sentence was not usable: {0}")]
+    InvalidSentence(String),
+    #[error("Too many AI requests, try again in {:.1}s", retry_after.as_secs_f32())]
+    RateLimited { retry_after: Duration },
+}
+
+impl CopilotError {
+    /// Short machine-readable name for the kind of failure
+    pub fn category(&self) -> &'static str {
+        match self {
+            Self::NotInitialized => "not_initialized",
+            Self::CliNotFound => "cli_not_found",
+            Self::NotAuthenticated => "not_authenticated",
+            Self::StartFailed(_) => "start_failed",
+            Self::SessionFailed(_) => "session_failed",
+            Self::SendFailed(_) => "send_failed",
+            Self::Timeout => "timeout",
+            Self::Cancelled => "cancelled",
+            Self::UnknownConversation(_) => "unknown_conversation",
+            Self::UnknownQuestion(_) => "unknown_question",
+            Self::ShuttingDown => "shutting_down",
+            Self::InvalidPrompt(_) => "invalid_prompt",
+            Self::UnknownModel(_) => "unknown_model",
+            Self::ListModelsFailed(_) => "list_models_failed",
+            Self::InvalidSentence(_) => "invalid_sentence",
+            Self::RateLimited { .. } => "rate_limited",
+        }
+    }
+
+    /// Whether the failure is likely to go away if the request is retried
+    pub fn is_transient(&self) -> bool {
+        matches!(self, Self::SendFailed(_) | Self::SessionFailed(_))
+    }
+}
+
+/// Built-in tutor personas
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Persona {
+    /// Friendly and patient, the default
+    #[default]
+    Encouraging,
+    /// Strict and direct, for learners who want to be pushed
+    DrillSergeant,
+    /// Just the keys, as few words as possible
+    Minimal,
+}
+
+impl Persona {
+    /// Persona part of the system prompt for the given layout
+    pub fn prompt(self, layout: Layout) -> String {
+        let (intro, style) = match self {
+            Persona::Encouraging => (
+                "You are a friendly Korean typing tutor helping non-Korean speakers learn to type Hangul.",
+                "- Encouraging and patient - learning a new writing system is hard!
+- Use simple explanations with concrete examples
+- Break down complex syllables step-by-step
+- Celebrate progress, never punish mistakes
+- Keep responses concise (1-3 sentences unless explaining in detail)",
+            ),
+            Persona::DrillSergeant => (
+                "You are a strict Korean typing drill instructor training non-Korean speakers to type Hangul fast and accurately.",
+                "- Direct and demanding - hold the student to a high standard
+- Name every mistake plainly and tell them exactly what to fix
+- Push for speed once accuracy is solid
+- Praise only genuinely good results
+- Keep responses to one or two short sentences",
+            ),
+            Persona::Minimal => (
+                "You are a terse Korean typing reference for non-Korean speakers learning to type Hangul.",
+                "- Answer with the keys to press and little else
+- No greetings, encouragement or filler
+- Use a single short line unless the user asks for detail",
+            ),
+        };
+
+        format!(
+            r#"{intro}
+
+<your_knowledge>
+- The {name} keyboard layout
+- How jamo (자모) combine to form syllables: initial + vowel + optional final
+- Common typing mistakes English speakers make
+- Korean pronunciation basics (romanization)
+</your_knowledge>
+
+<your_style>
+{style}
+- When showing keyboard keys, use the English letter equivalent
+- IMPORTANT: Always respond in the same language the user writes in. If they ask in Spanish, respond in Spanish. If they ask in Japanese, respond in Japanese. Only the Korean characters being taught should remain in Korean.
+</your_style>"#,
+            name = layout.name(),
+        )
+    }
 }
 
 /// Context about the user's current learning state
@@ -60,6 +281,35 @@ pub struct AssistantResponse {
     pub tool_used: Option<String>,
 }
 
+/// Who wrote a transcript entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    User,
+    Assistant,
+}
+
+/// One message in a conversation's transcript
+#[derive(Debug, Clone, Serialize)]
+pub struct TranscriptTurn {
+    pub role: Role,
+    pub content: String,
+    pub at: DateTime<Utc>,
+}
+
+/// One turn of a roleplay scenario
+#[derive(Debug, Clone, Serialize)]
+pub struct ScenarioTurn {
+    /// Pass back to continue the scenario
+    pub conversation_id: Uuid,
+    /// What the assistant's character says
+    pub line: String,
+    /// A reply the learner could type
+    pub suggested_reply: String,
+    /// Keys that type the suggested reply, labelled for the learner's keyboard
+    pub keystrokes: Vec<KeyPress>,
+}
+
 /// Result of checking Copilot CLI availability
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CopilotAvailability {
@@ -69,6 +319,19 @@ pub struct CopilotAvailability {
     pub message: String,
 }
 
+impl CopilotAvailability {
+    /// Why Copilot can't be started, or `None` if it can
+    pub fn error(&self) -> Option<CopilotError> {
+        if !self.cli_installed {
+            Some(CopilotError::CliNotFound)
+        } else if !self.cli_authenticated {
+            Some(CopilotError::NotAuthenticated)
+        } else {
+            None
+        }
+    }
+}
+
 /// Check if GitHub Copilot CLI is installed
 fn is_copilot_cli_installed() -> bool {
     // Try to find 'copilot' in PATH
@@ -147,107 +410,467 @@ pub fn check_availability() -> CopilotAvailability {
     }
 }
 
-/// The Copilot service manages client lifecycle and sessions
-pub struct CopilotService {
-    client: Arc<Mutex<Option<Client>>>,
-    is_running: Arc<RwLock<bool>>,
-    system_prompt: String,
+/// Outcome of a health-check ping
+#[derive(Debug, Clone, Serialize)]
+pub struct PingResult {
+    pub ok: bool,
+    /// Round-trip time, present only when the ping succeeded
+    pub latency_ms: Option<u64>,
+    pub error: Option<String>,
 }
 
-impl CopilotService {
-    /// Create a new Copilot service (does not start the client)
-    pub fn new() -> Self {
-        let system_prompt = r#"You are a friendly Korean typing tutor helping non-Korean speakers learn to type Hangul.
+/// Tokens consumed since start-up or the last reset
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct UsageStats {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub request_count: u64,
+    /// True when any counts were estimated from text length rather than
+    /// reported by Copilot
+    pub estimated: bool,
+}
 
-<your_knowledge>
-- The 2-Bulsik (두벌식) keyboard layout standard in Korea
-- How jamo (자모) combine to form syllables: initial + vowel + optional final
-- Common typing mistakes English speakers make
-- Korean pronunciation basics (romanization)
-</your_knowledge>
+impl UsageStats {
+    /// Add one request's usage, estimating tokens from the text.
+    ///
+    /// The session events we consume carry no token counts, so every
+    /// request is estimated for now.
+    fn record_estimated(&mut self, prompt: &str, completion: &str) {
+        self.prompt_tokens += estimate_tokens(prompt);
+        self.completion_tokens += estimate_tokens(completion);
+        self.request_count += 1;
+        self.estimated = true;
+    }
+}
 
-<your_style>
-- Encouraging and patient - learning a new writing system is hard!
-- Use simple explanations with concrete examples
-- Break down complex syllables step-by-step
-- Celebrate progress, never punish mistakes
-- Keep responses concise (1-3 sentences unless explaining in detail)
-- When showing keyboard keys, use the English letter equivalent
-- IMPORTANT: Always respond in the same language the user writes in. If they ask in Spanish, respond in Spanish. If they ask in Japanese, respond in Japanese. Only the Korean characters being taught should remain in Korean.
-</your_style>
+/// The most recent request failure, for bug reports
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorInfo {
+    /// See [`CopilotError::category`]
+    pub category: String,
+    pub message: String,
+    pub occurred_at: DateTime<Utc>,
+    pub seconds_ago: u64,
+}
 
-<keyboard_layout>
-The 2-Bulsik layout maps English keys to Korean jamo:
-- Consonants (left hand): ㅂ(q) ㅈ(w) ㄷ(e) ㄱ(r) ㅅ(t) ㅁ(a) ㄴ(s) ㅇ(d) ㄹ(f) ㅎ(g) ㅋ(z) ㅌ(x) ㅊ(c) ㅍ(v)
-- Vowels (right hand): ㅛ(y) ㅕ(u) ㅑ(i) ㅐ(o) ㅔ(p) ㅗ(h) ㅓ(j) ㅏ(k) ㅣ(l) ㅠ(b) ㅜ(n) ㅡ(m)
-- Double consonants: Shift + base consonant (ㄲ=Shift+r, ㄸ=Shift+e, etc.)
-</keyboard_layout>
+/// A failure as stored by the service
+struct RecordedError {
+    category: &'static str,
+    message: String,
+    at: DateTime<Utc>,
+}
+
+/// A boxed future, for the factory and session traits below
+type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// What the service needs from a Copilot session, so tests can stand in
+/// for the CLI
+trait ChatSession: Send + Sync {
+    /// Events for the messages sent from now on
+    fn subscribe(&self) -> broadcast::Receiver<SessionEvent>;
+    /// Send a message, returning its id; the reply arrives as events
+    fn send<'a>(&'a self, prompt: &'a str) -> BoxFuture<'a, Result<String, String>>;
+}
+
+impl ChatSession for Session {
+    fn subscribe(&self) -> broadcast::Receiver<SessionEvent> {
+        Session::subscribe(self)
+    }
+
+    fn send<'a>(&'a self, prompt: &'a str) -> BoxFuture<'a, Result<String, String>> {
+        Box::pin(async move { Session::send(self, prompt).await.map_err(|e| e.to_string()) })
+    }
+}
+
+/// Checks the CLI is usable, then builds and starts a client
+type ClientFactory =
+    Arc<dyn Fn() -> BoxFuture<'static, Result<Client, CopilotError>> + Send + Sync>;
+
+/// Client factory backed by the SDK, talking to the CLI over stdio
+fn sdk_client() -> ClientFactory {
+    Arc::new(|| {
+        Box::pin(async {
+            // Check if Copilot CLI is available before attempting to start
+            debug!("Checking Copilot CLI availability...");
+            let availability = check_availability();
+            debug!(
+                "Availability: cli_installed={}, cli_authenticated={}, available={}",
+                availability.cli_installed, availability.cli_authenticated, availability.available
+            );
+
+            if !availability.cli_installed {
+                warn!("Copilot CLI not installed");
+                return Err(CopilotError::CliNotFound);
+            }
+
+            if !availability.cli_authenticated {
+                warn!("GitHub CLI not authenticated");
+                return Err(CopilotError::NotAuthenticated);
+            }
+
+            debug!("Starting Copilot client with stdio transport...");
+
+            let client = Client::builder().use_stdio(true).build().map_err(|e| {
+                error!("Failed to build client: {}", e);
+                CopilotError::StartFailed(e.to_string())
+            })?;
+
+            debug!("Client built, starting...");
+
+            client.start().await.map_err(|e| {
+                error!("Failed to start client: {}", e);
+                CopilotError::StartFailed(e.to_string())
+            })?;
+            Ok(client)
+        })
+    })
+}
+
+/// Creates a session on a client and registers the given tools on it
+type SessionFactory = Arc<
+    dyn Fn(
+            Arc<Client>,
+            SessionConfig,
+            Vec<(Tool, ToolHandler)>,
+        ) -> BoxFuture<'static, Result<Arc<dyn ChatSession>, CopilotError>>
+        + Send
+        + Sync,
+>;
+
+/// Session factory backed by the SDK
+fn sdk_sessions() -> SessionFactory {
+    Arc::new(|client, config, tools| {
+        Box::pin(async move {
+            let session = client.create_session(config).await.map_err(|e| {
+                error!("Failed to create session: {}", e);
+                CopilotError::SessionFailed(e.to_string())
+            })?;
+            // Registered here rather than in the config, since each
+            // handler has to come with its tool
+            for (tool, handler) in tools {
+                session
+                    .register_tool_with_handler(tool, Some(handler))
+                    .await;
+            }
+            Ok(session as Arc<dyn ChatSession>)
+        })
+    })
+}
+
+/// A session along with the system prompt it was created with
+struct TutorSession {
+    session: Arc<dyn ChatSession>,
+    /// Kept so the prompt can be checked after the layout or persona changes
+    system_prompt: String,
+    /// Names of the tools registered on the session
+    tools: Vec<String>,
+}
 
-When the user asks about typing a character or word, explain which English keys to press in order."#.to_string();
+/// A multi-turn chat with its own session
+struct Conversation {
+    /// Created on the first message so conversations can be opened offline
+    session: Option<Arc<TutorSession>>,
+    last_used: Instant,
+    /// Level a roleplay scenario is held to, if this is one
+    scenario_level: Option<u32>,
+    /// One permit, so turns take place one at a time on the session
+    turn: Arc<Semaphore>,
+    /// Messages exchanged so far, at most [`TRANSCRIPT_CAPACITY`]
+    transcript: VecDeque<TranscriptTurn>,
+}
+
+/// The Copilot service manages client lifecycle and sessions.
+///
+/// All state is shared behind `Arc`s, so clones refer to the same service.
+#[derive(Clone)]
+pub struct CopilotService {
+    /// Held only long enough to clone the handle, never across a request
+    client: Arc<Mutex<Option<Arc<Client>>>>,
+    /// Idle session reused across requests, created lazily on first `ask`.
+    /// Requests check it out while in use so concurrent requests never
+    /// read each other's events.
+    session: Arc<Mutex<Option<Arc<TutorSession>>>>,
+    /// Bumped on reset so sessions checked out before it are not returned
+    session_epoch: Arc<AtomicU64>,
+    is_running: Arc<RwLock<bool>>,
+    timeout_secs: Arc<RwLock<u64>>,
+    /// Shorter timeout for hints, which are only useful while fresh
+    hint_timeout_secs: Arc<RwLock<u64>>,
+    /// Cancellation tokens for requests that are still in flight
+    requests: Arc<Mutex<HashMap<Uuid, CancellationToken>>>,
+    /// Signalled whenever a request finishes, so shutdown can drain
+    request_finished: Arc<Notify>,
+    /// Set while shutting down; new requests are rejected
+    shutting_down: Arc<AtomicBool>,
+    /// Keyboard layout the learner is practising on
+    layout: Arc<RwLock<Layout>>,
+    /// Keyboard the learner types on, used to label keys in local hints
+    physical_layout: Arc<RwLock<PhysicalLayout>>,
+    /// Built-in persona, used unless a custom prompt is set
+    persona: Arc<RwLock<Persona>>,
+    /// Custom persona prompt that replaces the built-in one
+    custom_prompt: Arc<RwLock<Option<String>>>,
+    /// Model requested for new sessions; `None` uses the CLI default
+    model: Arc<RwLock<Option<String>>>,
+    /// Open conversations, each remembering its own prior turns
+    conversations: Arc<Mutex<HashMap<Uuid, Conversation>>>,
+    conversation_ttl: Arc<RwLock<Duration>>,
+    /// Tokens consumed by successful requests
+    usage: Arc<Mutex<UsageStats>>,
+    /// Recent explain/hint answers keyed by normalized prompt
+    response_cache: Arc<Mutex<LruCache<String, AssistantResponse>>>,
+    /// Create the shared session in the background as soon as the client starts
+    prewarm: Arc<AtomicBool>,
+    /// Most recent failed request, cleared by the next success
+    last_error: Arc<Mutex<Option<RecordedError>>>,
+    /// Answer with canned local responses instead of calling Copilot
+    mock: Arc<AtomicBool>,
+    /// When a request last started or finished
+    last_activity: Arc<Mutex<Instant>>,
+    /// Stop the client after this long without requests; zero disables
+    idle_timeout: Arc<RwLock<Duration>>,
+    /// Set when the client was stopped for being idle, so the next request
+    /// restarts it
+    idle_stopped: Arc<AtomicBool>,
+    /// Set when the client process died, so the next request restarts it
+    terminated: Arc<AtomicBool>,
+    /// Caps requests sent to Copilot; cached and mock answers are free
+    rate_limiter: Arc<Mutex<TokenBucket>>,
+    /// One permit per request that may be sent to the CLI at once, so
+    /// background work can't saturate its single stdio pipe
+    concurrency: Arc<Mutex<Arc<Semaphore>>>,
+    /// Target of each open quiz question
+    quizzes: Arc<Mutex<LruCache<Uuid, String>>>,
+    /// Starts the client
+    client_factory: ClientFactory,
+    /// Creates sessions on the running client
+    session_factory: SessionFactory,
+}
 
+impl CopilotService {
+    /// Create a new Copilot service (does not start the client)
+    pub fn new() -> Self {
         Self {
             client: Arc::new(Mutex::new(None)),
+            session: Arc::new(Mutex::new(None)),
+            session_epoch: Arc::new(AtomicU64::new(0)),
             is_running: Arc::new(RwLock::new(false)),
-            system_prompt,
+            timeout_secs: Arc::new(RwLock::new(DEFAULT_TIMEOUT_SECS)),
+            hint_timeout_secs: Arc::new(RwLock::new(DEFAULT_HINT_TIMEOUT_SECS)),
+            requests: Arc::new(Mutex::new(HashMap::new())),
+            request_finished: Arc::new(Notify::new()),
+            shutting_down: Arc::new(AtomicBool::new(false)),
+            layout: Arc::new(RwLock::new(Layout::default())),
+            physical_layout: Arc::new(RwLock::new(PhysicalLayout::default())),
+            persona: Arc::new(RwLock::new(Persona::default())),
+            custom_prompt: Arc::new(RwLock::new(None)),
+            model: Arc::new(RwLock::new(None)),
+            conversations: Arc::new(Mutex::new(HashMap::new())),
+            conversation_ttl: Arc::new(RwLock::new(Duration::from_secs(
+                DEFAULT_CONVERSATION_TTL_SECS,
+            ))),
+            usage: Arc::new(Mutex::new(UsageStats::default())),
+            response_cache: Arc::new(Mutex::new(LruCache::new(DEFAULT_CACHE_CAPACITY))),
+            prewarm: Arc::new(AtomicBool::new(true)),
+            last_error: Arc::new(Mutex::new(None)),
+            mock: Arc::new(AtomicBool::new(false)),
+            last_activity: Arc::new(Mutex::new(Instant::now())),
+            idle_timeout: Arc::new(RwLock::new(Duration::from_secs(DEFAULT_IDLE_TIMEOUT_SECS))),
+            idle_stopped: Arc::new(AtomicBool::new(false)),
+            terminated: Arc::new(AtomicBool::new(false)),
+            rate_limiter: Arc::new(Mutex::new(TokenBucket::new(
+                DEFAULT_RATE_LIMIT_REQUESTS,
+                Duration::from_secs(DEFAULT_RATE_LIMIT_WINDOW_SECS),
+            ))),
+            concurrency: Arc::new(Mutex::new(Arc::new(Semaphore::new(
+                DEFAULT_MAX_CONCURRENCY,
+            )))),
+            quizzes: Arc::new(Mutex::new(LruCache::new(QUIZ_CAPACITY))),
+            client_factory: sdk_client(),
+            session_factory: sdk_sessions(),
         }
     }
 
     /// Initialize and start the Copilot client
-    /// Returns error if Copilot CLI is not installed or not authenticated
-    pub async fn start(&self) -> Result<(), CopilotError> {
+    /// Returns error if Copilot CLI is not installed or not authenticated.
+    /// Safe to call again; a running client is left alone and reported as
+    /// [`StartOutcome::AlreadyRunning`].
+    pub async fn start(&self) -> Result<StartOutcome, CopilotError> {
         let mut client_lock = self.client.lock().await;
 
         if client_lock.is_some() {
             debug!("Copilot client already running");
-            return Ok(());
+            return Ok(StartOutcome::AlreadyRunning);
         }
 
-        // Check if Copilot CLI is available before attempting to start
-        debug!("Checking Copilot CLI availability...");
-        let availability = check_availability();
-        debug!(
-            "Availability: cli_installed={}, cli_authenticated={}, available={}",
-            availability.cli_installed, availability.cli_authenticated, availability.available
-        );
+        let client = Arc::new((self.client_factory)().await?);
+        *client_lock = Some(Arc::clone(&client));
+        drop(client_lock);
+        *self.is_running.write().await = true;
+        self.terminated.store(false, Ordering::SeqCst);
+        self.touch().await;
 
-        if !availability.cli_installed {
-            warn!("Copilot CLI not installed");
-            return Err(CopilotError::CliNotFound);
+        info!("Copilot AI assistant ready");
+        if self.prewarm.load(Ordering::SeqCst) {
+            self.spawn_prewarm();
         }
+        self.spawn_idle_watchdog(client);
+        Ok(StartOutcome::Started)
+    }
+
+    /// Note that the service is in use, postponing the idle stop
+    async fn touch(&self) {
+        *self.last_activity.lock().await = Instant::now();
+    }
+
+    /// Stop the client after `secs` without requests (0 disables).
+    ///
+    /// A stopped client restarts on the next request.
+    pub async fn set_idle_timeout(&self, secs: u64) {
+        *self.idle_timeout.write().await = Duration::from_secs(secs);
+        info!("Copilot idle timeout set to {}s", secs);
+    }
+
+    /// Allow at most `requests` Copilot requests per `window_secs`.
+    ///
+    /// Zero for either disables the limit.
+    pub async fn set_rate_limit(&self, requests: u32, window_secs: u64) {
+        self.rate_limiter
+            .lock()
+            .await
+            .reconfigure(requests, Duration::from_secs(window_secs));
+        info!(
+            "Copilot rate limit set to {} requests per {}s",
+            requests, window_secs
+        );
+    }
 
-        if !availability.cli_authenticated {
-            warn!("GitHub CLI not authenticated");
-            return Err(CopilotError::NotAuthenticated);
+    /// Send at most `max` requests to the CLI at once; at least one.
+    ///
+    /// Requests already sent keep their place; the new cap applies to
+    /// those that start afterwards.
+    pub async fn set_max_concurrency(&self, max: usize) -> usize {
+        let max = max.max(1);
+        *self.concurrency.lock().await = Arc::new(Semaphore::new(max));
+        info!("Copilot concurrency cap set to {}", max);
+        max
+    }
+
+    /// Wait for a free request slot, giving up if `cancel` fires first
+    async fn acquire_slot(
+        &self,
+        cancel: &CancellationToken,
+    ) -> Result<OwnedSemaphorePermit, CopilotError> {
+        let slots = Arc::clone(&*self.concurrency.lock().await);
+        tokio::select! {
+            _ = cancel.cancelled() => Err(CopilotError::Cancelled),
+            permit = slots.acquire_owned() => {
+                Ok(permit.expect("concurrency semaphores are never closed"))
+            }
         }
+    }
 
-        debug!("Starting Copilot client with stdio transport...");
+    /// Watch `client` in the background and stop it once idle
+    fn spawn_idle_watchdog(&self, client: Arc<Client>) {
+        let service = self.clone();
+        tokio::spawn(async move { service.idle_watchdog(client).await });
+    }
 
-        let client = Client::builder().use_stdio(true).build().map_err(|e| {
-            error!("Failed to build client: {}", e);
-            CopilotError::StartFailed(e.to_string())
-        })?;
+    /// Stop `watched` once no request has run for the idle timeout.
+    ///
+    /// Exits as soon as `watched` is no longer the running client, so a
+    /// restart gets a watchdog of its own. Only the client and its sessions
+    /// are released; conversations keep their history and get new sessions
+    /// when the next request restarts the client.
+    async fn idle_watchdog(&self, watched: Arc<Client>) {
+        loop {
+            let timeout = *self.idle_timeout.read().await;
+            let idle = self.last_activity.lock().await.elapsed();
+            let wait = if timeout.is_zero() {
+                IDLE_RECHECK_INTERVAL
+            } else {
+                match timeout.checked_sub(idle) {
+                    Some(remaining) if !remaining.is_zero() => remaining,
+                    // Due, but a request is still running
+                    _ => timeout.min(IDLE_RECHECK_INTERVAL),
+                }
+            };
+            tokio::time::sleep(wait).await;
 
-        debug!("Client built, starting...");
+            // Held until the client is released, so a request can't
+            // register between the idle check and the teardown
+            let requests = self.requests.lock().await;
+            let mut client = self.client.lock().await;
+            match client.as_ref() {
+                Some(current) if Arc::ptr_eq(current, &watched) => {}
+                _ => return,
+            }
 
-        client.start().await.map_err(|e| {
-            error!("Failed to start client: {}", e);
-            CopilotError::StartFailed(e.to_string())
-        })?;
+            let timeout = *self.idle_timeout.read().await;
+            let idle = self.last_activity.lock().await.elapsed();
+            if timeout.is_zero() || idle < timeout || !requests.is_empty() {
+                continue;
+            }
 
-        *client_lock = Some(client);
-        *self.is_running.write().await = true;
+            info!("Copilot idle for {}s, stopping client", idle.as_secs());
+            client.take();
+            drop(client);
+            self.release_sessions().await;
+            self.idle_stopped.store(true, Ordering::SeqCst);
+            drop(requests);
+            if let Err(e) = watched.stop().await {
+                warn!("Failed to stop idle Copilot client: {}", e);
+            }
+            return;
+        }
+    }
 
-        info!("Copilot AI assistant ready");
-        Ok(())
+    /// Enable or disable creating the session in the background on start
+    pub fn set_prewarm(&self, enabled: bool) {
+        self.prewarm.store(enabled, Ordering::SeqCst);
+        info!(
+            "Session prewarm {}",
+            if enabled { "enabled" } else { "disabled" }
+        );
+    }
+
+    /// Create the shared session in the background so the first request
+    /// doesn't pay for it
+    fn spawn_prewarm(&self) {
+        let service = self.clone();
+        tokio::spawn(async move { service.prewarm().await });
+    }
+
+    /// Create the shared session and leave it cached.
+    ///
+    /// A failure is only logged; the first real request creates the
+    /// session instead.
+    async fn prewarm(&self) {
+        let Ok(client) = self.client().await else {
+            return;
+        };
+        match self.checkout_session(&client).await {
+            Ok((session, epoch)) => {
+                self.checkin_session(session, epoch).await;
+                debug!("Copilot session prewarmed");
+            }
+            Err(e) => warn!("Session prewarm failed, will retry on first request: {}", e),
+        }
     }
 
     /// Stop the Copilot client
     pub async fn stop(&self) -> Result<(), CopilotError> {
+        // An explicit stop is final; only idle stops and crashes restart
+        // on demand
+        self.idle_stopped.store(false, Ordering::SeqCst);
+        self.terminated.store(false, Ordering::SeqCst);
         let mut client_lock = self.client.lock().await;
 
         if let Some(client) = client_lock.take() {
             info!("Stopping Copilot client...");
             *self.is_running.write().await = false;
+            self.session.lock().await.take();
+            self.conversations.lock().await.clear();
             client
                 .stop()
                 .await
@@ -258,166 +881,3494 @@ When the user asks about typing a character or word, explain which English keys
         Ok(())
     }
 
-    /// Check if the service is running
-    pub async fn is_running(&self) -> bool {
-        *self.is_running.read().await
+    /// Forget a client whose process died, so the next request starts a
+    /// new one instead of failing on the dead pipe
+    async fn client_terminated(&self) {
+        if self.client.lock().await.take().is_none() {
+            return;
+        }
+        warn!("Copilot client terminated; it will restart on the next request");
+        self.release_sessions().await;
+        self.terminated.store(true, Ordering::SeqCst);
     }
 
-    /// Send a message to Copilot and get a response
-    pub async fn ask(
-        &self,
-        prompt: &str,
-        context: Option<LearningContext>,
-    ) -> Result<AssistantResponse, CopilotError> {
-        let client_lock = self.client.lock().await;
-        let client = client_lock.as_ref().ok_or(CopilotError::NotInitialized)?;
+    /// Drop every session of a client that is gone, keeping conversation
+    /// history
+    async fn release_sessions(&self) {
+        *self.is_running.write().await = false;
+        self.session_epoch.fetch_add(1, Ordering::SeqCst);
+        self.session.lock().await.take();
+        for conversation in self.conversations.lock().await.values_mut() {
+            conversation.session = None;
+        }
+    }
 
-        // Build context-aware prompt
-        let full_prompt = if let Some(ctx) = context {
-            format!(
-                "{}\n\n<current_context>\nLevel: {}\nTarget: {}\nRecent mistakes: {:?}\nAccuracy: {:.0}%\n</current_context>",
-                prompt,
-                ctx.current_level,
-                ctx.current_target.unwrap_or_default(),
-                ctx.recent_mistakes,
-                ctx.accuracy * 100.0
-            )
-        } else {
-            prompt.to_string()
-        };
+    /// Features that work right now, local ones first.
+    ///
+    /// The SDK doesn't report server-side tools, so Copilot features are
+    /// listed whenever the service can serve requests, followed by one
+    /// `tool:` entry per tool registered on the open sessions.
+    pub async fn capabilities(&self) -> Vec<String> {
+        let mut capabilities: Vec<String> =
+            LOCAL_CAPABILITIES.iter().map(|c| c.to_string()).collect();
+        if self.can_serve().await {
+            capabilities.extend(COPILOT_CAPABILITIES.iter().map(|c| c.to_string()));
+        }
+        // Mock answers never reach a session, so no tool is ever called
+        if *self.is_running.read().await
+            || self.idle_stopped.load(Ordering::SeqCst)
+            || self.is_restartable()
+        {
+            capabilities.extend(
+                self.session_tool_names()
+                    .await
+                    .into_iter()
+                    .map(|name| format!("tool:{}", name)),
+            );
+        }
+        capabilities
+    }
 
-        debug!("Creating Copilot session...");
+    /// Tools registered on the open sessions, or those a new session would
+    /// get if none is open
+    async fn session_tool_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .session
+            .lock()
+            .await
+            .iter()
+            .flat_map(|s| s.tools.clone())
+            .collect();
+        for conversation in self.conversations.lock().await.values() {
+            if let Some(session) = &conversation.session {
+                names.extend(session.tools.iter().cloned());
+            }
+        }
 
-        // Create session with our tutor persona
-        let config = SessionConfig {
-            system_message: Some(SystemMessageConfig {
-                mode: Some(SystemMessageMode::Replace),
-                content: Some(self.system_prompt.clone()),
-            }),
-            ..Default::default()
-        };
+        if names.is_empty() {
+            names = session_tools(self.layout().await)
+                .into_iter()
+                .map(|(tool, _)| tool.name)
+                .collect();
+        }
+        names.sort();
+        names.dedup();
+        names
+    }
 
-        let session = client.create_session(config).await.map_err(|e| {
-            error!("Failed to create session: {}", e);
-            CopilotError::SessionFailed(e.to_string())
-        })?;
+    /// Check if the service can serve requests.
+    ///
+    /// A client stopped for being idle still counts, since the next request
+    /// restarts it.
+    pub async fn is_running(&self) -> bool {
+        self.is_mock() || self.idle_stopped.load(Ordering::SeqCst) || *self.is_running.read().await
+    }
 
-        debug!("Session created, subscribing to events...");
+    /// Whether the client process died and the next request will start a
+    /// new one. A dead client is not [running](Self::is_running).
+    pub fn is_restartable(&self) -> bool {
+        self.terminated.load(Ordering::SeqCst)
+    }
 
-        // Subscribe BEFORE sending to not miss any events
-        let mut events = session.subscribe();
+    /// Whether a request can be served now, restarting a dead client if
+    /// need be
+    pub async fn can_serve(&self) -> bool {
+        self.is_running().await || self.is_restartable()
+    }
 
-        debug!("Sending message ({} chars)...", full_prompt.len());
+    /// Whether requests get canned local responses instead of Copilot's
+    pub fn is_mock(&self) -> bool {
+        self.mock.load(Ordering::SeqCst)
+    }
 
-        // Send the message
-        let message_id = session.send(full_prompt.as_str()).await.map_err(|e| {
-            error!("Failed to send message: {}", e);
-            CopilotError::SendFailed(e.to_string())
-        })?;
+    /// Turn mock mode on or off, for frontend development and UI tests.
+    ///
+    /// In mock mode the service reports itself as running and `ask`,
+    /// `get_hint` and `explain` answer deterministically from the local
+    /// layout and romanization tables, without touching the client.
+    pub fn set_mock(&self, enabled: bool) {
+        self.mock.store(enabled, Ordering::SeqCst);
+        info!(
+            "Copilot mock mode {}",
+            if enabled { "enabled" } else { "disabled" }
+        );
+    }
 
-        debug!("Message sent (id={}), waiting for response...", message_id);
+    /// Canned response used in mock mode
+    fn mock_response(content: String) -> AssistantResponse {
+        AssistantResponse {
+            content,
+            tool_used: Some(MOCK_TOOL.to_string()),
+        }
+    }
 
-        // Collect response from events
-        let mut response_content = String::new();
+    /// Set the response timeout, clamped to the allowed range.
+    /// Returns the timeout actually applied.
+    pub async fn set_timeout(&self, secs: u64) -> u64 {
+        let clamped = clamp_timeout_secs(secs);
+        if clamped != secs {
+            warn!("Timeout {}s out of range, clamped to {}s", secs, clamped);
+        }
+        *self.timeout_secs.write().await = clamped;
+        info!("Copilot response timeout set to {}s", clamped);
+        clamped
+    }
 
-        loop {
-            match tokio::time::timeout(std::time::Duration::from_secs(60), events.recv()).await {
-                Ok(Ok(event)) => {
-                    debug!("Event: {:?}", std::mem::discriminant(&event.data));
-                    match &event.data {
-                        SessionEventData::AssistantMessageDelta(delta) => {
-                            debug!("Delta: +{} chars", delta.delta_content.len());
-                            response_content.push_str(&delta.delta_content);
-                        }
-                        SessionEventData::AssistantMessage(msg) => {
-                            debug!("Full message: {} chars", msg.content.len());
-                            if response_content.is_empty() {
-                                response_content = msg.content.clone();
-                            }
-                        }
-                        SessionEventData::SessionIdle(_) => {
-                            debug!("Session idle");
-                            break;
-                        }
-                        SessionEventData::SessionError(err) => {
-                            error!("Copilot session error: {}", err.message);
-                            return Err(CopilotError::SendFailed(err.message.clone()));
-                        }
-                        _ => {}
-                    }
-                }
-                Ok(Err(e)) => {
-                    warn!("Event channel error: {:?}", e);
-                    break;
-                }
-                Err(_) => {
-                    error!("Timeout waiting for Copilot response");
-                    return Err(CopilotError::Timeout);
-                }
-            }
+    /// Current response timeout
+    pub async fn timeout(&self) -> Duration {
+        Duration::from_secs(*self.timeout_secs.read().await)
+    }
+
+    /// Set the hint timeout, clamped like [`Self::set_timeout`].
+    /// Returns the timeout actually applied.
+    pub async fn set_hint_timeout(&self, secs: u64) -> u64 {
+        let clamped = clamp_timeout_secs(secs);
+        if clamped != secs {
+            warn!(
+                "Hint timeout {}s out of range, clamped to {}s",
+                secs, clamped
+            );
         }
+        *self.hint_timeout_secs.write().await = clamped;
+        info!("Copilot hint timeout set to {}s", clamped);
+        clamped
+    }
 
-        info!("Copilot response: {} chars", response_content.len());
+    /// Current hint timeout
+    pub async fn hint_timeout(&self) -> Duration {
+        Duration::from_secs(*self.hint_timeout_secs.read().await)
+    }
 
-        Ok(AssistantResponse {
-            content: response_content,
-            tool_used: None,
-        })
+    /// Register an outstanding request and get the token that cancels it
+    pub async fn begin_request(&self, id: Uuid) -> CancellationToken {
+        let token = CancellationToken::new();
+        self.requests.lock().await.insert(id, token.clone());
+        token
     }
 
-    /// Get a hint for the current typing target
-    pub async fn get_hint(
-        &self,
-        target: &str,
-        user_input: &str,
-        level: u32,
-    ) -> Result<AssistantResponse, CopilotError> {
-        let prompt = format!(
-            "The student is trying to type \"{}\" but typed \"{}\". They are on level {}. Give a brief, encouraging hint about which key to press next. Don't give away the full answer.",
-            target, user_input, level
-        );
+    /// Forget an outstanding request once it has finished
+    pub async fn finish_request(&self, id: Uuid) {
+        self.requests.lock().await.remove(&id);
+        self.touch().await;
+        self.request_finished.notify_waiters();
+    }
+
+    /// Stop accepting requests, wait up to `grace` for in-flight requests
+    /// to finish, then stop the client.
+    ///
+    /// Requests still running when the grace period ends are cut off.
+    pub async fn shutdown_gracefully(&self, grace: Duration) -> Result<(), CopilotError> {
+        self.shutting_down.store(true, Ordering::SeqCst);
 
-        self.ask(&prompt, None).await
+        if tokio::time::timeout(grace, self.drain_requests())
+            .await
+            .is_err()
+        {
+            warn!(
+                "{} request(s) still running after {}s grace period",
+                self.requests.lock().await.len(),
+                grace.as_secs()
+            );
+        }
+
+        let result = self.stop().await;
+        // Allow the service to be started again later
+        self.shutting_down.store(false, Ordering::SeqCst);
+        result
     }
 
-    /// Explain a specific jamo or syllable
-    pub async fn explain(&self, text: &str) -> Result<AssistantResponse, CopilotError> {
-        let prompt = format!(
-            "Explain the Korean character or word \"{}\": what it is, how to pronounce it (romanization), and exactly which English keys to press to type it on a 2-Bulsik keyboard.",
-            text
-        );
+    /// Wait until no requests are in flight
+    async fn drain_requests(&self) {
+        loop {
+            // Register interest before checking so a finish in between is not missed
+            let finished = self.request_finished.notified();
+            if self.requests.lock().await.is_empty() {
+                return;
+            }
+            debug!("Waiting for in-flight requests before shutdown...");
+            finished.await;
+        }
+    }
 
-        self.ask(&prompt, None).await
+    /// Cancel an outstanding request. Returns false if no such request exists.
+    pub async fn cancel_request(&self, id: Uuid) -> bool {
+        match self.requests.lock().await.remove(&id) {
+            Some(token) => {
+                info!("Cancelling Copilot request {}", id);
+                token.cancel();
+                true
+            }
+            None => false,
+        }
     }
 
-    /// Analyze a typing mistake
-    pub async fn analyze_mistake(
-        &self,
-        expected: &str,
-        actual: &str,
-    ) -> Result<AssistantResponse, CopilotError> {
-        let prompt = format!(
-            "The student tried to type \"{}\" but typed \"{}\". Briefly explain what went wrong and how to fix it.",
-            expected, actual
-        );
+    /// Cancel every outstanding request. Returns how many were cancelled.
+    pub async fn cancel_all(&self) -> usize {
+        let requests: Vec<(Uuid, CancellationToken)> = self.requests.lock().await.drain().collect();
+        for (id, token) in &requests {
+            debug!("Cancelling Copilot request {}", id);
+            token.cancel();
+        }
+        requests.len()
+    }
 
-        self.ask(&prompt, None).await
+    /// Open a new conversation and return its id
+    pub async fn new_conversation(&self) -> Uuid {
+        self.open_conversation(None).await
     }
-}
 
-/// Get or initialize the global Copilot service
-pub fn get_service() -> &'static CopilotService {
-    COPILOT_SERVICE.get_or_init(CopilotService::new)
-}
+    async fn open_conversation(&self, scenario_level: Option<u32>) -> Uuid {
+        self.evict_idle_conversations().await;
+
+        let id = Uuid::new_v4();
+        self.conversations.lock().await.insert(
+            id,
+            Conversation {
+                session: None,
+                last_used: Instant::now(),
+                scenario_level,
+                turn: Arc::new(Semaphore::new(1)),
+                transcript: VecDeque::new(),
+            },
+        );
+        debug!("Opened conversation {}", id);
+        id
+    }
+
+    /// Close a conversation and free its session.
+    /// Returns false if it did not exist or had already expired.
+    pub async fn end_conversation(&self, id: Uuid) -> bool {
+        let ended = self.conversations.lock().await.remove(&id).is_some();
+        if ended {
+            debug!("Ended conversation {}", id);
+        }
+        ended
+    }
+
+    /// Messages exchanged in a conversation, oldest first
+    pub async fn transcript(&self, id: Uuid) -> Result<Vec<TranscriptTurn>, CopilotError> {
+        self.conversations
+            .lock()
+            .await
+            .get(&id)
+            .map(|c| c.transcript.iter().cloned().collect())
+            .ok_or(CopilotError::UnknownConversation(id))
+    }
+
+    /// Add a completed exchange to a conversation's transcript, as the
+    /// learner saw it: what they `said` and the `reply` shown to them
+    async fn record_exchange(&self, id: Uuid, said: &str, sent_at: DateTime<Utc>, reply: &str) {
+        let mut conversations = self.conversations.lock().await;
+        let Some(conversation) = conversations.get_mut(&id) else {
+            return;
+        };
+        let transcript = &mut conversation.transcript;
+        transcript.push_back(TranscriptTurn {
+            role: Role::User,
+            content: said.to_string(),
+            at: sent_at,
+        });
+        transcript.push_back(TranscriptTurn {
+            role: Role::Assistant,
+            content: reply.to_string(),
+            at: Utc::now(),
+        });
+        while transcript.len() > TRANSCRIPT_CAPACITY {
+            transcript.pop_front();
+        }
+    }
+
+    /// Set how long a conversation may sit idle before it is evicted, at
+    /// least [`MIN_CONVERSATION_TTL_SECS`]. Returns the TTL actually applied.
+    pub async fn set_conversation_ttl(&self, secs: u64) -> u64 {
+        let clamped = secs.max(MIN_CONVERSATION_TTL_SECS);
+        if clamped != secs {
+            warn!(
+                "Conversation TTL {}s too short, clamped to {}s",
+                secs, clamped
+            );
+        }
+        *self.conversation_ttl.write().await = Duration::from_secs(clamped);
+        info!("Conversation TTL set to {}s", clamped);
+        clamped
+    }
+
+    /// Wait for a conversation's earlier turns to finish before taking one.
+    /// Returns `None` for unknown conversations, which fail later anyway.
+    async fn take_turn(
+        &self,
+        id: Uuid,
+        cancel: &CancellationToken,
+    ) -> Result<Option<OwnedSemaphorePermit>, CopilotError> {
+        let Some(turn) = self
+            .conversations
+            .lock()
+            .await
+            .get(&id)
+            .map(|c| Arc::clone(&c.turn))
+        else {
+            return Ok(None);
+        };
+
+        tokio::select! {
+            _ = cancel.cancelled() => Err(CopilotError::Cancelled),
+            permit = turn.acquire_owned() => {
+                Ok(Some(permit.expect("conversation semaphores are never closed")))
+            }
+        }
+    }
+
+    /// Drop conversations that have been idle for longer than the TTL
+    async fn evict_idle_conversations(&self) {
+        let ttl = *self.conversation_ttl.read().await;
+        let mut conversations = self.conversations.lock().await;
+        let before = conversations.len();
+        conversations.retain(|_, c| c.last_used.elapsed() < ttl);
+
+        let evicted = before - conversations.len();
+        if evicted > 0 {
+            info!("Evicted {} idle conversation(s)", evicted);
+        }
+    }
+
+    /// Send a message to Copilot and get a response
+    pub async fn ask(
+        &self,
+        prompt: &str,
+        context: Option<LearningContext>,
+        cancel: &CancellationToken,
+    ) -> Result<AssistantResponse, CopilotError> {
+        let timeout = self.timeout().await;
+        self.ask_within(prompt, context, timeout, cancel).await
+    }
+
+    /// Send a message to Copilot, giving up if no response arrives within
+    /// `timeout`
+    pub async fn ask_within(
+        &self,
+        prompt: &str,
+        context: Option<LearningContext>,
+        timeout: Duration,
+        cancel: &CancellationToken,
+    ) -> Result<AssistantResponse, CopilotError> {
+        self.ask_on(None, prompt, context, timeout, cancel, &mut |_| {})
+            .await
+    }
+
+    /// Send a message and wait for the complete response, for scripts and
+    /// tests that want no streaming. `timeout_secs` applies to this call
+    /// only and is clamped like [`Self::set_timeout`].
+    pub async fn ask_blocking(
+        &self,
+        prompt: &str,
+        context: Option<LearningContext>,
+        timeout_secs: u64,
+        cancel: &CancellationToken,
+    ) -> Result<AssistantResponse, CopilotError> {
+        let timeout = Duration::from_secs(clamp_timeout_secs(timeout_secs));
+        self.ask_within(prompt, context, timeout, cancel).await
+    }
+
+    /// Send a message to Copilot, calling `on_delta` with each chunk of the
+    /// response as it arrives, and return the full response once complete
+    pub async fn ask_streaming<F>(
+        &self,
+        prompt: &str,
+        context: Option<LearningContext>,
+        cancel: &CancellationToken,
+        mut on_delta: F,
+    ) -> Result<AssistantResponse, CopilotError>
+    where
+        F: FnMut(&str) + Send,
+    {
+        let timeout = self.timeout().await;
+        self.ask_on(None, prompt, context, timeout, cancel, &mut on_delta)
+            .await
+    }
+
+    /// Send a message within a conversation, so the assistant remembers
+    /// earlier turns of that conversation
+    pub async fn ask_conversation(
+        &self,
+        conversation: Uuid,
+        prompt: &str,
+        context: Option<LearningContext>,
+        cancel: &CancellationToken,
+    ) -> Result<AssistantResponse, CopilotError> {
+        // Held until the exchange is recorded, so a second message on the
+        // same conversation can't interleave with this one
+        let _turn = self.take_turn(conversation, cancel).await?;
+
+        let timeout = self.timeout().await;
+        let sent_at = Utc::now();
+        let response = self
+            .ask_on(
+                Some(conversation),
+                prompt,
+                context,
+                timeout,
+                cancel,
+                &mut |_| {},
+            )
+            .await?;
+        self.record_exchange(conversation, prompt, sent_at, &response.content)
+            .await;
+        Ok(response)
+    }
+
+    /// Send a message on a conversation's session, or on the shared session
+    /// when `conversation` is `None`, remembering the outcome for diagnostics.
+    ///
+    /// Callers on a conversation take its turn first and record the
+    /// exchange in its transcript themselves.
+    async fn ask_on(
+        &self,
+        conversation: Option<Uuid>,
+        prompt: &str,
+        context: Option<LearningContext>,
+        timeout: Duration,
+        cancel: &CancellationToken,
+        on_delta: &mut (dyn FnMut(&str) + Send),
+    ) -> Result<AssistantResponse, CopilotError> {
+        if self.is_mock() {
+            let content = format!("Mock response to: {}", prompt);
+            on_delta(&content);
+            return Ok(Self::mock_response(content));
+        }
+
+        self.touch().await;
+        let result = self
+            .send_on(conversation, prompt, context, timeout, cancel, on_delta)
+            .await;
+        self.touch().await;
+        self.record_outcome(&result).await;
+        result
+    }
+
+    /// Remember a failed request, or forget the last failure once a
+    /// request succeeds. Cancellations are neither.
+    async fn record_outcome<T>(&self, result: &Result<T, CopilotError>) {
+        let mut last_error = self.last_error.lock().await;
+        match result {
+            Ok(_) => *last_error = None,
+            Err(CopilotError::Cancelled) => {}
+            Err(e) => {
+                *last_error = Some(RecordedError {
+                    category: e.category(),
+                    message: e.to_string(),
+                    at: Utc::now(),
+                })
+            }
+        }
+    }
+
+    /// The most recent request failure, unless a request has succeeded since
+    pub async fn last_error(&self) -> Option<ErrorInfo> {
+        self.last_error.lock().await.as_ref().map(|e| ErrorInfo {
+            category: e.category.to_string(),
+            message: e.message.clone(),
+            occurred_at: e.at,
+            seconds_ago: (Utc::now() - e.at).num_seconds().max(0) as u64,
+        })
+    }
+
+    /// Refuse a request that would reach the CLI during shutdown or beyond
+    /// the rate limit; one that is let through uses up a token
+    async fn admit_request(&self) -> Result<(), CopilotError> {
+        if self.shutting_down.load(Ordering::SeqCst) {
+            return Err(CopilotError::ShuttingDown);
+        }
+        if let Err(retry_after) = self.rate_limiter.lock().await.try_acquire() {
+            warn!("Copilot request rate limited for {:?}", retry_after);
+            return Err(CopilotError::RateLimited { retry_after });
+        }
+        Ok(())
+    }
+
+    /// Send a message without recording the outcome; see [`Self::ask_on`]
+    async fn send_on(
+        &self,
+        conversation: Option<Uuid>,
+        prompt: &str,
+        context: Option<LearningContext>,
+        timeout: Duration,
+        cancel: &CancellationToken,
+        on_delta: &mut (dyn FnMut(&str) + Send),
+    ) -> Result<AssistantResponse, CopilotError> {
+        self.admit_request().await?;
+        // Restarting an idle client can take a while
+        let client = unless_cancelled(cancel, self.client()).await?;
+
+        // Build context-aware prompt
+        let full_prompt = if let Some(ctx) = context {
+            format!(
+                "{}\n\n<current_context>\nLevel: {}\nTarget: {}\nRecent mistakes: {:?}\nAccuracy: {:.0}%\n</current_context>",
+                prompt,
+                ctx.current_level,
+                ctx.current_target.unwrap_or_default(),
+                ctx.recent_mistakes,
+                ctx.accuracy * 100.0
+            )
+        } else {
+            prompt.to_string()
+        };
+
+        // Once text has reached the learner a retry would show it twice
+        let streamed = AtomicBool::new(false);
+        let mut forward = |chunk: &str| {
+            streamed.store(true, Ordering::SeqCst);
+            on_delta(chunk);
+        };
+        let on_delta: Mutex<&mut (dyn FnMut(&str) + Send)> = Mutex::new(&mut forward);
+        let may_retry = || !streamed.load(Ordering::SeqCst);
+        let result = retry_transient(cancel, may_retry, |attempt| {
+            let full_prompt = &full_prompt;
+            let on_delta = &on_delta;
+            let client = &client;
+            async move {
+                match conversation {
+                    Some(id) => {
+                        if attempt > 1 {
+                            // The old session may be what broke; start over on a new one
+                            self.discard_conversation_session(id).await;
+                        }
+                        let session =
+                            unless_cancelled(cancel, self.conversation_session(client, id)).await?;
+                        let mut on_delta = on_delta.lock().await;
+                        self.send_and_collect(
+                            &*session.session,
+                            full_prompt,
+                            cancel,
+                            timeout,
+                            &mut **on_delta,
+                        )
+                        .await
+                    }
+                    // A failed shared session is simply never checked back in,
+                    // so a retry always gets a different one
+                    None => {
+                        let (session, epoch) =
+                            unless_cancelled(cancel, self.checkout_session(client)).await?;
+                        let mut on_delta = on_delta.lock().await;
+                        let result = self
+                            .send_and_collect(
+                                &*session.session,
+                                full_prompt,
+                                cancel,
+                                timeout,
+                                &mut **on_delta,
+                            )
+                            .await;
+                        if result.is_ok() {
+                            self.checkin_session(session, epoch).await;
+                        }
+                        result
+                    }
+                }
+            }
+        })
+        .await;
+
+        if let (Err(_), Some(id)) = (&result, conversation) {
+            // Drop the session so the next request starts on a fresh one
+            debug!("Discarding conversation session after error");
+            self.discard_conversation_session(id).await;
+        }
+        result
+    }
+
+    /// Get a handle to the running client without holding the lock,
+    /// restarting it if it was stopped for being idle or died
+    async fn client(&self) -> Result<Arc<Client>, CopilotError> {
+        if let Some(client) = self.client.lock().await.clone() {
+            return Ok(client);
+        }
+
+        // Both flags are cleared, whichever caused the restart
+        let idle = self.idle_stopped.swap(false, Ordering::SeqCst);
+        let dead = self.terminated.swap(false, Ordering::SeqCst);
+        if idle || dead {
+            info!("Restarting Copilot client");
+            self.start().await?;
+            if let Some(client) = self.client.lock().await.clone() {
+                return Ok(client);
+            }
+        }
+        Err(CopilotError::NotInitialized)
+    }
+
+    /// Forget the session used by a conversation
+    async fn discard_conversation_session(&self, id: Uuid) {
+        if let Some(c) = self.conversations.lock().await.get_mut(&id) {
+            c.session = None;
+        }
+    }
+
+    /// Get a conversation's session, creating it on first use
+    async fn conversation_session(
+        &self,
+        client: &Arc<Client>,
+        id: Uuid,
+    ) -> Result<Arc<TutorSession>, CopilotError> {
+        self.evict_idle_conversations().await;
+
+        {
+            let mut conversations = self.conversations.lock().await;
+            let conversation = conversations
+                .get_mut(&id)
+                .ok_or(CopilotError::UnknownConversation(id))?;
+            conversation.last_used = Instant::now();
+
+            if let Some(session) = conversation.session.as_ref() {
+                debug!("Continuing conversation {}", id);
+                return Ok(Arc::clone(session));
+            }
+        }
+
+        // Create the session without holding the map, so other
+        // conversations are not blocked meanwhile
+        let epoch = self.session_epoch.load(Ordering::SeqCst);
+        let session = self.create_session(client).await?;
+
+        let mut conversations = self.conversations.lock().await;
+        let conversation = conversations
+            .get_mut(&id)
+            .ok_or(CopilotError::UnknownConversation(id))?;
+        // A session created across a reset serves this turn only
+        if self.session_epoch.load(Ordering::SeqCst) != epoch {
+            return Ok(session);
+        }
+        Ok(Arc::clone(
+            conversation.session.get_or_insert(Arc::clone(&session)),
+        ))
+    }
+
+    /// Take the idle shared session, or create one if it is in use or
+    /// has not been created yet.
+    ///
+    /// Returns the session epoch to pass back to `checkin_session`.
+    async fn checkout_session(
+        &self,
+        client: &Arc<Client>,
+    ) -> Result<(Arc<TutorSession>, u64), CopilotError> {
+        let epoch = self.session_epoch.load(Ordering::SeqCst);
+
+        if let Some(session) = self.session.lock().await.take() {
+            debug!("Reusing cached Copilot session");
+            return Ok((session, epoch));
+        }
+
+        Ok((self.create_session(client).await?, epoch))
+    }
+
+    /// Make a healthy session available for reuse, unless the cache was
+    /// reset while it was checked out or another session is already idle
+    async fn checkin_session(&self, session: Arc<TutorSession>, epoch: u64) {
+        let mut cached = self.session.lock().await;
+        if cached.is_none() && self.session_epoch.load(Ordering::SeqCst) == epoch {
+            *cached = Some(session);
+        }
+    }
+
+    /// Create a session with our tutor persona for the active layout
+    async fn create_session(
+        &self,
+        client: &Arc<Client>,
+    ) -> Result<Arc<TutorSession>, CopilotError> {
+        debug!("Creating Copilot session...");
+
+        let layout = self.layout().await;
+        let system_prompt = self.current_system_prompt().await;
+        let config = SessionConfig {
+            model: self.model.read().await.clone(),
+            system_message: Some(SystemMessageConfig {
+                mode: Some(SystemMessageMode::Replace),
+                content: Some(system_prompt.clone()),
+            }),
+            ..Default::default()
+        };
+
+        let tools = session_tools(layout);
+        let names = tools.iter().map(|(tool, _)| tool.name.clone()).collect();
+        let session = (self.session_factory)(Arc::clone(client), config, tools).await?;
+
+        Ok(Arc::new(TutorSession {
+            session,
+            system_prompt,
+            tools: names,
+        }))
+    }
+
+    /// The system prompt new sessions are given, for the active persona
+    /// and layout
+    async fn current_system_prompt(&self) -> String {
+        let layout = *self.layout.read().await;
+        let persona = match self.custom_prompt.read().await.clone() {
+            Some(custom) => custom,
+            None => self.persona.read().await.prompt(layout),
+        };
+        system_prompt(&persona, layout)
+    }
+
+    /// Check the system prompt each open session was created with against
+    /// the active layout and persona, returning one message per problem.
+    ///
+    /// With no open sessions, the prompt new sessions would get is checked.
+    /// Sessions checked out by a running request are skipped.
+    pub async fn validate_prompt(&self) -> Vec<String> {
+        let mut prompts = Vec::new();
+        if let Some(session) = self.session.lock().await.as_ref() {
+            prompts.push(("Shared session".to_string(), session.system_prompt.clone()));
+        }
+        for (id, conversation) in self.conversations.lock().await.iter() {
+            if let Some(session) = &conversation.session {
+                prompts.push((
+                    format!("Conversation {}", id),
+                    session.system_prompt.clone(),
+                ));
+            }
+        }
+
+        let current = self.current_system_prompt().await;
+        if prompts.is_empty() {
+            prompts.push(("New sessions".to_string(), current.clone()));
+        }
+        stored_prompt_problems(&prompts, &current, self.layout().await)
+    }
+
+    /// Get the active keyboard layout
+    pub async fn layout(&self) -> Layout {
+        *self.layout.read().await
+    }
+
+    /// Switch keyboard layout.
+    ///
+    /// Sessions are reset so the tutor, and its lookup tool, pick up the
+    /// new layout's key map.
+    pub async fn set_layout(&self, layout: Layout) {
+        let mut current = self.layout.write().await;
+        if *current == layout {
+            return;
+        }
+        *current = layout;
+        drop(current);
+
+        info!("Keyboard layout set to {}", layout.name());
+        self.reset_session().await;
+    }
+
+    /// Get the learner's physical keyboard
+    pub async fn physical_layout(&self) -> PhysicalLayout {
+        *self.physical_layout.read().await
+    }
+
+    /// Set the learner's physical keyboard.
+    ///
+    /// Only locally computed keys are relabelled; the tutor's key map keeps
+    /// QWERTY positions, so the session is left alone.
+    pub async fn set_physical_layout(&self, physical: PhysicalLayout) {
+        *self.physical_layout.write().await = physical;
+        info!("Physical keyboard set to {}", physical.name());
+    }
+
+    /// Switch to a built-in persona, discarding any custom prompt
+    pub async fn set_persona(&self, persona: Persona) {
+        *self.persona.write().await = persona;
+        self.custom_prompt.write().await.take();
+        info!("Tutor persona set to {:?}", persona);
+        self.reset_session().await;
+    }
+
+    /// Replace the tutor persona with a custom prompt.
+    ///
+    /// The keyboard layout section is still appended. Sessions are reset
+    /// so the new persona takes effect on the next request.
+    pub async fn set_system_prompt(&self, prompt: String) -> Result<(), CopilotError> {
+        validate_system_prompt(&prompt)?;
+
+        *self.custom_prompt.write().await = Some(prompt);
+        info!("Custom tutor persona set");
+        self.reset_session().await;
+        Ok(())
+    }
+
+    /// Get the model used for new sessions, if one was chosen
+    pub async fn model(&self) -> Option<String> {
+        self.model.read().await.clone()
+    }
+
+    /// List the ids of the models the Copilot backend offers
+    pub async fn list_models(&self) -> Result<Vec<String>, CopilotError> {
+        let client = self.client().await?;
+
+        let models = client.list_models().await.map_err(|e| {
+            error!("Failed to list models: {}", e);
+            CopilotError::ListModelsFailed(e.to_string())
+        })?;
+
+        Ok(models.into_iter().map(|m| m.id).collect())
+    }
+
+    /// Switch to another model.
+    ///
+    /// The name is checked against the models the backend reports, and
+    /// sessions are reset so the next request uses the new model.
+    pub async fn set_model(&self, name: &str) -> Result<(), CopilotError> {
+        let available = self.list_models().await?;
+        validate_model(name, &available)?;
+
+        *self.model.write().await = Some(name.to_string());
+        info!("Copilot model set to {}", name);
+        self.reset_session().await;
+        Ok(())
+    }
+
+    /// Tokens consumed since start-up or the last reset
+    pub async fn usage(&self) -> UsageStats {
+        self.usage.lock().await.clone()
+    }
+
+    /// Clear the usage counters
+    pub async fn reset_usage(&self) {
+        *self.usage.lock().await = UsageStats::default();
+    }
+
+    /// Discard the cached session so the next request starts a fresh conversation.
+    ///
+    /// Open conversations keep their transcripts but continue on a new
+    /// session, which has not seen their earlier turns. Cached answers are
+    /// dropped too, since they came from the old persona, model or layout.
+    pub async fn reset_session(&self) {
+        self.session_epoch.fetch_add(1, Ordering::SeqCst);
+        if self.session.lock().await.take().is_some() {
+            info!("Copilot session reset");
+        }
+        for conversation in self.conversations.lock().await.values_mut() {
+            conversation.session = None;
+        }
+        self.response_cache.lock().await.clear();
+    }
+
+    /// Change how many explain/hint answers are remembered (0 disables)
+    pub async fn set_cache_capacity(&self, capacity: usize) {
+        self.response_cache.lock().await.set_capacity(capacity);
+        info!("Response cache capacity set to {}", capacity);
+    }
+
+    /// Warm the response cache with opening hints for upcoming `targets`,
+    /// so [`Self::get_hint`] answers instantly once drilling reaches them.
+    ///
+    /// At most [`PREFETCH_LIMIT`] targets are fetched, concurrently, through
+    /// the rate limiter like any other request. Returns how many hints are
+    /// now cached.
+    pub async fn prefetch_hints(
+        &self,
+        targets: &[String],
+        level: u32,
+        cancel: &CancellationToken,
+    ) -> usize {
+        // Mock hints are computed locally and never cached
+        if self.is_mock() {
+            return 0;
+        }
+
+        let layout = self.layout().await;
+        let prompts = targets
+            .iter()
+            .take(PREFETCH_LIMIT)
+            .map(|target| hint_prompt(target, "", layout, level))
+            .collect();
+        self.prefetch(prompts, |prompt| {
+            let service = self.clone();
+            let cancel = cancel.clone();
+            async move {
+                let timeout = service.hint_timeout().await;
+                service.ask_within(&prompt, None, timeout, &cancel).await
+            }
+        })
+        .await
+    }
+
+    /// Fetch uncached `prompts` concurrently with `fetch` into the cache
+    async fn prefetch<F, Fut>(&self, prompts: Vec<String>, fetch: F) -> usize
+    where
+        F: Fn(String) -> Fut,
+        Fut: Future<Output = Result<AssistantResponse, CopilotError>> + Send + 'static,
+    {
+        let mut tasks = JoinSet::new();
+        for prompt in prompts {
+            let service = self.clone();
+            // Futures are lazy, so nothing is sent if the prompt is cached
+            let request = fetch(prompt.clone());
+            tasks.spawn(async move { service.cached(&prompt, || request).await });
+        }
+
+        let mut warmed = 0;
+        while let Some(result) = tasks.join_next().await {
+            match result {
+                Ok(Ok(_)) => warmed += 1,
+                Ok(Err(e)) => debug!("Hint prefetch skipped: {}", e),
+                Err(e) => warn!("Hint prefetch task failed: {}", e),
+            }
+        }
+        warmed
+    }
+
+    /// Return a cached answer for `prompt`, or `fetch` one and remember it.
+    ///
+    /// Answers are only stored if the session was not reset while fetching,
+    /// so a persona or model change can't be undone by a slow request.
+    async fn cached<F, Fut>(
+        &self,
+        prompt: &str,
+        fetch: F,
+    ) -> Result<AssistantResponse, CopilotError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<AssistantResponse, CopilotError>>,
+    {
+        let key = normalize_prompt(prompt);
+        if let Some(hit) = self.response_cache.lock().await.get(&key) {
+            debug!("Response cache hit");
+            return Ok(AssistantResponse {
+                content: hit.content.clone(),
+                tool_used: Some(CACHE_TOOL.to_string()),
+            });
+        }
+
+        let epoch = self.session_epoch.load(Ordering::SeqCst);
+        let response = fetch().await?;
+        if self.session_epoch.load(Ordering::SeqCst) == epoch {
+            self.response_cache
+                .lock()
+                .await
+                .insert(key, response.clone());
+        }
+        Ok(response)
+    }
+
+    /// Send a prompt on a session and collect the full response
+    async fn send_and_collect(
+        &self,
+        session: &dyn ChatSession,
+        full_prompt: &str,
+        cancel: &CancellationToken,
+        timeout: Duration,
+        on_delta: &mut (dyn FnMut(&str) + Send),
+    ) -> Result<AssistantResponse, CopilotError> {
+        // Held until the whole response has arrived
+        let _slot = self.acquire_slot(cancel).await?;
+
+        debug!("Subscribing to session events...");
+
+        // Subscribe BEFORE sending to not miss any events
+        let mut events = session.subscribe();
+
+        debug!("Sending message ({} chars)...", full_prompt.len());
+
+        // Send the message
+        let message_id = session.send(full_prompt).await.map_err(|e| {
+            error!("Failed to send message: {}", e);
+            CopilotError::SendFailed(e)
+        })?;
+
+        debug!("Message sent (id={}), waiting for response...", message_id);
+
+        let response = match collect_response(&mut events, timeout, cancel, on_delta).await {
+            Err(CopilotError::StartFailed(reason)) => {
+                self.client_terminated().await;
+                return Err(CopilotError::StartFailed(reason));
+            }
+            result => result?,
+        };
+
+        info!("Copilot response: {} chars", response.content.len());
+        self.usage
+            .lock()
+            .await
+            .record_estimated(full_prompt, &response.content);
+
+        Ok(response)
+    }
+
+    /// Send a trivial prompt through the shared session and time the reply.
+    ///
+    /// Uses a short fixed timeout so an unresponsive backend is reported
+    /// quickly rather than after the full response timeout.
+    pub async fn ping(&self) -> PingResult {
+        let started = Instant::now();
+
+        let result = async {
+            // A real prompt, so it counts like any other request
+            self.admit_request().await?;
+            let client = self.client().await?;
+            let (session, epoch) = self.checkout_session(&client).await?;
+            self.send_and_collect(
+                &*session.session,
+                PING_PROMPT,
+                &CancellationToken::new(),
+                PING_TIMEOUT,
+                &mut |_| {},
+            )
+            .await?;
+            self.checkin_session(session, epoch).await;
+            Ok::<_, CopilotError>(())
+        }
+        .await;
+
+        match result {
+            Ok(_) => {
+                let latency_ms = started.elapsed().as_millis() as u64;
+                debug!("Copilot ping OK in {}ms", latency_ms);
+                PingResult {
+                    ok: true,
+                    latency_ms: Some(latency_ms),
+                    error: None,
+                }
+            }
+            Err(e) => {
+                warn!("Copilot ping failed: {}", e);
+                PingResult {
+                    ok: false,
+                    latency_ms: None,
+                    error: Some(e.to_string()),
+                }
+            }
+        }
+    }
+
+    /// Get a hint for the current typing target
+    pub async fn get_hint(
+        &self,
+        target: &str,
+        user_input: &str,
+        level: u32,
+        cancel: &CancellationToken,
+    ) -> Result<AssistantResponse, CopilotError> {
+        let layout = self.layout().await;
+        let physical = self.physical_layout().await;
+        if self.is_mock() {
+            let local = hint::local_hint(layout, physical, target, user_input);
+            return Ok(Self::mock_response(local.content));
+        }
+
+        // Missing half of a compound vowel has one right answer; no need to ask
+        if let Some(content) = hint::compound_vowel_hint(layout, physical, target, user_input) {
+            return Ok(AssistantResponse {
+                content,
+                tool_used: Some(hint::LOCAL_TOOL.to_string()),
+            });
+        }
+
+        let prompt = hint_prompt(target, user_input, layout, level);
+        let timeout = self.hint_timeout().await;
+        let response = self
+            .cached(&prompt, || self.ask_within(&prompt, None, timeout, cancel))
+            .await?;
+        Ok(Self::check_keys(response, target, layout, physical))
+    }
+
+    /// Append corrections for any key the response names wrongly
+    fn check_keys(
+        response: AssistantResponse,
+        target: &str,
+        layout: Layout,
+        physical: PhysicalLayout,
+    ) -> AssistantResponse {
+        let content = keycheck::with_corrections(&response.content, target, layout, physical);
+        if content != response.content {
+            warn!("Corrected a wrong key claim in Copilot's answer");
+        }
+        AssistantResponse {
+            content,
+            ..response
+        }
+    }
+
+    /// Explain a specific jamo or syllable
+    pub async fn explain(
+        &self,
+        text: &str,
+        cancel: &CancellationToken,
+    ) -> Result<AssistantResponse, CopilotError> {
+        if self.is_mock() {
+            return Ok(Self::mock_response(self.mock_explanation(text).await));
+        }
+
+        let layout = self.layout().await;
+        let prompt = format!(
+            "Explain the Korean character or word \"{}\": what it is, how to pronounce it (romanization), and exactly which English keys to press to type it on a {} keyboard.",
+            text,
+            layout.name()
+        );
+
+        let response = self
+            .cached(&prompt, || self.ask(&prompt, None, cancel))
+            .await?;
+        Ok(Self::check_keys(
+            response,
+            text,
+            layout,
+            self.physical_layout().await,
+        ))
+    }
+
+    /// Explain the difference between two easily confused syllables.
+    ///
+    /// The prompt states exactly which jamo differ, worked out locally, so
+    /// the explanation starts from the real difference. `None` unless both
+    /// are syllable blocks.
+    pub async fn compare(
+        &self,
+        a: char,
+        b: char,
+        cancel: &CancellationToken,
+    ) -> Option<Result<AssistantResponse, CopilotError>> {
+        let layout = self.layout().await;
+        let physical = self.physical_layout().await;
+        if self.is_mock() {
+            let local = explain::local_comparison(layout, physical, a, b)?;
+            return Some(Ok(Self::mock_response(local.content)));
+        }
+
+        let prompt = format!(
+            "A learner keeps confusing the Korean syllables \"{}\" and \"{}\". {} Explain how to hear and say the difference, and which keys differ when typing them on a {} keyboard.",
+            a,
+            b,
+            explain::describe_difference(a, b)?,
+            layout.name()
+        );
+        let result = self
+            .cached(&prompt, || self.ask(&prompt, None, cancel))
+            .await
+            .map(|response| Self::check_keys(response, &format!("{}{}", a, b), layout, physical));
+        Some(result)
+    }
+
+    /// Describe each syllable's romanization and keys, for mock mode
+    async fn mock_explanation(&self, text: &str) -> String {
+        let physical = self.physical_layout().await;
+        let syllables = match explain::breakdown(text, self.layout().await) {
+            Ok(syllables) => syllables,
+            Err(e) => return e.to_string(),
+        };
+        let lines: Vec<String> = syllables
+            .into_iter()
+            .map(|s| {
+                let keys: Vec<String> = physical
+                    .translate_all(&s.keystrokes)
+                    .iter()
+                    .map(|k| k.to_string())
+                    .collect();
+                format!(
+                    "{} ({}): press {}",
+                    s.syllable,
+                    s.romanization,
+                    keys.join(", ")
+                )
+            })
+            .collect();
+
+        if lines.is_empty() {
+            format!("\"{}\" has no Hangul syllables to explain.", text)
+        } else {
+            lines.join("\n")
+        }
+    }
+
+    /// Explain several characters or words with a single request.
+    ///
+    /// The reply is split on [`BATCH_DELIMITER`]; if it doesn't come back
+    /// with one entry per text, each text is explained separately instead.
+    pub async fn explain_batch(
+        &self,
+        texts: &[String],
+        cancel: &CancellationToken,
+    ) -> Result<Vec<AssistantResponse>, CopilotError> {
+        if texts.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let list: Vec<String> = texts
+            .iter()
+            .enumerate()
+            .map(|(i, text)| format!("{}. \"{}\"", i + 1, text))
+            .collect();
+        let prompt = format!(
+            "Explain each of these {} Korean characters or words in order: what it is, how to pronounce it (romanization), and exactly which English keys to press to type it on a {} keyboard.\n{}\n\nKeep each explanation brief. Put a line containing only {} between explanations, and nothing else around them.",
+            texts.len(),
+            self.layout().await.name(),
+            list.join("\n"),
+            BATCH_DELIMITER
+        );
+
+        let response = self.ask(&prompt, None, cancel).await?;
+        if let Some(entries) = split_batch_response(&response.content, texts.len()) {
+            return Ok(entries
+                .into_iter()
+                .map(|content| AssistantResponse {
+                    content,
+                    tool_used: response.tool_used.clone(),
+                })
+                .collect());
+        }
+
+        warn!("Batch explain reply was malformed, explaining one at a time");
+        let mut responses = Vec::with_capacity(texts.len());
+        for text in texts {
+            responses.push(self.explain(text, cancel).await?);
+        }
+        Ok(responses)
+    }
+
+    /// Ask for a short practice sentence about `topic` using only the jamo
+    /// unlocked at `level`.
+    ///
+    /// The reply is checked locally; a sentence with locked jamo is retried
+    /// once, naming the jamo to avoid.
+    pub async fn generate_sentence(
+        &self,
+        topic: &str,
+        level: u32,
+        cancel: &CancellationToken,
+    ) -> Result<String, CopilotError> {
+        if self.is_mock() {
+            return Ok(lesson::generate_targets(level, 3, Some(MOCK_SEED)).join(" "));
+        }
+
+        let unlocked = lesson::unlocked_jamo(level);
+        let list = |jamo: &[char]| {
+            jamo.iter()
+                .map(char::to_string)
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        let prompt = format!(
+            "Write one short, natural Korean sentence about \"{}\" for a beginner. Use only syllables built from these jamo: initial consonants {}; vowels {}; final consonants {}. Reply with the sentence only, no translation or romanization.",
+            topic,
+            list(&unlocked.initials),
+            list(&unlocked.medials),
+            if unlocked.finals.is_empty() {
+                "none (open syllables only)".to_string()
+            } else {
+                list(&unlocked.finals)
+            }
+        );
+
+        validated_sentence(&unlocked, &prompt, |prompt| async move {
+            self.ask(&prompt, None, cancel).await
+        })
+        .await
+    }
+
+    /// Start a roleplay of `scenario` (e.g. "ordering coffee") in Korean
+    /// held to the jamo unlocked at `level`.
+    ///
+    /// The scenario runs in its own conversation, so later turns remember
+    /// earlier ones. Lines with locked jamo are regenerated once.
+    pub async fn start_scenario(
+        &self,
+        scenario: &str,
+        level: u32,
+        cancel: &CancellationToken,
+    ) -> Result<ScenarioTurn, CopilotError> {
+        let id = self.open_conversation(Some(level)).await;
+        let prompt = format!(
+            "Let's roleplay \"{}\" in Korean. You play the other person and speak first. {}",
+            scenario,
+            scenario_rules(&lesson::unlocked_jamo(level))
+        );
+        let result = self
+            .scenario_turn(id, level, scenario, &prompt, cancel)
+            .await;
+        if result.is_err() {
+            self.end_conversation(id).await;
+        }
+        result
+    }
+
+    /// Continue a scenario with what the learner typed
+    pub async fn scenario_reply(
+        &self,
+        conversation: Uuid,
+        reply: &str,
+        cancel: &CancellationToken,
+    ) -> Result<ScenarioTurn, CopilotError> {
+        let level = self
+            .conversations
+            .lock()
+            .await
+            .get(&conversation)
+            .and_then(|c| c.scenario_level)
+            .ok_or(CopilotError::UnknownConversation(conversation))?;
+        let prompt = format!(
+            "The student replied: \"{}\". Stay in character and continue. {}",
+            reply,
+            scenario_rules(&lesson::unlocked_jamo(level))
+        );
+        self.scenario_turn(conversation, level, reply, &prompt, cancel)
+            .await
+    }
+
+    /// Take a scenario turn with `prompt`, recording it in the transcript
+    /// as the learner saw it: what they `said`, then the character's line
+    async fn scenario_turn(
+        &self,
+        conversation: Uuid,
+        level: u32,
+        said: &str,
+        prompt: &str,
+        cancel: &CancellationToken,
+    ) -> Result<ScenarioTurn, CopilotError> {
+        // Held across retries, so the turn is recorded as one exchange
+        let _turn = self.take_turn(conversation, cancel).await?;
+
+        let sent_at = Utc::now();
+        let (line, suggested_reply) = if self.is_mock() {
+            let mut lines = lesson::generate_targets(level, 4, Some(MOCK_SEED));
+            let reply = lines.split_off(2);
+            (lines.join(" "), reply.join(" "))
+        } else {
+            let timeout = self.timeout().await;
+            validated_turn(&lesson::unlocked_jamo(level), prompt, |prompt| async move {
+                self.ask_on(
+                    Some(conversation),
+                    &prompt,
+                    None,
+                    timeout,
+                    cancel,
+                    &mut |_| {},
+                )
+                .await
+            })
+            .await?
+        };
+        self.record_exchange(conversation, said, sent_at, &line)
+            .await;
+
+        let keys = self.layout().await.keystrokes_for_text(&suggested_reply);
+        Ok(ScenarioTurn {
+            conversation_id: conversation,
+            line,
+            suggested_reply,
+            keystrokes: self.physical_layout().await.translate_all(&keys),
+        })
+    }
+
+    /// Pose a "which keys type this?" question about a syllable from `level`.
+    ///
+    /// Copilot words the question when it is running; otherwise, or if it
+    /// fails, a plain local wording is used.
+    pub async fn quiz(
+        &self,
+        level: u32,
+        cancel: &CancellationToken,
+    ) -> Result<QuizQuestion, CopilotError> {
+        let seed = self.is_mock().then_some(MOCK_SEED);
+        let target = lesson::generate_targets(level, 1, seed)
+            .pop()
+            .unwrap_or_else(|| "가".to_string());
+
+        let question = if self.is_mock() || !self.can_serve().await {
+            quiz::local_question(&target)
+        } else {
+            let prompt = format!(
+                "Write one short, friendly quiz question asking a student which keys type \"{}\" on a {} keyboard. Don't give the answer. Reply with the question only.",
+                target,
+                self.layout().await.name()
+            );
+            match self.ask(&prompt, None, cancel).await {
+                Ok(response) if response.content.contains(target.as_str()) => {
+                    response.content.trim().to_string()
+                }
+                Ok(_) => quiz::local_question(&target),
+                Err(CopilotError::Cancelled) => return Err(CopilotError::Cancelled),
+                Err(e) => {
+                    warn!("Quiz question fell back to local wording: {}", e);
+                    quiz::local_question(&target)
+                }
+            }
+        };
+
+        let id = Uuid::new_v4();
+        self.quizzes.lock().await.insert(id, target.clone());
+        Ok(QuizQuestion {
+            id,
+            question,
+            target,
+        })
+    }
+
+    /// Grade an answer to a quiz question.
+    ///
+    /// Key sequences and typed Hangul are graded locally. Only free-form
+    /// answers are sent to Copilot, which is told the right keys.
+    pub async fn check_answer(
+        &self,
+        id: Uuid,
+        answer: &str,
+        cancel: &CancellationToken,
+    ) -> Result<QuizResult, CopilotError> {
+        let target = self
+            .quizzes
+            .lock()
+            .await
+            .get(&id)
+            .cloned()
+            .ok_or(CopilotError::UnknownQuestion(id))?;
+        let layout = self.layout().await;
+        let physical = self.physical_layout().await;
+
+        if let Some(result) = quiz::grade_locally(answer, &target, layout, physical) {
+            return Ok(result);
+        }
+
+        let expected = physical.translate_all(&layout.keystrokes_for_text(&target));
+        let keys: Vec<String> = expected.iter().map(|k| k.to_string()).collect();
+        if self.is_mock() || !self.can_serve().await {
+            return Ok(QuizResult {
+                correct: false,
+                expected,
+                feedback: format!(
+                    "Answer with the keys, like \"{}\", or type {} itself.",
+                    keys.join(" "),
+                    target
+                ),
+            });
+        }
+
+        let prompt = format!(
+            "A student was asked which keys type \"{}\" on a {} keyboard. The correct keys are {}. They answered: \"{}\". Reply with CORRECT or INCORRECT on the first line, then one sentence of feedback.",
+            target,
+            layout.name(),
+            keys.join(", "),
+            answer
+        );
+        let response = self.ask(&prompt, None, cancel).await?;
+        let (verdict, feedback) = response
+            .content
+            .trim()
+            .split_once('\n')
+            .unwrap_or((response.content.trim(), ""));
+        Ok(QuizResult {
+            correct: verdict.trim().eq_ignore_ascii_case("correct"),
+            expected,
+            feedback: feedback.trim().to_string(),
+        })
+    }
+
+    /// Describe how a whole word is pronounced, including sound changes
+    /// between its syllables
+    pub async fn pronunciation_note(
+        &self,
+        text: &str,
+        cancel: &CancellationToken,
+    ) -> Result<AssistantResponse, CopilotError> {
+        let prompt = format!(
+            "In one or two sentences, describe how \"{}\" is pronounced as a whole word, mentioning any sound changes between syllables. Don't explain individual keys.",
+            text
+        );
+
+        self.ask(&prompt, None, cancel).await
+    }
+
+    /// Analyze a typing mistake
+    pub async fn analyze_mistake(
+        &self,
+        expected: &str,
+        actual: &str,
+        cancel: &CancellationToken,
+    ) -> Result<AssistantResponse, CopilotError> {
+        let mut prompt = format!(
+            "The student tried to type \"{}\" but typed \"{}\". Briefly explain what went wrong and how to fix it.",
+            expected, actual
+        );
+        if jamo::contains_orphan_jamo(actual) && !jamo::contains_orphan_jamo(expected) {
+            // Separate jamo usually mean the IME wasn't composing, not a wrong key
+            prompt.push_str(" Note: their characters didn't combine into syllable blocks; they typed separate jamo instead of composed syllables.");
+        }
+
+        self.ask(&prompt, None, cancel).await
+    }
+}
+
+/// Check that a model name is one of the available models
+fn validate_model(name: &str, available: &[String]) -> Result<(), CopilotError> {
+    if available.iter().any(|m| m == name) {
+        Ok(())
+    } else {
+        Err(CopilotError::UnknownModel(name.to_string()))
+    }
+}
+
+/// Run `attempt` until it succeeds, fails permanently, or runs out of tries.
+///
+/// Only transient errors are retried, and only while `may_retry` allows it,
+/// with exponential backoff between attempts. `attempt` receives the 1-based
+/// attempt number. Cancelling during a backoff returns `Cancelled`
+/// immediately.
+async fn retry_transient<T, F, Fut>(
+    cancel: &CancellationToken,
+    may_retry: impl Fn() -> bool,
+    mut attempt: F,
+) -> Result<T, CopilotError>
+where
+    F: FnMut(u32) -> Fut,
+    Fut: Future<Output = Result<T, CopilotError>>,
+{
+    let mut n = 1;
+    loop {
+        match attempt(n).await {
+            Err(e) if e.is_transient() && n < MAX_ATTEMPTS && may_retry() => {
+                let delay = RETRY_BASE_DELAY * 2u32.pow(n - 1);
+                warn!(
+                    "Copilot attempt {} failed ({}), retrying in {}ms",
+                    n,
+                    e,
+                    delay.as_millis()
+                );
+                tokio::select! {
+                    _ = cancel.cancelled() => return Err(CopilotError::Cancelled),
+                    _ = tokio::time::sleep(delay) => {}
+                }
+                n += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Run `work`, giving up with `Cancelled` as soon as `cancel` fires
+async fn unless_cancelled<T>(
+    cancel: &CancellationToken,
+    work: impl Future<Output = Result<T, CopilotError>>,
+) -> Result<T, CopilotError> {
+    tokio::select! {
+        _ = cancel.cancelled() => Err(CopilotError::Cancelled),
+        result = work => result,
+    }
+}
+
+/// Whether mock mode was requested through [`MOCK_ENV_VAR`]
+fn mock_from_env() -> bool {
+    std::env::var(MOCK_ENV_VAR)
+        .map(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true"))
+        .unwrap_or(false)
+}
+
+/// Split a batch explain reply into `expected` entries.
+///
+/// Returns `None` if the reply doesn't contain exactly that many non-empty
+/// entries, since entries can't then be matched to their texts.
+fn split_batch_response(content: &str, expected: usize) -> Option<Vec<String>> {
+    let mut entries = vec![String::new()];
+    for line in content.lines() {
+        if line.trim() == BATCH_DELIMITER {
+            entries.push(String::new());
+        } else {
+            let entry = entries.last_mut().expect("starts with one entry");
+            entry.push_str(line);
+            entry.push('\n');
+        }
+    }
+
+    let entries: Vec<String> = entries
+        .iter()
+        .map(|e| e.trim().to_string())
+        .filter(|e| !e.is_empty())
+        .collect();
+    (entries.len() == expected).then_some(entries)
+}
+
+/// First non-empty line of a reply, without surrounding quotes
+fn clean_sentence(content: &str) -> String {
+    let line = content.lines().map(str::trim).find(|l| !l.is_empty());
+    let quotes: &[char] = &['"', '\'', '“', '”'];
+    jamo::normalize(line.unwrap_or_default().trim_matches(quotes).trim())
+}
+
+/// Prompt asking for a hint; shared by hints and prefetching so both use
+/// the same cache entry
+fn hint_prompt(target: &str, user_input: &str, layout: Layout, level: u32) -> String {
+    format!(
+        "The student is trying to type \"{}\" but typed \"{}\" on a {} keyboard. They are on level {}. Give a brief, encouraging hint about which key to press next. Don't give away the full answer.",
+        target,
+        user_input,
+        layout.name(),
+        level
+    )
+}
+
+/// Why `sentence` can't be given to a learner who knows only `unlocked`
+/// jamo, along with any locked jamo it uses
+fn sentence_problem(unlocked: &UnlockedJamo, sentence: &str) -> Option<(String, Vec<char>)> {
+    let locked = unlocked.locked_in(sentence);
+    if !sentence.chars().any(|c| jamo::decompose(c).is_some()) {
+        Some((
+            format!("\"{}\" contains no Hangul syllables", sentence),
+            locked,
+        ))
+    } else if let Err(e) = jamo::reject_hanja(sentence) {
+        Some((e.to_string(), locked))
+    } else if locked.is_empty() {
+        None
+    } else {
+        let jamo: Vec<String> = locked.iter().map(char::to_string).collect();
+        Some((
+            format!("\"{}\" uses locked jamo {}", sentence, jamo.join(" ")),
+            locked,
+        ))
+    }
+}
+
+/// `prompt` with a reminder not to use `locked` jamo
+fn avoid_locked(prompt: &str, locked: &[char]) -> String {
+    let jamo: Vec<String> = locked.iter().map(char::to_string).collect();
+    format!(
+        "{}\n\nDon't use {}; the student hasn't learned them yet.",
+        prompt,
+        jamo.join(" ")
+    )
+}
+
+/// Instructions that keep a scenario turn within `unlocked` jamo
+fn scenario_rules(unlocked: &UnlockedJamo) -> String {
+    let list = |jamo: &[char]| {
+        jamo.iter()
+            .map(char::to_string)
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    format!(
+        "Reply with exactly two lines and nothing else: your next short line, then a short reply the student could type. Use only syllables built from these jamo: initial consonants {}; vowels {}; final consonants {}. No translation or romanization.",
+        list(&unlocked.initials),
+        list(&unlocked.medials),
+        if unlocked.finals.is_empty() {
+            "none (open syllables only)".to_string()
+        } else {
+            list(&unlocked.finals)
+        }
+    )
+}
+
+/// Fetch a scenario turn with `fetch` until both its line and suggested
+/// reply only use `unlocked` jamo.
+///
+/// Like [`validated_sentence`], gives up after [`SENTENCE_ATTEMPTS`].
+async fn validated_turn<F, Fut>(
+    unlocked: &UnlockedJamo,
+    prompt: &str,
+    mut fetch: F,
+) -> Result<(String, String), CopilotError>
+where
+    F: FnMut(String) -> Fut,
+    Fut: Future<Output = Result<AssistantResponse, CopilotError>>,
+{
+    let mut request = prompt.to_string();
+    let mut problem = String::new();
+    for attempt in 1..=SENTENCE_ATTEMPTS {
+        let content = fetch(request.clone()).await?.content;
+        let lines: Vec<String> = content
+            .lines()
+            .map(clean_sentence)
+            .filter(|l| !l.is_empty())
+            .collect();
+
+        if let [line, reply, ..] = &lines[..] {
+            match sentence_problem(unlocked, line).or_else(|| sentence_problem(unlocked, reply)) {
+                None => return Ok((line.clone(), reply.clone())),
+                Some((p, locked)) => {
+                    problem = p;
+                    if !locked.is_empty() {
+                        request = avoid_locked(prompt, &locked);
+                    }
+                }
+            }
+        } else {
+            problem = format!("expected a line and a reply, got \"{}\"", content.trim());
+        }
+        warn!("Scenario turn attempt {} rejected: {}", attempt, problem);
+    }
+    Err(CopilotError::InvalidSentence(problem))
+}
+
+/// Fetch a sentence with `fetch` until it only uses `unlocked` jamo.
+///
+/// Gives up after [`SENTENCE_ATTEMPTS`]; each retry tells the model which
+/// jamo it used that the learner hasn't been taught.
+async fn validated_sentence<F, Fut>(
+    unlocked: &UnlockedJamo,
+    prompt: &str,
+    mut fetch: F,
+) -> Result<String, CopilotError>
+where
+    F: FnMut(String) -> Fut,
+    Fut: Future<Output = Result<AssistantResponse, CopilotError>>,
+{
+    let mut request = prompt.to_string();
+    let mut problem = String::new();
+    for attempt in 1..=SENTENCE_ATTEMPTS {
+        let sentence = clean_sentence(&fetch(request.clone()).await?.content);
+        match sentence_problem(unlocked, &sentence) {
+            None => return Ok(sentence),
+            Some((p, locked)) => {
+                problem = p;
+                if !locked.is_empty() {
+                    request = avoid_locked(prompt, &locked);
+                }
+            }
+        }
+        warn!(
+            "Practice sentence attempt {} rejected: {}",
+            attempt, problem
+        );
+    }
+    Err(CopilotError::InvalidSentence(problem))
+}
+
+/// Cache key for a prompt: trimmed, lowercased, with whitespace collapsed
+fn normalize_prompt(prompt: &str) -> String {
+    prompt
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Rough token count for `text`.
+///
+/// English averages about four characters per token; Hangul syllables
+/// usually take a token each, so they are counted one-to-one.
+fn estimate_tokens(text: &str) -> u64 {
+    let (hangul, other) = text.chars().fold((0u64, 0u64), |(hangul, other), c| {
+        if jamo::is_complete_syllable(c) || jamo::is_orphan_jamo(c) {
+            (hangul + 1, other)
+        } else {
+            (hangul, other + 1)
+        }
+    });
+    hangul + other.div_ceil(CHARS_PER_TOKEN)
+}
+
+/// Build the full system prompt from a persona and the active layout.
+///
+/// The layout's key map is always appended so the tutor's key claims stay
+/// accurate whichever persona is in use.
+fn system_prompt(persona: &str, layout: Layout) -> String {
+    format!(
+        r#"{persona}
+
+<keyboard_layout>
+{layout}
+</keyboard_layout>
+
+When the user asks about typing a character or word, explain which English keys to press in order."#,
+        layout = layout.prompt_section(),
+    )
+}
+
+/// Mappings in a prompt's `<keyboard_layout>` block that `layout` disagrees
+/// with.
+///
+/// Reads both "ㄱ(r)" and "ㄲ=Shift+r" forms; a jamo's keys may match any
+/// position it can be typed in. Returns one message per wrong mapping, and
+/// one if the block is missing.
+fn prompt_layout_problems(prompt: &str, layout: Layout) -> Vec<String> {
+    let block = prompt
+        .split_once("<keyboard_layout>")
+        .and_then(|(_, rest)| rest.split_once("</keyboard_layout>"))
+        .map(|(block, _)| block);
+    let Some(block) = block else {
+        return vec!["The prompt has no <keyboard_layout> block".to_string()];
+    };
+
+    let key_list = |keys: &[KeyPress]| {
+        keys.iter()
+            .map(KeyPress::to_string)
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    let mut problems = Vec::new();
+    let chars: Vec<char> = block.chars().collect();
+    for (i, &jamo) in chars.iter().enumerate() {
+        if !jamo::is_consonant(jamo) && !jamo::is_vowel(jamo) {
+            continue;
+        }
+        let spec: String = match chars.get(i + 1) {
+            Some('(') => chars[i + 2..].iter().take_while(|&&c| c != ')').collect(),
+            Some('=') => chars[i + 2..]
+                .iter()
+                .take_while(|&&c| !matches!(c, ',' | ')' | '\n'))
+                .collect(),
+            _ => continue,
+        };
+        let Some(claimed) = parse_key_spec(&spec) else {
+            continue;
+        };
+
+        let expected: Vec<Vec<KeyPress>> = [Position::Initial, Position::Medial, Position::Final]
+            .into_iter()
+            .filter_map(|p| layout.jamo_to_keys_at(jamo, p))
+            .collect();
+        if !expected.contains(&claimed) {
+            problems.push(match expected.first() {
+                Some(keys) => format!(
+                    "{} is mapped to {} in the prompt, but {} types it with {}",
+                    jamo,
+                    key_list(&claimed),
+                    layout.name(),
+                    key_list(keys)
+                ),
+                None => format!(
+                    "{} is mapped to {} in the prompt, but {} has no keys for it",
+                    jamo,
+                    key_list(&claimed),
+                    layout.name()
+                ),
+            });
+        }
+    }
+    problems
+}
+
+/// Problems with the system prompts sessions were created with, each
+/// labelled with its session.
+///
+/// A prompt is reported when its key map disagrees with `layout`, or when
+/// it differs from `current`, the prompt a new session would be given.
+fn stored_prompt_problems(
+    prompts: &[(String, String)],
+    current: &str,
+    layout: Layout,
+) -> Vec<String> {
+    let mut problems = Vec::new();
+    for (label, prompt) in prompts {
+        if prompt != current {
+            problems.push(format!(
+                "{} was created with an outdated system prompt",
+                label
+            ));
+        }
+        problems.extend(
+            prompt_layout_problems(prompt, layout)
+                .into_iter()
+                .map(|problem| format!("{}: {}", label, problem)),
+        );
+    }
+    problems
+}
+
+/// Read keys written as in the prompt ("r", "Shift+r", "k k"), or `None`
+/// if the text isn't keys
+fn parse_key_spec(spec: &str) -> Option<Vec<KeyPress>> {
+    let keys: Option<Vec<KeyPress>> = spec
+        .split_whitespace()
+        .map(|word| {
+            let (key, shift) = match word.strip_prefix("Shift+") {
+                Some(key) => (key, true),
+                None => (word, false),
+            };
+            let mut chars = key.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) if c.is_ascii_graphic() => Some(KeyPress { key: c, shift }),
+                _ => None,
+            }
+        })
+        .collect();
+    keys.filter(|keys| !keys.is_empty())
+}
+
+/// Definition of the [`LOOKUP_TOOL`] offered to every session
+fn lookup_tool() -> Tool {
+    Tool::new(LOOKUP_TOOL)
+        .description(
+            "Look up the exact keys to press and the Revised Romanization for Korean \
+             text on the learner's keyboard layout. Use this instead of guessing keys.",
+        )
+        .schema(json!({
+            "type": "object",
+            "properties": {
+                "text": {
+                    "type": "string",
+                    "description": "Hangul syllables or jamo to look up",
+                },
+            },
+            "required": ["text"],
+        }))
+}
+
+/// Tools registered on every new session, with their handlers.
+///
+/// Handlers answer for `layout`, the one the session's system prompt
+/// describes; every session is replaced when the layout changes.
+fn session_tools(layout: Layout) -> Vec<(Tool, ToolHandler)> {
+    let lookup: ToolHandler = Arc::new(move |_name: &str, arguments: &Value| {
+        ToolResultObject::text(hangul_lookup(layout, arguments))
+    });
+    vec![(lookup_tool(), lookup)]
+}
+
+/// Answer a [`LOOKUP_TOOL`] call with the keys (QWERTY positions, as in the
+/// system prompt) and romanization of the requested text, as JSON
+fn hangul_lookup(layout: Layout, arguments: &Value) -> String {
+    let Some(text) = arguments.get("text").and_then(Value::as_str) else {
+        return json!({ "error": "missing \"text\" argument" }).to_string();
+    };
+    let text = jamo::normalize(text.trim());
+    let keys: Vec<String> = layout
+        .keystrokes_for_text(&text)
+        .iter()
+        .filter(|k| k.key != ' ')
+        .map(KeyPress::to_string)
+        .collect();
+    if keys.is_empty() {
+        return json!({ "error": format!("\"{}\" has no Hangul to look up", text) }).to_string();
+    }
+    json!({
+        "text": text,
+        "romanization": romanize::revised(&text),
+        "keys": keys,
+    })
+    .to_string()
+}
+
+/// Check a custom persona prompt is usable
+fn validate_system_prompt(prompt: &str) -> Result<(), CopilotError> {
+    if prompt.trim().is_empty() {
+        return Err(CopilotError::InvalidPrompt(
+            "prompt must not be empty".to_string(),
+        ));
+    }
+    let len = prompt.chars().count();
+    if len > MAX_SYSTEM_PROMPT_CHARS {
+        return Err(CopilotError::InvalidPrompt(format!(
+            "prompt is {} characters, the limit is {}",
+            len, MAX_SYSTEM_PROMPT_CHARS
+        )));
+    }
+    Ok(())
+}
+
+/// Collect a response from session events until the session goes idle.
+///
+/// Each chunk is passed to `on_delta` as it arrives, and the last tool the
+/// model called is reported in `tool_used`. Gives up with
+/// `Timeout` if no event arrives within `timeout`, or with `Cancelled` as
+/// soon as `cancel` fires.
+async fn collect_response(
+    events: &mut broadcast::Receiver<SessionEvent>,
+    timeout: Duration,
+    cancel: &CancellationToken,
+    on_delta: &mut (dyn FnMut(&str) + Send),
+) -> Result<AssistantResponse, CopilotError> {
+    let mut response_content = String::new();
+    let mut tool_used = None;
+
+    loop {
+        let received = tokio::select! {
+            _ = cancel.cancelled() => {
+                info!("Copilot request cancelled");
+                return Err(CopilotError::Cancelled);
+            }
+            received = tokio::time::timeout(timeout, events.recv()) => received,
+        };
+
+        match received {
+            Ok(Ok(event)) => {
+                debug!("Event: {:?}", std::mem::discriminant(&event.data));
+                match &event.data {
+                    SessionEventData::AssistantMessageDelta(delta) => {
+                        debug!("Delta: +{} chars", delta.delta_content.len());
+                        response_content.push_str(&delta.delta_content);
+                        on_delta(&delta.delta_content);
+                    }
+                    SessionEventData::AssistantMessage(msg) => {
+                        debug!("Full message: {} chars", msg.content.len());
+                        if response_content.is_empty() {
+                            response_content = msg.content.clone();
+                            on_delta(&msg.content);
+                        }
+                    }
+                    SessionEventData::ToolExecutionStart(tool) => {
+                        debug!("Tool call: {}", tool.tool_name);
+                        tool_used = Some(tool.tool_name.clone());
+                    }
+                    SessionEventData::SessionIdle(_) => {
+                        debug!("Session idle");
+                        break;
+                    }
+                    SessionEventData::SessionError(err) => {
+                        error!("Copilot session error: {}", err.message);
+                        return Err(CopilotError::SendFailed(err.message.clone()));
+                    }
+                    _ => {}
+                }
+            }
+            Ok(Err(broadcast::error::RecvError::Lagged(missed))) => {
+                warn!("Missed {} Copilot events", missed);
+            }
+            // The CLI went away before finishing, so what arrived is partial
+            Ok(Err(broadcast::error::RecvError::Closed)) => {
+                error!("Copilot event channel closed before the response finished");
+                return Err(CopilotError::StartFailed(CLIENT_TERMINATED.to_string()));
+            }
+            Err(_) => {
+                error!("Timeout waiting for Copilot response");
+                return Err(CopilotError::Timeout);
+            }
+        }
+    }
+
+    Ok(AssistantResponse {
+        content: response_content,
+        tool_used,
+    })
+}
+
+/// Clamp a requested timeout to the supported range
+fn clamp_timeout_secs(secs: u64) -> u64 {
+    secs.clamp(MIN_TIMEOUT_SECS, MAX_TIMEOUT_SECS)
+}
+
+/// Get or initialize the global Copilot service, in mock mode if
+/// [`MOCK_ENV_VAR`] asks for it
+pub fn get_service() -> &'static CopilotService {
+    COPILOT_SERVICE.get_or_init(|| {
+        let service = CopilotService::new();
+        if mock_from_env() {
+            service.set_mock(true);
+        }
+        service
+    })
+}
 
 /// Initialize the Copilot service (call on app startup)
-pub async fn init() -> Result<(), CopilotError> {
+pub async fn init() -> Result<StartOutcome, CopilotError> {
     let service = get_service();
     service.start().await
 }
 
-/// Shutdown the Copilot service (call on app exit)
-pub async fn shutdown() -> Result<(), CopilotError> {
-    let service = get_service();
-    service.stop().await
+/// Shutdown the Copilot service (call on app exit), giving in-flight
+/// requests up to `grace` to finish
+pub async fn shutdown(grace: Duration) -> Result<(), CopilotError> {
+    let service = get_service();
+    service.shutdown_gracefully(grace).await
+}
+
+/// Cancel outstanding requests and stop the client without waiting, for
+/// when the UI has gone away. Safe to call if the service never started.
+pub async fn abandon() -> Result<(), CopilotError> {
+    let service = get_service();
+    let cancelled = service.cancel_all().await;
+    if cancelled > 0 {
+        info!("Cancelled {} request(s) left by a closed window", cancelled);
+    }
+    service.stop().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use copilot_sdk::{
+        AssistantMessageData, AssistantMessageDeltaData, SessionIdleData, ToolExecutionStartData,
+    };
+
+    #[tokio::test]
+    async fn cancelled_request_returns_promptly() {
+        let (_tx, mut events) = broadcast::channel::<SessionEvent>(8);
+        let cancel = CancellationToken::new();
+
+        let canceller = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            canceller.cancel();
+        });
+
+        let started = std::time::Instant::now();
+        let result =
+            collect_response(&mut events, Duration::from_secs(60), &cancel, &mut |_| {}).await;
+
+        assert!(matches!(result, Err(CopilotError::Cancelled)));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn cancel_while_creating_a_session_returns_promptly() {
+        let mut service = CopilotService::new();
+        service.set_mock(false);
+        // Session creation that never finishes, like a hung CLI
+        service.session_factory = Arc::new(|_, _, _| Box::pin(std::future::pending()));
+        *service.client.lock().await = Some(Arc::new(Client::builder().build().unwrap()));
+
+        let cancel = CancellationToken::new();
+        let canceller = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            canceller.cancel();
+        });
+
+        let result = tokio::time::timeout(Duration::from_secs(1), service.ask("hi", None, &cancel))
+            .await
+            .expect("cancel was not noticed while creating the session");
+        assert!(matches!(result, Err(CopilotError::Cancelled)));
+    }
+
+    #[tokio::test]
+    async fn channel_closed_before_idle_is_a_dead_client() {
+        let (tx, mut events) = broadcast::channel::<SessionEvent>(8);
+        drop(tx);
+
+        let result = collect_response(
+            &mut events,
+            Duration::from_secs(60),
+            &CancellationToken::new(),
+            &mut |_| {},
+        )
+        .await;
+        assert!(
+            matches!(&result, Err(CopilotError::StartFailed(reason)) if reason == CLIENT_TERMINATED),
+            "{:?}",
+            result
+        );
+    }
+
+    #[tokio::test]
+    async fn streamed_chunks_are_accumulated_into_the_full_response() {
+        let (tx, mut events) = broadcast::channel::<SessionEvent>(8);
+        for chunk in ["안녕", "하세", "요"] {
+            let data = SessionEventData::AssistantMessageDelta(AssistantMessageDeltaData {
+                delta_content: chunk.to_string(),
+            });
+            tx.send(SessionEvent { data }).unwrap();
+        }
+        for data in [
+            // The final message repeats what was streamed
+            SessionEventData::AssistantMessage(AssistantMessageData {
+                content: "안녕하세요".to_string(),
+            }),
+            SessionEventData::SessionIdle(SessionIdleData),
+        ] {
+            tx.send(SessionEvent { data }).unwrap();
+        }
+
+        let response = collect_response(
+            &mut events,
+            Duration::from_secs(60),
+            &CancellationToken::new(),
+            &mut |_| {},
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.content, "안녕하세요");
+
+        let service = CopilotService::new();
+        service.set_mock(true);
+        let response = service
+            .ask_blocking("hi", None, 5, &CancellationToken::new())
+            .await
+            .unwrap();
+        assert_eq!(response.content, "Mock response to: hi");
+    }
+
+    #[tokio::test]
+    async fn tool_call_event_sets_tool_used() {
+        let (tx, mut events) = broadcast::channel::<SessionEvent>(8);
+        for data in [
+            SessionEventData::ToolExecutionStart(ToolExecutionStartData {
+                tool_call_id: "call-1".to_string(),
+                tool_name: LOOKUP_TOOL.to_string(),
+            }),
+            SessionEventData::AssistantMessage(AssistantMessageData {
+                content: "Press d, k, s.".to_string(),
+            }),
+            SessionEventData::SessionIdle(SessionIdleData),
+        ] {
+            tx.send(SessionEvent { data }).unwrap();
+        }
+
+        let response = collect_response(
+            &mut events,
+            Duration::from_secs(60),
+            &CancellationToken::new(),
+            &mut |_| {},
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.content, "Press d, k, s.");
+        assert_eq!(response.tool_used.as_deref(), Some(LOOKUP_TOOL));
+    }
+
+    #[test]
+    fn hangul_lookup_gives_keys_and_romanization() {
+        let result: Value =
+            serde_json::from_str(&hangul_lookup(Layout::TwoBulsik, &json!({ "text": "안" })))
+                .unwrap();
+        assert_eq!(result["romanization"], "an");
+        assert_eq!(result["keys"], json!(["d", "k", "s"]));
+
+        let missing: Value =
+            serde_json::from_str(&hangul_lookup(Layout::TwoBulsik, &json!({}))).unwrap();
+        assert!(missing["error"].is_string());
+    }
+
+    #[test]
+    fn unavailable_cli_names_its_error() {
+        let availability = |cli_installed, cli_authenticated| CopilotAvailability {
+            cli_installed,
+            cli_authenticated,
+            available: cli_installed && cli_authenticated,
+            message: String::new(),
+        };
+        assert!(matches!(
+            availability(false, false).error(),
+            Some(CopilotError::CliNotFound)
+        ));
+        assert!(matches!(
+            availability(true, false).error(),
+            Some(CopilotError::NotAuthenticated)
+        ));
+        assert!(availability(true, true).error().is_none());
+    }
+
+    #[tokio::test]
+    async fn local_capabilities_are_listed_without_a_client() {
+        let service = CopilotService::new();
+        service.set_mock(false);
+        assert_eq!(service.capabilities().await, LOCAL_CAPABILITIES);
+
+        service.set_mock(true);
+        let capabilities = service.capabilities().await;
+        assert!(capabilities.starts_with(&LOCAL_CAPABILITIES.map(String::from)));
+        assert!(capabilities.iter().any(|c| c == "copilot:explain"));
+        assert!(!capabilities.iter().any(|c| c.starts_with("tool:")));
+    }
+
+    #[tokio::test]
+    async fn session_tools_are_listed_as_capabilities() {
+        let service = CopilotService::new();
+        service.set_mock(false);
+        // Stopped for being idle, so the next request gets a new session
+        service.idle_stopped.store(true, Ordering::SeqCst);
+
+        let capabilities = service.capabilities().await;
+        assert!(capabilities.iter().any(|c| c == "copilot:chat"));
+        assert_eq!(
+            capabilities.last().map(String::as_str),
+            Some("tool:hangul_lookup")
+        );
+    }
+
+    /// Client factory that skips the CLI checks, for services whose
+    /// sessions are scripted
+    fn unchecked_clients() -> ClientFactory {
+        Arc::new(|| {
+            Box::pin(async {
+                Client::builder()
+                    .build()
+                    .map_err(|e| CopilotError::StartFailed(e.to_string()))
+            })
+        })
+    }
+
+    #[tokio::test]
+    async fn start_with_a_running_client_reports_already_running() {
+        let mut service = CopilotService::new();
+        service.set_mock(false);
+        service.set_prewarm(false);
+        service.client_factory = unchecked_clients();
+
+        assert_eq!(service.start().await.unwrap(), StartOutcome::Started);
+        assert!(service.is_running().await);
+        assert_eq!(service.start().await.unwrap(), StartOutcome::AlreadyRunning);
+        assert_eq!(service.start().await.unwrap(), StartOutcome::AlreadyRunning);
+        assert!(service.is_running().await);
+    }
+
+    #[tokio::test]
+    async fn dead_client_is_cleared_for_restart() {
+        let service = CopilotService::new();
+        service.set_mock(false);
+        *service.client.lock().await = Some(Arc::new(Client::builder().build().unwrap()));
+        *service.is_running.write().await = true;
+        let id = service.new_conversation().await;
+
+        service.client_terminated().await;
+
+        assert!(service.client.lock().await.is_none());
+        assert!(!service.is_running().await);
+        assert!(service.is_restartable());
+        assert!(service.can_serve().await);
+        // Conversations survive to continue on the new client
+        assert!(service.transcript(id).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn retries_transient_failure_then_succeeds() {
+        // Stands in for a client whose first send hits a dropped pipe
+        let mut attempts = 0;
+        let result = retry_transient(
+            &CancellationToken::new(),
+            || true,
+            |n| {
+                attempts += 1;
+                async move {
+                    if n == 1 {
+                        Err(CopilotError::SendFailed("broken pipe".to_string()))
+                    } else {
+                        Ok("OK")
+                    }
+                }
+            },
+        )
+        .await;
+
+        assert_eq!(result.unwrap(), "OK");
+        assert_eq!(attempts, 2);
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_timeouts() {
+        let mut attempts = 0;
+        let result: Result<(), _> = retry_transient(
+            &CancellationToken::new(),
+            || true,
+            |_| {
+                attempts += 1;
+                async { Err(CopilotError::Timeout) }
+            },
+        )
+        .await;
+
+        assert!(matches!(result, Err(CopilotError::Timeout)));
+        assert_eq!(attempts, 1);
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_attempts() {
+        let mut attempts = 0;
+        let result: Result<(), _> = retry_transient(
+            &CancellationToken::new(),
+            || true,
+            |_| {
+                attempts += 1;
+                async { Err(CopilotError::SessionFailed("down".to_string())) }
+            },
+        )
+        .await;
+
+        assert!(matches!(result, Err(CopilotError::SessionFailed(_))));
+        assert_eq!(attempts, MAX_ATTEMPTS);
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_once_text_was_streamed() {
+        let mut attempts = 0;
+        let result: Result<(), _> = retry_transient(
+            &CancellationToken::new(),
+            || false,
+            |_| {
+                attempts += 1;
+                async { Err(CopilotError::SendFailed("broken pipe".to_string())) }
+            },
+        )
+        .await;
+
+        assert!(matches!(result, Err(CopilotError::SendFailed(_))));
+        assert_eq!(attempts, 1);
+    }
+
+    /// Stands in for a CLI session, answering each message with `reply`
+    /// or, if there is none, never answering
+    struct ScriptedSession {
+        events: broadcast::Sender<SessionEvent>,
+        reply: Option<String>,
+    }
+
+    impl ChatSession for ScriptedSession {
+        fn subscribe(&self) -> broadcast::Receiver<SessionEvent> {
+            self.events.subscribe()
+        }
+
+        fn send<'a>(&'a self, _prompt: &'a str) -> BoxFuture<'a, Result<String, String>> {
+            if let Some(reply) = &self.reply {
+                for data in [
+                    SessionEventData::AssistantMessage(AssistantMessageData {
+                        content: reply.clone(),
+                    }),
+                    SessionEventData::SessionIdle(SessionIdleData),
+                ] {
+                    let _ = self.events.send(SessionEvent { data });
+                }
+            }
+            Box::pin(std::future::ready(Ok("message".to_string())))
+        }
+    }
+
+    /// Counts the messages sent on a scripted session
+    struct CountingSession {
+        inner: ScriptedSession,
+        sent: Arc<AtomicU64>,
+    }
+
+    impl ChatSession for CountingSession {
+        fn subscribe(&self) -> broadcast::Receiver<SessionEvent> {
+            self.inner.subscribe()
+        }
+
+        fn send<'a>(&'a self, prompt: &'a str) -> BoxFuture<'a, Result<String, String>> {
+            self.sent.fetch_add(1, Ordering::SeqCst);
+            self.inner.send(prompt)
+        }
+    }
+
+    /// Session factory whose nth session (from 1) answers with `reply(n)`,
+    /// along with the number of sessions created so far
+    fn scripted_sessions(
+        reply: impl Fn(u64) -> Option<String> + Send + Sync + 'static,
+    ) -> (SessionFactory, Arc<AtomicU64>) {
+        let created = Arc::new(AtomicU64::new(0));
+        let counter = Arc::clone(&created);
+        let factory: SessionFactory = Arc::new(move |_client, _config, _tools| {
+            let n = counter.fetch_add(1, Ordering::SeqCst) + 1;
+            let session: Arc<dyn ChatSession> = Arc::new(ScriptedSession {
+                events: broadcast::channel(8).0,
+                reply: reply(n),
+            });
+            Box::pin(std::future::ready(Ok(session)))
+        });
+        (factory, created)
+    }
+
+    #[tokio::test]
+    async fn concurrent_requests_do_not_wait_for_each_other() {
+        let mut service = CopilotService::new();
+        service.set_mock(false);
+        // The first session never answers; later ones answer straight away
+        let (factory, created) = scripted_sessions(|n| (n > 1).then(|| "네".to_string()));
+        service.session_factory = factory;
+        *service.client.lock().await = Some(Arc::new(Client::builder().build().unwrap()));
+
+        let cancel = CancellationToken::new();
+        let blocked = {
+            let service = service.clone();
+            let cancel = cancel.clone();
+            tokio::spawn(async move { service.ask("first", None, &cancel).await })
+        };
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while created.load(Ordering::SeqCst) == 0 {
+                tokio::task::yield_now().await;
+            }
+        })
+        .await
+        .expect("first ask never created a session");
+
+        let second = tokio::time::timeout(
+            Duration::from_secs(1),
+            service.ask("second", None, &CancellationToken::new()),
+        )
+        .await
+        .expect("second ask waited for the blocked one");
+        assert_eq!(second.unwrap().content, "네");
+        assert!(!blocked.is_finished());
+
+        cancel.cancel();
+        let first = blocked.await.unwrap();
+        assert!(matches!(first, Err(CopilotError::Cancelled)));
+    }
+
+    #[tokio::test]
+    async fn blocking_ask_is_cancelled_by_its_request_id() {
+        let mut service = CopilotService::new();
+        service.set_mock(false);
+        let (factory, created) = scripted_sessions(|_| None);
+        service.session_factory = factory;
+        *service.client.lock().await = Some(Arc::new(Client::builder().build().unwrap()));
+
+        let id = Uuid::new_v4();
+        let cancel = service.begin_request(id).await;
+        let blocked = {
+            let service = service.clone();
+            tokio::spawn(async move { service.ask_blocking("hi", None, 60, &cancel).await })
+        };
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while created.load(Ordering::SeqCst) == 0 {
+                tokio::task::yield_now().await;
+            }
+        })
+        .await
+        .expect("blocking ask never created a session");
+
+        assert!(service.cancel_request(id).await);
+        let result = tokio::time::timeout(Duration::from_secs(1), blocked)
+            .await
+            .expect("cancel was not noticed by the blocking ask")
+            .unwrap();
+        assert!(matches!(result, Err(CopilotError::Cancelled)));
+    }
+
+    fn reply(content: &str) -> Result<AssistantResponse, CopilotError> {
+        Ok(AssistantResponse {
+            content: content.to_string(),
+            tool_used: None,
+        })
+    }
+
+    #[tokio::test]
+    async fn eleventh_rapid_request_is_rate_limited() {
+        let service = CopilotService::new();
+        service.set_mock(false);
+        let cancel = CancellationToken::new();
+
+        // Without a client each request fails, but still uses up a token
+        for _ in 0..DEFAULT_RATE_LIMIT_REQUESTS {
+            let result = service.ask("hi", None, &cancel).await;
+            assert!(matches!(result, Err(CopilotError::NotInitialized)));
+        }
+
+        match service.ask("hi", None, &cancel).await {
+            Err(e @ CopilotError::RateLimited { retry_after }) => {
+                assert!(retry_after > Duration::ZERO);
+                assert!(retry_after <= Duration::from_secs(1));
+                assert!(e.to_string().contains("try again in"));
+            }
+            other => panic!("expected rate limit, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn request_beyond_concurrency_cap_waits_for_a_slot() {
+        let service = CopilotService::new();
+        let cancel = CancellationToken::new();
+
+        let first = service.acquire_slot(&cancel).await.unwrap();
+        let _second = service.acquire_slot(&cancel).await.unwrap();
+
+        let waiter = service.clone();
+        let cancel_third = cancel.clone();
+        let third = tokio::spawn(async move { waiter.acquire_slot(&cancel_third).await });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!third.is_finished());
+
+        drop(first);
+        let third = tokio::time::timeout(Duration::from_secs(1), third)
+            .await
+            .expect("third request should get the freed slot");
+        assert!(third.unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn concurrency_cap_is_configurable() {
+        let service = CopilotService::new();
+        let cancel = CancellationToken::new();
+        assert_eq!(service.set_max_concurrency(0).await, 1);
+
+        let _only = service.acquire_slot(&cancel).await.unwrap();
+        cancel.cancel();
+        assert!(matches!(
+            service.acquire_slot(&cancel).await,
+            Err(CopilotError::Cancelled)
+        ));
+    }
+
+    #[tokio::test]
+    async fn quiz_answer_is_graded_locally_without_copilot() {
+        let service = CopilotService::new();
+        service.set_mock(false);
+        let cancel = CancellationToken::new();
+
+        let question = service.quiz(1, &cancel).await.unwrap();
+        assert_eq!(question.question, quiz::local_question(&question.target));
+
+        let keys: Vec<String> = Layout::TwoBulsik
+            .keystrokes_for_text(&question.target)
+            .iter()
+            .map(|k| k.key.to_string())
+            .collect();
+        let result = service
+            .check_answer(question.id, &keys.join(" "), &cancel)
+            .await
+            .unwrap();
+        assert!(result.correct, "{}", result.feedback);
+    }
+
+    #[tokio::test]
+    async fn unknown_quiz_question_is_rejected() {
+        let service = CopilotService::new();
+        let result = service
+            .check_answer(Uuid::new_v4(), "r k", &CancellationToken::new())
+            .await;
+        assert!(matches!(result, Err(CopilotError::UnknownQuestion(_))));
+    }
+
+    #[tokio::test]
+    async fn unlocked_sentence_is_accepted_first_time() {
+        let unlocked = lesson::unlocked_jamo(1);
+        let mut prompts = Vec::new();
+        let sentence = validated_sentence(&unlocked, "prompt", |p| {
+            prompts.push(p);
+            std::future::ready(reply("\"가나 다니\"\n(ga-na da-ni)"))
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(sentence, "가나 다니");
+        assert_eq!(prompts, vec!["prompt".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn locked_sentence_retries_naming_jamo() {
+        let unlocked = lesson::unlocked_jamo(1);
+        let mut replies = vec![reply("나비"), reply("가나")];
+        replies.reverse();
+        let mut prompts = Vec::new();
+        let sentence = validated_sentence(&unlocked, "prompt", |p| {
+            prompts.push(p);
+            std::future::ready(replies.pop().unwrap())
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(sentence, "가나");
+        assert_eq!(prompts.len(), 2);
+        assert!(prompts[1].contains("Don't use ㅂ"), "{}", prompts[1]);
+    }
+
+    #[tokio::test]
+    async fn sentence_rejected_after_second_failure() {
+        let unlocked = lesson::unlocked_jamo(1);
+        let mut calls = 0;
+        let result = validated_sentence(&unlocked, "prompt", |_| {
+            calls += 1;
+            std::future::ready(reply("고양이"))
+        })
+        .await;
+
+        assert!(matches!(result, Err(CopilotError::InvalidSentence(_))));
+        assert_eq!(calls, SENTENCE_ATTEMPTS);
+    }
+
+    #[tokio::test]
+    async fn scenario_turn_with_locked_jamo_is_regenerated() {
+        let unlocked = lesson::unlocked_jamo(1);
+        // ㅂ and ㅎ aren't taught at level 1
+        let mut replies = vec![reply("바나나?\n네, 하나"), reply("\"가나?\"\n\n다니")];
+        replies.reverse();
+        let mut prompts = Vec::new();
+        let turn = validated_turn(&unlocked, "prompt", |p| {
+            prompts.push(p);
+            std::future::ready(replies.pop().unwrap())
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(turn, ("가나?".to_string(), "다니".to_string()));
+        assert!(prompts[1].contains("Don't use ㅂ"), "{}", prompts[1]);
+    }
+
+    #[tokio::test]
+    async fn scenario_turn_needs_a_suggested_reply() {
+        let unlocked = lesson::unlocked_jamo(1);
+        let result =
+            validated_turn(&unlocked, "prompt", |_| std::future::ready(reply("가나"))).await;
+
+        assert!(matches!(result, Err(CopilotError::InvalidSentence(_))));
+    }
+
+    #[tokio::test]
+    async fn mock_sentence_is_the_same_every_time() {
+        let service = CopilotService::new();
+        service.set_mock(true);
+        let cancel = CancellationToken::new();
+
+        let first = service.generate_sentence("food", 3, &cancel).await.unwrap();
+        let second = service.generate_sentence("food", 3, &cancel).await.unwrap();
+
+        assert_eq!(first, second);
+        assert!(lesson::unlocked_jamo(3).locked_in(&first).is_empty());
+    }
+
+    #[tokio::test]
+    async fn mock_scenario_continues_in_its_conversation() {
+        let service = CopilotService::new();
+        service.set_mock(true);
+        let cancel = CancellationToken::new();
+
+        let turn = service
+            .start_scenario("ordering coffee", 1, &cancel)
+            .await
+            .unwrap();
+        assert!(lesson::unlocked_jamo(1).locked_in(&turn.line).is_empty());
+
+        let next = service
+            .scenario_reply(turn.conversation_id, "가나", &cancel)
+            .await
+            .unwrap();
+        assert_eq!(next.conversation_id, turn.conversation_id);
+
+        // The transcript shows what the learner typed and saw, not the
+        // prompts behind them
+        let transcript = service.transcript(turn.conversation_id).await.unwrap();
+        let contents: Vec<&str> = transcript.iter().map(|t| t.content.as_str()).collect();
+        assert_eq!(
+            contents,
+            [
+                "ordering coffee",
+                turn.line.as_str(),
+                "가나",
+                next.line.as_str()
+            ]
+        );
+
+        // Plain conversations aren't scenarios
+        let plain = service.new_conversation().await;
+        assert!(matches!(
+            service.scenario_reply(plain, "가나", &cancel).await,
+            Err(CopilotError::UnknownConversation(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn conversation_transcript_records_both_sides() {
+        let service = CopilotService::new();
+        service.set_mock(true);
+        let cancel = CancellationToken::new();
+        let id = service.new_conversation().await;
+
+        for prompt in ["안녕하세요", "What does 가 sound like?"] {
+            service
+                .ask_conversation(id, prompt, None, &cancel)
+                .await
+                .unwrap();
+        }
+
+        let transcript = service.transcript(id).await.unwrap();
+        let roles: Vec<Role> = transcript.iter().map(|t| t.role).collect();
+        assert_eq!(
+            roles,
+            [Role::User, Role::Assistant, Role::User, Role::Assistant]
+        );
+        assert_eq!(transcript[2].content, "What does 가 sound like?");
+        assert!(transcript[0].at <= transcript[1].at);
+
+        // One-off questions have no transcript
+        service.ask("가", None, &cancel).await.unwrap();
+        assert_eq!(service.transcript(id).await.unwrap().len(), 4);
+        assert!(service.transcript(Uuid::new_v4()).await.is_err());
+    }
+
+    #[test]
+    fn splits_delimited_batch_reply() {
+        let reply = "가 is ga: r k\n---\n나 is na: s k\n\n---\n\n다 is da:\ne k\n";
+        assert_eq!(
+            split_batch_response(reply, 3),
+            Some(vec![
+                "가 is ga: r k".to_string(),
+                "나 is na: s k".to_string(),
+                "다 is da:\ne k".to_string(),
+            ])
+        );
+    }
+
+    #[test]
+    fn batch_reply_with_wrong_entry_count_is_rejected() {
+        assert_eq!(split_batch_response("가 is ga\n---\n나 is na", 3), None);
+        assert_eq!(split_batch_response("가, 나 and 다 are...", 3), None);
+    }
+
+    #[tokio::test]
+    async fn failed_ask_sets_last_error_and_success_clears_it() {
+        let service = CopilotService::new();
+        assert!(service.last_error().await.is_none());
+
+        // Not started, so the request fails
+        let result = service.ask("hello", None, &CancellationToken::new()).await;
+        assert!(result.is_err());
+        let error = service.last_error().await.unwrap();
+        assert_eq!(error.category, "not_initialized");
+        assert_eq!(error.message, CopilotError::NotInitialized.to_string());
+
+        service
+            .record_outcome(&Ok(AssistantResponse {
+                content: "ok".to_string(),
+                tool_used: None,
+            }))
+            .await;
+        assert!(service.last_error().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn cancellation_is_not_recorded_as_error() {
+        let service = CopilotService::new();
+        service
+            .record_outcome::<()>(&Err(CopilotError::Cancelled))
+            .await;
+        assert!(service.last_error().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn mock_mode_hints_without_client() {
+        let service = CopilotService::new();
+        service.set_mock(true);
+
+        let hint = service
+            .get_hint("가", "", 1, &CancellationToken::new())
+            .await
+            .unwrap();
+        assert_eq!(hint.content, "Try pressing the r key next.");
+        assert_eq!(hint.tool_used.as_deref(), Some(MOCK_TOOL));
+        assert!(service.client.lock().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn mock_mode_explains_from_local_tables() {
+        let service = CopilotService::new();
+        service.set_mock(true);
+
+        let explanation = service
+            .explain("가", &CancellationToken::new())
+            .await
+            .unwrap();
+        assert_eq!(explanation.content, "가 (ga): press r, k");
+        assert!(service.is_running().await);
+    }
+
+    #[tokio::test]
+    async fn idle_client_is_stopped_and_marked_for_restart() {
+        let service = CopilotService::new();
+        *service.idle_timeout.write().await = Duration::from_millis(50);
+        let client = Arc::new(Client::builder().build().unwrap());
+        *service.client.lock().await = Some(Arc::clone(&client));
+        *service.is_running.write().await = true;
+
+        service.spawn_idle_watchdog(client);
+        tokio::time::sleep(Duration::from_millis(300)).await;
+
+        assert!(service.client.lock().await.is_none());
+        assert!(service.idle_stopped.load(Ordering::SeqCst));
+        // Still reported as available, since the next request restarts it
+        assert!(service.is_running().await);
+    }
+
+    #[tokio::test]
+    async fn idle_stop_keeps_conversations() {
+        let mut service = CopilotService::new();
+        service.set_mock(false);
+        service.set_prewarm(false);
+        service.client_factory = unchecked_clients();
+        let (factory, created) = scripted_sessions(|_| Some("네".to_string()));
+        service.session_factory = factory;
+        *service.idle_timeout.write().await = Duration::from_millis(50);
+        let cancel = CancellationToken::new();
+
+        service.start().await.unwrap();
+        let conversation = service.new_conversation().await;
+        service
+            .ask_conversation(conversation, "안녕", None, &cancel)
+            .await
+            .unwrap();
+
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert!(service.client.lock().await.is_none());
+
+        // The next turn restarts the client and continues on a new session
+        service
+            .ask_conversation(conversation, "또 안녕", None, &cancel)
+            .await
+            .unwrap();
+        assert!(service.client.lock().await.is_some());
+        assert_eq!(created.load(Ordering::SeqCst), 2);
+        assert_eq!(service.transcript(conversation).await.unwrap().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn idle_stop_waits_for_a_registered_request() {
+        let service = CopilotService::new();
+        *service.idle_timeout.write().await = Duration::from_millis(50);
+        let client = Arc::new(Client::builder().build().unwrap());
+        *service.client.lock().await = Some(Arc::clone(&client));
+        let id = Uuid::new_v4();
+        service.begin_request(id).await;
+
+        service.spawn_idle_watchdog(client);
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert!(service.client.lock().await.is_some());
+
+        service.finish_request(id).await;
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert!(service.client.lock().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn activity_postpones_idle_stop() {
+        let service = CopilotService::new();
+        *service.idle_timeout.write().await = Duration::from_millis(200);
+        let client = Arc::new(Client::builder().build().unwrap());
+        *service.client.lock().await = Some(Arc::clone(&client));
+
+        service.spawn_idle_watchdog(client);
+        for _ in 0..4 {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            service.touch().await;
+        }
+
+        assert!(service.client.lock().await.is_some());
+    }
+
+    #[tokio::test]
+    async fn prewarm_leaves_session_ready() {
+        let mut service = CopilotService::new();
+        service.set_mock(false);
+        service.client_factory = unchecked_clients();
+        let (factory, created) = scripted_sessions(|_| Some("OK".to_string()));
+        service.session_factory = factory;
+
+        service.start().await.unwrap();
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while service.session.lock().await.is_none() {
+                tokio::task::yield_now().await;
+            }
+        })
+        .await
+        .expect("session was not prewarmed");
+
+        // The first request gets the prewarmed session
+        let response = service
+            .ask("hi", None, &CancellationToken::new())
+            .await
+            .unwrap();
+        assert_eq!(response.content, "OK");
+        assert_eq!(created.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn prewarm_without_client_is_harmless() {
+        let service = CopilotService::new();
+        service.prewarm().await;
+        assert!(service.session.lock().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn identical_explains_call_client_once() {
+        let mut service = CopilotService::new();
+        service.set_mock(false);
+        service.set_prewarm(false);
+        service.client_factory = unchecked_clients();
+        let sent = Arc::new(AtomicU64::new(0));
+        let counter = Arc::clone(&sent);
+        service.session_factory = Arc::new(move |_client, _config, _tools| {
+            let session: Arc<dyn ChatSession> = Arc::new(CountingSession {
+                inner: ScriptedSession {
+                    events: broadcast::channel(8).0,
+                    reply: Some("가 is ㄱ + ㅏ".to_string()),
+                },
+                sent: Arc::clone(&counter),
+            });
+            Box::pin(std::future::ready(Ok(session)))
+        });
+        let cancel = CancellationToken::new();
+
+        service.start().await.unwrap();
+        let first = service.explain("가", &cancel).await.unwrap();
+        let second = service.explain("가", &cancel).await.unwrap();
+
+        assert_eq!(sent.load(Ordering::SeqCst), 1);
+        assert_ne!(first.tool_used.as_deref(), Some(CACHE_TOOL));
+        assert_eq!(second.tool_used.as_deref(), Some(CACHE_TOOL));
+        assert_eq!(second.content, first.content);
+    }
+
+    #[tokio::test]
+    async fn cache_ignores_surrounding_whitespace() {
+        let service = CopilotService::new();
+        let fetch = || async {
+            Ok(AssistantResponse {
+                content: "가 is ㄱ + ㅏ".to_string(),
+                tool_used: None,
+            })
+        };
+
+        service.cached("explain 가", fetch).await.unwrap();
+        let response = service.cached("  explain 가\n", fetch).await.unwrap();
+
+        assert_eq!(response.tool_used.as_deref(), Some(CACHE_TOOL));
+    }
+
+    #[tokio::test]
+    async fn prefetched_hint_is_served_from_cache() {
+        let service = CopilotService::new();
+        let layout = service.layout().await;
+        let prompts = vec![
+            hint_prompt("나", "", layout, 1),
+            hint_prompt("다", "", layout, 1),
+        ];
+        let calls = Arc::new(AtomicU64::new(0));
+
+        let warmed = service
+            .prefetch(prompts, |_| {
+                let calls = Arc::clone(&calls);
+                async move {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    Ok(AssistantResponse {
+                        content: "Try the S key.".to_string(),
+                        tool_used: None,
+                    })
+                }
+            })
+            .await;
+        assert_eq!(warmed, 2);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        // Not started, so this could only be answered from the cache
+        let hint = service
+            .get_hint("나", "", 1, &CancellationToken::new())
+            .await
+            .unwrap();
+        assert_eq!(hint.tool_used.as_deref(), Some(CACHE_TOOL));
+        assert_eq!(hint.content, "Try the S key.");
+    }
+
+    #[tokio::test]
+    async fn persona_change_invalidates_cache() {
+        let service = CopilotService::new();
+        let fetch = || async {
+            Ok(AssistantResponse {
+                content: "hint".to_string(),
+                tool_used: None,
+            })
+        };
+
+        service.cached("hint for 가", fetch).await.unwrap();
+        service.set_persona(Persona::Minimal).await;
+        let response = service.cached("hint for 가", fetch).await.unwrap();
+
+        assert_eq!(response.tool_used, None);
+    }
+
+    #[tokio::test]
+    async fn layout_change_resets_conversation_sessions() {
+        let mut service = CopilotService::new();
+        service.set_mock(false);
+        service.set_prewarm(false);
+        service.client_factory = unchecked_clients();
+        let (factory, created) = scripted_sessions(|_| Some("네".to_string()));
+        service.session_factory = factory;
+        let cancel = CancellationToken::new();
+
+        service.start().await.unwrap();
+        let id = service.new_conversation().await;
+        service
+            .ask_conversation(id, "안녕", None, &cancel)
+            .await
+            .unwrap();
+
+        service.set_layout(Layout::ThreeBulsikFinal).await;
+        assert_eq!(service.validate_prompt().await, Vec::<String>::new());
+
+        // The next turn gets a session prompted for the new layout
+        service
+            .ask_conversation(id, "안녕", None, &cancel)
+            .await
+            .unwrap();
+        assert_eq!(created.load(Ordering::SeqCst), 2);
+        assert_eq!(service.validate_prompt().await, Vec::<String>::new());
+        assert_eq!(service.transcript(id).await.unwrap().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn custom_prompt_applies_to_open_conversations() {
+        let mut service = CopilotService::new();
+        service.set_mock(false);
+        service.set_prewarm(false);
+        service.client_factory = unchecked_clients();
+        let (factory, created) = scripted_sessions(|_| Some("Arr".to_string()));
+        service.session_factory = factory;
+        let cancel = CancellationToken::new();
+
+        service.start().await.unwrap();
+        let id = service.new_conversation().await;
+        service
+            .ask_conversation(id, "hello", None, &cancel)
+            .await
+            .unwrap();
+
+        service
+            .set_system_prompt("Be a pirate.".to_string())
+            .await
+            .unwrap();
+        service
+            .ask_conversation(id, "hello", None, &cancel)
+            .await
+            .unwrap();
+        assert_eq!(created.load(Ordering::SeqCst), 2);
+        assert_eq!(service.validate_prompt().await, Vec::<String>::new());
+    }
+
+    #[test]
+    fn usage_accumulates_estimated_tokens() {
+        let mut usage = UsageStats::default();
+        usage.record_estimated("abcdefgh", "한글");
+        usage.record_estimated("abc", "");
+
+        assert_eq!(
+            usage,
+            UsageStats {
+                prompt_tokens: 3,
+                completion_tokens: 2,
+                request_count: 2,
+                estimated: true,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn reset_usage_clears_counters() {
+        let service = CopilotService::new();
+        service
+            .usage
+            .lock()
+            .await
+            .record_estimated("prompt", "reply");
+
+        service.reset_usage().await;
+        assert_eq!(service.usage().await, UsageStats::default());
+    }
+
+    #[tokio::test]
+    async fn shutdown_waits_for_pending_request() {
+        let service = Arc::new(CopilotService::new());
+        let id = Uuid::new_v4();
+        service.begin_request(id).await;
+
+        let finisher = Arc::clone(&service);
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            finisher.finish_request(id).await;
+        });
+
+        let started = std::time::Instant::now();
+        service
+            .shutdown_gracefully(Duration::from_secs(5))
+            .await
+            .unwrap();
+        let elapsed = started.elapsed();
+
+        assert!(elapsed >= Duration::from_millis(100));
+        assert!(elapsed < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn shutdown_gives_up_after_grace_period() {
+        let service = CopilotService::new();
+        service.begin_request(Uuid::new_v4()).await;
+
+        let started = std::time::Instant::now();
+        service
+            .shutdown_gracefully(Duration::from_millis(100))
+            .await
+            .unwrap();
+
+        assert!(started.elapsed() >= Duration::from_millis(100));
+    }
+
+    #[tokio::test]
+    async fn rejects_requests_while_shutting_down() {
+        let service = CopilotService::new();
+        service.shutting_down.store(true, Ordering::SeqCst);
+
+        let result = service.ask("hello", None, &CancellationToken::new()).await;
+        assert!(matches!(result, Err(CopilotError::ShuttingDown)));
+    }
+
+    #[tokio::test]
+    async fn ping_does_not_restart_a_client_while_shutting_down() {
+        let service = CopilotService::new();
+        service.set_mock(false);
+        service.idle_stopped.store(true, Ordering::SeqCst);
+        service.shutting_down.store(true, Ordering::SeqCst);
+
+        let result = service.ping().await;
+        assert!(!result.ok);
+        assert_eq!(result.error, Some(CopilotError::ShuttingDown.to_string()));
+        // Still waiting for a request to restart it
+        assert!(service.idle_stopped.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn pings_count_towards_the_rate_limit() {
+        let service = CopilotService::new();
+        service.set_mock(false);
+        service.set_rate_limit(1, 60).await;
+
+        assert_eq!(
+            service.ping().await.error,
+            Some(CopilotError::NotInitialized.to_string())
+        );
+        let limited = service.ping().await;
+        assert!(limited.error.unwrap().contains("try again in"));
+    }
+
+    #[tokio::test]
+    async fn cancel_request_signals_registered_token() {
+        let service = CopilotService::new();
+        let id = Uuid::new_v4();
+        let token = service.begin_request(id).await;
+
+        assert!(service.cancel_request(id).await);
+        assert!(token.is_cancelled());
+        assert!(!service.cancel_request(id).await);
+    }
+
+    #[tokio::test]
+    async fn cancel_all_clears_outstanding_requests() {
+        let service = CopilotService::new();
+        let first = service.begin_request(Uuid::new_v4()).await;
+        let second = service.begin_request(Uuid::new_v4()).await;
+
+        assert_eq!(service.cancel_all().await, 2);
+        assert!(first.is_cancelled() && second.is_cancelled());
+        assert!(service.requests.lock().await.is_empty());
+
+        // Nothing left, and stopping a never-started client is fine
+        assert_eq!(service.cancel_all().await, 0);
+        assert!(service.stop().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn asks_on_one_conversation_take_turns() {
+        let service = Arc::new(CopilotService::new());
+        service.set_mock(true);
+        let cancel = CancellationToken::new();
+        let busy = service.new_conversation().await;
+        let other = service.new_conversation().await;
+
+        // An earlier turn is still running on `busy`
+        let turn = service.take_turn(busy, &cancel).await.unwrap();
+
+        let waiting = {
+            let service = Arc::clone(&service);
+            tokio::spawn(async move {
+                service
+                    .ask_conversation(busy, "second", None, &CancellationToken::new())
+                    .await
+            })
+        };
+
+        // Other conversations aren't held up
+        let overlapping = tokio::time::timeout(
+            Duration::from_millis(100),
+            service.ask_conversation(other, "hello", None, &cancel),
+        )
+        .await;
+        assert!(overlapping.unwrap().is_ok());
+        assert!(!waiting.is_finished());
+
+        drop(turn);
+        let second = tokio::time::timeout(Duration::from_secs(1), waiting)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(second.is_ok());
+    }
+
+    #[tokio::test]
+    async fn waiting_for_a_turn_can_be_cancelled() {
+        let service = CopilotService::new();
+        service.set_mock(true);
+        let id = service.new_conversation().await;
+        let _turn = service
+            .take_turn(id, &CancellationToken::new())
+            .await
+            .unwrap();
+
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        let result = service.ask_conversation(id, "hello", None, &cancel).await;
+        assert!(matches!(result, Err(CopilotError::Cancelled)));
+    }
+
+    #[tokio::test]
+    async fn end_conversation_frees_it_once() {
+        let service = CopilotService::new();
+        let id = service.new_conversation().await;
+
+        assert!(service.end_conversation(id).await);
+        assert!(!service.end_conversation(id).await);
+    }
+
+    #[tokio::test]
+    async fn idle_conversations_are_evicted_after_ttl() {
+        let service = CopilotService::new();
+        let idle = service.new_conversation().await;
+        let recent = service.new_conversation().await;
+
+        assert_eq!(service.set_conversation_ttl(60).await, 60);
+        service
+            .conversations
+            .lock()
+            .await
+            .get_mut(&idle)
+            .unwrap()
+            .last_used -= Duration::from_secs(61);
+        service.new_conversation().await;
+
+        assert!(!service.end_conversation(idle).await);
+        assert!(service.end_conversation(recent).await);
+    }
+
+    #[tokio::test]
+    async fn zero_conversation_ttl_is_clamped() {
+        let service = CopilotService::new();
+        let id = service.new_conversation().await;
+
+        assert_eq!(
+            service.set_conversation_ttl(0).await,
+            MIN_CONVERSATION_TTL_SECS
+        );
+        service.new_conversation().await;
+
+        assert!(service.end_conversation(id).await);
+    }
+
+    #[tokio::test]
+    async fn ping_reports_failure_when_not_started() {
+        let result = CopilotService::new().ping().await;
+
+        assert!(!result.ok);
+        assert_eq!(result.latency_ms, None);
+        assert_eq!(result.error, Some(CopilotError::NotInitialized.to_string()));
+    }
+
+    #[test]
+    fn rejects_models_the_backend_does_not_offer() {
+        let available = vec!["gpt-4.1".to_string(), "claude-sonnet-4".to_string()];
+
+        assert!(validate_model("gpt-4.1", &available).is_ok());
+        assert!(matches!(
+            validate_model("gpt-2", &available),
+            Err(CopilotError::UnknownModel(name)) if name == "gpt-2"
+        ));
+    }
+
+    #[test]
+    fn system_prompt_describes_active_layout() {
+        let two = system_prompt(
+            &Persona::Encouraging.prompt(Layout::TwoBulsik),
+            Layout::TwoBulsik,
+        );
+        assert!(two.contains("- Consonants (left hand): ㅂ(q) ㅈ(w) ㄷ(e) ㄱ(r) ㅅ(t) ㅁ(a) ㄴ(s) ㅇ(d) ㄹ(f) ㅎ(g) ㅋ(z) ㅌ(x) ㅊ(c) ㅍ(v)"));
+
+        let three = system_prompt(
+            &Persona::Encouraging.prompt(Layout::ThreeBulsikFinal),
+            Layout::ThreeBulsikFinal,
+        );
+        assert!(three.contains("3-Bulsik Final"));
+        assert!(three.contains("ㅎ(m)"));
+    }
+
+    #[test]
+    fn encouraging_is_the_default_persona() {
+        let prompt = Persona::default().prompt(Layout::TwoBulsik);
+        assert!(prompt.contains("Encouraging and patient"));
+        assert!(prompt.contains("2-Bulsik"));
+    }
+
+    #[test]
+    fn generated_prompts_match_their_layout() {
+        for layout in [Layout::TwoBulsik, Layout::ThreeBulsikFinal] {
+            let prompt = system_prompt(&Persona::Encouraging.prompt(layout), layout);
+            assert_eq!(
+                prompt_layout_problems(&prompt, layout),
+                Vec::<String>::new()
+            );
+        }
+    }
+
+    #[test]
+    fn corrupted_prompt_mapping_is_reported() {
+        let prompt = system_prompt("Be kind.", Layout::TwoBulsik).replace("ㄱ(r)", "ㄱ(e)");
+        assert_eq!(
+            prompt_layout_problems(&prompt, Layout::TwoBulsik),
+            vec!["ㄱ is mapped to e in the prompt, but 2-Bulsik (두벌식) types it with r"]
+        );
+
+        // A prompt left over from another layout is stale throughout
+        let stale = system_prompt("Be kind.", Layout::ThreeBulsikFinal);
+        assert!(!prompt_layout_problems(&stale, Layout::TwoBulsik).is_empty());
+        assert_eq!(
+            prompt_layout_problems("Be kind.", Layout::TwoBulsik).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn stale_session_prompts_are_reported() {
+        let layout = Layout::TwoBulsik;
+        let current = system_prompt(&Persona::Encouraging.prompt(layout), layout);
+        let fresh = ("Shared session".to_string(), current.clone());
+        assert_eq!(
+            stored_prompt_problems(std::slice::from_ref(&fresh), &current, layout),
+            Vec::<String>::new()
+        );
+
+        // Created before switching from 3-Bulsik
+        let old_layout = Layout::ThreeBulsikFinal;
+        let stale = (
+            "Conversation 1".to_string(),
+            system_prompt(&Persona::Encouraging.prompt(old_layout), old_layout),
+        );
+        let problems = stored_prompt_problems(&[fresh, stale], &current, layout);
+        assert_eq!(
+            problems[0],
+            "Conversation 1 was created with an outdated system prompt"
+        );
+        assert!(problems.len() > 1);
+        assert!(
+            problems[1..]
+                .iter()
+                .all(|p| p.starts_with("Conversation 1: "))
+        );
+    }
+
+    #[test]
+    fn custom_prompt_keeps_layout_section() {
+        let prompt = system_prompt("Be a pirate.", Layout::TwoBulsik);
+        assert!(prompt.starts_with("Be a pirate."));
+        assert!(prompt.contains("<keyboard_layout>"));
+    }
+
+    #[test]
+    fn rejects_empty_or_oversized_prompts() {
+        assert!(validate_system_prompt("Be strict.").is_ok());
+        assert!(matches!(
+            validate_system_prompt("  \n"),
+            Err(CopilotError::InvalidPrompt(_))
+        ));
+        assert!(matches!(
+            validate_system_prompt(&"x".repeat(MAX_SYSTEM_PROMPT_CHARS + 1)),
+            Err(CopilotError::InvalidPrompt(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn hints_time_out_sooner_than_explanations() {
+        let service = CopilotService::new();
+        assert!(service.hint_timeout().await < service.timeout().await);
+
+        assert_eq!(service.set_hint_timeout(1).await, MIN_TIMEOUT_SECS);
+        assert_eq!(
+            service.hint_timeout().await,
+            Duration::from_secs(MIN_TIMEOUT_SECS)
+        );
+        assert_eq!(
+            service.timeout().await,
+            Duration::from_secs(DEFAULT_TIMEOUT_SECS)
+        );
+    }
+
+    #[test]
+    fn clamps_timeout_to_allowed_range() {
+        assert_eq!(clamp_timeout_secs(1), MIN_TIMEOUT_SECS);
+        assert_eq!(clamp_timeout_secs(30), 30);
+        assert_eq!(clamp_timeout_secs(1_000), MAX_TIMEOUT_SECS);
+    }
 }
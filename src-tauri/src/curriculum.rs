@@ -0,0 +1,298 @@
+//! Which jamo are taught at each level.
+//!
+//! The default curriculum is bundled with the app, and teachers can load
+//! their own JSON file to reorder lessons:
+//!
+//! ```json
+//! { "levels": [["ㄱ", "ㄴ", "ㅏ"], ["ㅓ", "ㄱ"]] }
+//! ```
+//!
+//! A consonant is first taught as an initial. Listing it again, at the same
+//! or a later level, teaches it as a final. Consonants that can only be
+//! finals, like ㄳ, are taught as finals straight away.
+
+use crate::jamo;
+use crate::lesson::UnlockedJamo;
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::{PoisonError, RwLock};
+use thiserror::Error;
+
+/// The curriculum bundled with the app
+const DEFAULT_CURRICULUM: &str = include_str!("../data/curriculum.json");
+
+/// Curriculum used by lesson generation. Lessons are generated outside
+/// async code, so this is a blocking lock.
+static ACTIVE: OnceCell<RwLock<Curriculum>> = OnceCell::new();
+
+/// Errors that can occur loading a curriculum
+#[derive(Debug, Error)]
+pub enum CurriculumError {
+    #[error("Failed to read curriculum file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Curriculum file is not valid JSON: {0}")]
+    Parse(#[from] serde_json::Error),
+    #[error("Curriculum has no levels")]
+    Empty,
+    #[error("Level {level}: '{jamo}' is not a Hangul jamo")]
+    NotJamo { level: u32, jamo: char },
+    #[error("Level {level}: '{jamo}' is listed more often than it can be taught")]
+    Repeated { level: u32, jamo: char },
+}
+
+/// The file format
+#[derive(Deserialize)]
+struct CurriculumFile {
+    levels: Vec<Vec<char>>,
+}
+
+/// Whether a word can be given to learners at some level
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WordValidation {
+    pub word: String,
+    /// True when every jamo in the word has been taught
+    pub fits: bool,
+    /// Jamo not yet taught in the position they appear, in order of first
+    /// appearance
+    pub locked: Vec<char>,
+}
+
+/// Jamo taught at each level, starting from level 1.
+///
+/// Levels are cumulative; anything past the end uses every jamo listed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Curriculum {
+    levels: Vec<Vec<char>>,
+    /// Jamo introduced at each level, by position in the syllable
+    introduced: Vec<UnlockedJamo>,
+}
+
+impl Curriculum {
+    /// Build a curriculum, checking that every entry is a jamo that can be
+    /// taught where it is listed
+    pub fn new(levels: Vec<Vec<char>>) -> Result<Self, CurriculumError> {
+        if levels.is_empty() {
+            return Err(CurriculumError::Empty);
+        }
+
+        let mut initials = Vec::new();
+        let mut finals = Vec::new();
+        let mut introduced = Vec::with_capacity(levels.len());
+        for (i, jamo) in levels.iter().enumerate() {
+            let level = i as u32 + 1;
+            let mut at_level = UnlockedJamo {
+                initials: Vec::new(),
+                medials: Vec::new(),
+                finals: Vec::new(),
+            };
+
+            for &j in jamo {
+                let can_start = jamo::CHOSEONG.contains(&j);
+                let can_end = jamo::JONGSEONG.contains(&j);
+                if jamo::JUNGSEONG.contains(&j) {
+                    if at_level.medials.contains(&j) || level_of(&levels[..i], j).is_some() {
+                        return Err(CurriculumError::Repeated { level, jamo: j });
+                    }
+                    at_level.medials.push(j);
+                } else if !can_start && !can_end {
+                    return Err(CurriculumError::NotJamo { level, jamo: j });
+                } else if can_start && !initials.contains(&j) {
+                    initials.push(j);
+                    at_level.initials.push(j);
+                } else if can_end && !finals.contains(&j) {
+                    finals.push(j);
+                    at_level.finals.push(j);
+                } else {
+                    return Err(CurriculumError::Repeated { level, jamo: j });
+                }
+            }
+            introduced.push(at_level);
+        }
+
+        Ok(Self { levels, introduced })
+    }
+
+    /// Parse a curriculum from JSON
+    pub fn from_json(json: &str) -> Result<Self, CurriculumError> {
+        let file: CurriculumFile = serde_json::from_str(json)?;
+        Self::new(file.levels)
+    }
+
+    /// Load a curriculum from a JSON file
+    pub fn load(path: &Path) -> Result<Self, CurriculumError> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    /// The curriculum bundled with the app
+    pub fn bundled() -> Self {
+        Self::from_json(DEFAULT_CURRICULUM).expect("bundled curriculum is valid")
+    }
+
+    /// Number of levels
+    pub fn len(&self) -> usize {
+        self.levels.len()
+    }
+
+    /// Always false; a curriculum has at least one level
+    pub fn is_empty(&self) -> bool {
+        self.levels.is_empty()
+    }
+
+    /// Jamo first introduced at `level`, in the order listed. Empty past the
+    /// end of the curriculum.
+    pub fn jamo_for_level(&self, level: u32) -> Vec<char> {
+        let Some(jamo) = self.level(level) else {
+            return Vec::new();
+        };
+
+        // Finals may repeat consonants taught as initials at the same level
+        let mut chars = Vec::new();
+        for &c in jamo
+            .initials
+            .iter()
+            .chain(&jamo.medials)
+            .chain(&jamo.finals)
+        {
+            if !chars.contains(&c) {
+                chars.push(c);
+            }
+        }
+        chars
+    }
+
+    /// The level that first introduces `jamo` in any position
+    pub fn level_of_jamo(&self, jamo: char) -> Option<u32> {
+        level_of(&self.levels, jamo)
+    }
+
+    /// Jamo introduced up to and including `level`
+    pub fn unlocked(&self, level: u32) -> UnlockedJamo {
+        let introduced = &self.introduced[..(level.max(1) as usize).min(self.introduced.len())];
+        UnlockedJamo {
+            initials: introduced.iter().flat_map(|l| l.initials.clone()).collect(),
+            medials: introduced.iter().flat_map(|l| l.medials.clone()).collect(),
+            finals: introduced.iter().flat_map(|l| l.finals.clone()).collect(),
+        }
+    }
+
+    /// Check whether `word` only uses jamo taught up to `level`.
+    ///
+    /// Only syllable blocks are checked; spaces and punctuation are fine.
+    pub fn validate_word(&self, word: &str, level: u32) -> WordValidation {
+        let locked = self.unlocked(level).locked_in(&jamo::normalize(word));
+        WordValidation {
+            word: word.to_string(),
+            fits: locked.is_empty(),
+            locked,
+        }
+    }
+
+    fn level(&self, level: u32) -> Option<&UnlockedJamo> {
+        level
+            .checked_sub(1)
+            .and_then(|i| self.introduced.get(i as usize))
+    }
+}
+
+impl Default for Curriculum {
+    fn default() -> Self {
+        Self::bundled()
+    }
+}
+
+/// First level in `levels` that lists `jamo`
+fn level_of(levels: &[Vec<char>], jamo: char) -> Option<u32> {
+    levels
+        .iter()
+        .position(|l| l.contains(&jamo))
+        .map(|i| i as u32 + 1)
+}
+
+/// Get the curriculum lessons are generated from
+pub fn active() -> &'static RwLock<Curriculum> {
+    ACTIVE.get_or_init(|| RwLock::new(Curriculum::bundled()))
+}
+
+/// A copy of the active curriculum
+pub fn current() -> Curriculum {
+    active()
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+}
+
+/// Replace the active curriculum
+pub fn set_active(curriculum: Curriculum) {
+    *active().write().unwrap_or_else(PoisonError::into_inner) = curriculum;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn custom_two_level_curriculum() {
+        let curriculum =
+            Curriculum::from_json(r#"{ "levels": [["ㅁ", "ㅗ"], ["ㄱ", "ㅏ", "ㅁ"]] }"#).unwrap();
+
+        assert_eq!(curriculum.len(), 2);
+        assert_eq!(curriculum.jamo_for_level(1), vec!['ㅁ', 'ㅗ']);
+        assert_eq!(curriculum.jamo_for_level(2), vec!['ㄱ', 'ㅏ', 'ㅁ']);
+        assert!(curriculum.jamo_for_level(3).is_empty());
+        assert_eq!(curriculum.level_of_jamo('ㅁ'), Some(1));
+        assert_eq!(curriculum.level_of_jamo('ㄱ'), Some(2));
+        assert_eq!(curriculum.level_of_jamo('ㅎ'), None);
+
+        // ㅁ is an initial from level 1 and a final from level 2
+        assert!(curriculum.unlocked(1).finals.is_empty());
+        assert_eq!(curriculum.unlocked(2).finals, vec!['ㅁ']);
+        assert_eq!(curriculum.unlocked(9), curriculum.unlocked(2));
+    }
+
+    #[test]
+    fn rejects_characters_that_are_not_jamo() {
+        let err = Curriculum::from_json(r#"{ "levels": [["ㄱ", "가"]] }"#).unwrap_err();
+        assert!(matches!(
+            err,
+            CurriculumError::NotJamo {
+                level: 1,
+                jamo: '가'
+            }
+        ));
+    }
+
+    #[test]
+    fn rejects_jamo_taught_too_often() {
+        // ㄸ can't be a final, so it can only be listed once
+        let err = Curriculum::new(vec![vec!['ㄸ'], vec!['ㄸ']]).unwrap_err();
+        assert!(matches!(err, CurriculumError::Repeated { level: 2, .. }));
+        assert!(matches!(
+            Curriculum::new(Vec::new()),
+            Err(CurriculumError::Empty)
+        ));
+    }
+
+    #[test]
+    fn word_with_locked_final_is_flagged() {
+        let curriculum = Curriculum::bundled();
+
+        // Finals are first taught at level 4
+        let result = curriculum.validate_word("가방", 3);
+        assert!(!result.fits);
+        assert_eq!(result.locked, vec!['ㅇ']);
+
+        assert!(curriculum.validate_word("가방", 4).fits);
+        assert!(curriculum.validate_word("나비", 2).fits);
+    }
+
+    #[test]
+    fn bundled_curriculum_loads() {
+        let curriculum = Curriculum::bundled();
+        assert_eq!(
+            curriculum.jamo_for_level(1),
+            vec!['ㄱ', 'ㄴ', 'ㄷ', 'ㅏ', 'ㅣ']
+        );
+        assert_eq!(curriculum.unlocked(4).finals.len(), 6);
+    }
+}
@@ -0,0 +1,268 @@
+//! Syllable-by-syllable word breakdowns.
+//!
+//! Builds the structured part of an explanation from the local romanize
+//! and layout tables, so only the overall pronunciation note needs Copilot,
+//! and a plain explanation can be given with no Copilot at all. Comparisons
+//! of easily confused syllables (사/자) are grounded the same way.
+
+use crate::copilot::AssistantResponse;
+use crate::hint::LOCAL_TOOL;
+use crate::jamo::{self, HanjaError, MistakeKind};
+use crate::layout::{KeyPress, Layout, PhysicalLayout};
+use crate::romanize;
+use serde::Serialize;
+
+/// How to read and type one syllable of a word
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SyllableExplanation {
+    pub syllable: char,
+    /// Romanization of the syllable on its own
+    pub romanization: String,
+    pub keystrokes: Vec<KeyPress>,
+}
+
+/// Break text into per-syllable explanations.
+///
+/// Decomposed (NFD) text is normalized first. Characters that are not
+/// syllable blocks (spaces, punctuation) are skipped, but Hanja is an error
+/// rather than being dropped silently.
+pub fn breakdown(text: &str, layout: Layout) -> Result<Vec<SyllableExplanation>, HanjaError> {
+    jamo::reject_hanja(text)?;
+    Ok(jamo::normalize(text)
+        .chars()
+        .filter(|&c| jamo::decompose(c).is_some())
+        .filter_map(|syllable| {
+            Some(SyllableExplanation {
+                syllable,
+                romanization: romanize::revised(&syllable.to_string()),
+                keystrokes: layout.keystrokes_for(syllable)?,
+            })
+        })
+        .collect())
+}
+
+/// Explain how to say and type `text` without calling Copilot.
+///
+/// "안 is pronounced 'an'. Press d, k, s." Keys are labelled for the
+/// learner's keyboard. Hanja is an error, as for [`breakdown`].
+pub fn local_explanation(
+    layout: Layout,
+    physical: PhysicalLayout,
+    text: &str,
+) -> Result<AssistantResponse, HanjaError> {
+    jamo::reject_hanja(text)?;
+    let text = jamo::normalize(text.trim());
+    let keys: Vec<String> = physical
+        .translate_all(&layout.keystrokes_for_text(&text))
+        .iter()
+        .filter(|k| k.key != ' ')
+        .map(|k| k.to_string())
+        .collect();
+
+    let content = if keys.is_empty() {
+        format!("\"{}\" has no Hangul to explain.", text)
+    } else {
+        format!(
+            "{} is pronounced '{}'. Press {}.",
+            text,
+            romanize::revised(&text),
+            keys.join(", ")
+        )
+    };
+    Ok(AssistantResponse {
+        content,
+        tool_used: Some(LOCAL_TOOL.to_string()),
+    })
+}
+
+/// A component of a syllable block
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyllablePart {
+    Initial,
+    Medial,
+    Final,
+}
+
+impl SyllablePart {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Initial => "initial",
+            Self::Medial => "vowel",
+            Self::Final => "final",
+        }
+    }
+}
+
+/// One part two syllables disagree on
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct JamoDifference {
+    pub part: SyllablePart,
+    /// The first syllable's jamo; `None` for a missing final
+    pub a: Option<char>,
+    pub b: Option<char>,
+}
+
+/// How syllable `b` differs from `a`, part by part, or `None` unless both
+/// are syllable blocks
+pub fn syllable_diff(a: char, b: char) -> Option<Vec<JamoDifference>> {
+    let (a_cho, a_jung, a_jong) = jamo::decompose(a)?;
+    let (b_cho, b_jung, b_jong) = jamo::decompose(b)?;
+    Some(
+        [
+            (SyllablePart::Initial, Some(a_cho), Some(b_cho)),
+            (SyllablePart::Medial, Some(a_jung), Some(b_jung)),
+            (SyllablePart::Final, a_jong, b_jong),
+        ]
+        .into_iter()
+        .filter(|(_, a, b)| a != b)
+        .map(|(part, a, b)| JamoDifference { part, a, b })
+        .collect(),
+    )
+}
+
+/// Describe how two syllables differ in one sentence, such as "사 and 자
+/// differ only in the initial: ㅅ vs ㅈ." `None` unless both are syllable
+/// blocks.
+pub fn describe_difference(a: char, b: char) -> Option<String> {
+    let diff = syllable_diff(a, b)?;
+    let jamo = |j: Option<char>| j.map_or_else(|| "none".to_string(), String::from);
+
+    let mut sentence = match diff.as_slice() {
+        [] => format!("{} and {} are the same syllable.", a, b),
+        [only] => format!(
+            "{} and {} differ only in the {}: {} vs {}.",
+            a,
+            b,
+            only.part.name(),
+            jamo(only.a),
+            jamo(only.b)
+        ),
+        parts => {
+            let parts: Vec<String> = parts
+                .iter()
+                .map(|d| format!("the {} ({} vs {})", d.part.name(), jamo(d.a), jamo(d.b)))
+                .collect();
+            format!("{} and {} differ in {}.", a, b, parts.join(" and "))
+        }
+    };
+    let shifted = |x, y| jamo::classify(x, y).contains(&MistakeKind::ShiftOmitted);
+    if shifted(a, b) || shifted(b, a) {
+        sentence.push_str(" One is typed with Shift and the other without.");
+    }
+    Some(sentence)
+}
+
+/// Compare two syllables without calling Copilot: how they differ, and how
+/// each is said and typed. `None` unless both are syllable blocks.
+pub fn local_comparison(
+    layout: Layout,
+    physical: PhysicalLayout,
+    a: char,
+    b: char,
+) -> Option<AssistantResponse> {
+    let describe = |syllable: char| {
+        let keys: Vec<String> = physical
+            .translate_all(&layout.keystrokes_for(syllable).unwrap_or_default())
+            .iter()
+            .map(|k| k.to_string())
+            .collect();
+        format!(
+            "{} is '{}' ({})",
+            syllable,
+            romanize::revised(&syllable.to_string()),
+            keys.join(", ")
+        )
+    };
+    let content = format!(
+        "{} {}; {}.",
+        describe_difference(a, b)?,
+        describe(a),
+        describe(b)
+    );
+    Some(AssistantResponse {
+        content,
+        tool_used: Some(LOCAL_TOOL.to_string()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plain_keys(keys: &str) -> Vec<KeyPress> {
+        keys.chars().map(KeyPress::plain).collect()
+    }
+
+    #[test]
+    fn local_explanation_gives_romanization_and_keys() {
+        let response = local_explanation(Layout::TwoBulsik, PhysicalLayout::Qwerty, "안").unwrap();
+        assert_eq!(response.content, "안 is pronounced 'an'. Press d, k, s.");
+        assert_eq!(response.tool_used.as_deref(), Some(LOCAL_TOOL));
+
+        let response =
+            local_explanation(Layout::TwoBulsik, PhysicalLayout::Qwerty, "hello").unwrap();
+        assert_eq!(response.content, "\"hello\" has no Hangul to explain.");
+    }
+
+    #[test]
+    fn sa_and_ja_differ_only_in_the_initial() {
+        assert_eq!(
+            syllable_diff('사', '자').unwrap(),
+            [JamoDifference {
+                part: SyllablePart::Initial,
+                a: Some('ㅅ'),
+                b: Some('ㅈ'),
+            }]
+        );
+
+        let response = local_comparison(Layout::TwoBulsik, PhysicalLayout::Qwerty, '사', '자');
+        assert_eq!(
+            response.unwrap().content,
+            "사 and 자 differ only in the initial: ㅅ vs ㅈ. 사 is 'sa' (t, k); 자 is 'ja' (w, k)."
+        );
+    }
+
+    #[test]
+    fn describes_several_differences_and_shift() {
+        assert_eq!(
+            describe_difference('각', '까').unwrap(),
+            "각 and 까 differ in the initial (ㄱ vs ㄲ) and the final (ㄱ vs none). \
+             One is typed with Shift and the other without."
+        );
+        assert_eq!(describe_difference('사', 'a'), None);
+    }
+
+    #[test]
+    fn breaks_annyeong_into_two_syllables() {
+        let entries = breakdown("안녕", Layout::TwoBulsik).unwrap();
+
+        assert_eq!(
+            entries,
+            vec![
+                SyllableExplanation {
+                    syllable: '안',
+                    romanization: "an".to_string(),
+                    keystrokes: plain_keys("dks"),
+                },
+                SyllableExplanation {
+                    syllable: '녕',
+                    romanization: "nyeong".to_string(),
+                    keystrokes: plain_keys("sud"),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_non_syllables() {
+        let entries = breakdown("안녕, 하세요!", Layout::TwoBulsik).unwrap();
+        assert_eq!(entries.len(), 5);
+    }
+
+    #[test]
+    fn hanja_is_rejected() {
+        let err = breakdown("漢字 한자", Layout::TwoBulsik).unwrap_err();
+        assert_eq!(err.hanja, "漢字");
+    }
+}
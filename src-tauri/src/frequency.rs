@@ -0,0 +1,121 @@
+//! Hangul syllable frequency.
+//!
+//! An embedded table of how often syllables occur in a small sample of
+//! written Korean (see the header of `data/syllable_frequency.tsv` for its
+//! sources), so practice can favour syllables like 이 and 다 over rare ones.
+
+use crate::jamo;
+use once_cell::sync::OnceCell;
+use std::collections::HashMap;
+
+/// Tab-separated `syllable<TAB>count` lines, most frequent first
+const TABLE: &str = include_str!("../data/syllable_frequency.tsv");
+
+/// Most jamo a suggestion may differ from the input by
+const MAX_SUGGESTION_DISTANCE: usize = 2;
+
+static RANKED: OnceCell<Vec<(char, u32)>> = OnceCell::new();
+static COUNTS: OnceCell<HashMap<char, u32>> = OnceCell::new();
+
+/// Syllables with their counts, in table order
+fn ranked() -> &'static [(char, u32)] {
+    RANKED.get_or_init(|| {
+        TABLE
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .filter_map(|line| {
+                let (syllable, count) = line.split_once('\t')?;
+                let mut chars = syllable.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => Some((c, count.trim().parse().ok()?)),
+                    _ => None,
+                }
+            })
+            .collect()
+    })
+}
+
+/// Occurrences of `syllable` per million syllables, 0 if it is rare
+pub fn frequency(syllable: char) -> u32 {
+    COUNTS
+        .get_or_init(|| ranked().iter().copied().collect())
+        .get(&syllable)
+        .copied()
+        .unwrap_or(0)
+}
+
+/// The `n` most frequent syllables, most frequent first.
+///
+/// Returns the whole table if it has fewer than `n` entries.
+pub fn top_n(n: usize) -> Vec<char> {
+    ranked().iter().take(n).map(|&(c, _)| c).collect()
+}
+
+/// Common syllables `input` was probably meant to be, closest first.
+///
+/// Compares jamo by jamo, so a stray jamo ("ㅎㅏㄴ") or one wrong jamo
+/// ("햔") still finds 한. Ties go to the more frequent syllable. Returns at
+/// most `limit`, never `input` itself.
+pub fn suggestions(input: &str, limit: usize) -> Vec<char> {
+    let input = jamo::normalize(input.trim());
+    if input.is_empty() {
+        return Vec::new();
+    }
+
+    // Stable, so equally close syllables stay in frequency order
+    let mut near: Vec<(usize, char)> = ranked()
+        .iter()
+        .filter(|&&(c, _)| input != c.to_string())
+        .map(|&(c, _)| (jamo::jamo_edit_distance(&input, &c.to_string()), c))
+        .filter(|&(distance, _)| distance <= MAX_SUGGESTION_DISTANCE)
+        .collect();
+    near.sort_by_key(|&(distance, _)| distance);
+    near.into_iter().take(limit).map(|(_, c)| c).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn common_syllable_ranks_above_rare_one() {
+        assert!(frequency('이') > frequency('뷁'));
+        assert_eq!(frequency('뷁'), 0);
+        for c in "없같많겠렇께".chars() {
+            assert!(frequency(c) > 0, "{} missing from the table", c);
+        }
+    }
+
+    #[test]
+    fn everyday_syllables_outrank_technical_ones() {
+        for common in ['요', '니'] {
+            for technical in ['압', '축'] {
+                assert!(frequency(common) > frequency(technical));
+            }
+        }
+    }
+
+    #[test]
+    fn top_n_is_most_frequent_first() {
+        let top = top_n(3);
+        assert_eq!(top.len(), 3);
+        assert_eq!(top[0], '다');
+        assert!(frequency(top[0]) >= frequency(top[1]));
+        assert!(frequency(top[1]) >= frequency(top[2]));
+    }
+
+    #[test]
+    fn near_miss_suggests_intended_syllable() {
+        assert_eq!(suggestions("ㅎㅏㄴ", 5)[0], '한');
+        assert!(suggestions("햔", 5).contains(&'한'));
+        assert!(suggestions("햔", 5).len() <= 5);
+        assert!(!suggestions("한", 5).contains(&'한'));
+        assert!(suggestions("", 5).is_empty());
+    }
+
+    #[test]
+    fn table_parses_every_entry() {
+        let entries = TABLE.lines().filter(|line| !line.starts_with('#')).count();
+        assert_eq!(ranked().len(), entries);
+    }
+}
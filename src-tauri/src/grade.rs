@@ -0,0 +1,161 @@
+//! Grading typed text against a target.
+//!
+//! Compares syllable by syllable and, within each syllable, jamo by jamo so
+//! the frontend can highlight exactly which parts were wrong.
+
+use crate::jamo::{self, HanjaError};
+use serde::Serialize;
+
+/// How one target character compares with what was typed in its place
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CharDiff {
+    pub expected: char,
+    /// `None` when the learner has not typed this far
+    pub got: Option<char>,
+    /// Whether the initial, medial and final match. Characters that are not
+    /// syllable blocks match all three or none.
+    pub jamo_matches: [bool; 3],
+}
+
+/// Result of grading a whole string
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GradeResult {
+    pub correct: bool,
+    pub per_char: Vec<CharDiff>,
+    /// Characters typed past the end of the target
+    pub extra: Vec<char>,
+    /// Jamo edits needed to turn the typed text into the target, for
+    /// partial credit
+    pub jamo_distance: usize,
+}
+
+/// Compare the jamo of two characters
+fn jamo_matches(expected: char, got: char) -> [bool; 3] {
+    match (jamo::decompose(expected), jamo::decompose(got)) {
+        (Some((e_cho, e_jung, e_jong)), Some((g_cho, g_jung, g_jong))) => {
+            [e_cho == g_cho, e_jung == g_jung, e_jong == g_jong]
+        }
+        _ => [expected == got; 3],
+    }
+}
+
+/// Grade `typed` against `target`, aligning characters by position.
+///
+/// Both strings are normalized first, so decomposed (NFD) input grades the
+/// same as composed text. Hanja in either string is an error, since it
+/// can't be typed or compared jamo by jamo.
+pub fn grade(target: &str, typed: &str) -> Result<GradeResult, HanjaError> {
+    jamo::reject_hanja(target)?;
+    jamo::reject_hanja(typed)?;
+    let target = jamo::normalize(target);
+    let typed = jamo::normalize(typed);
+    let mut typed_chars = typed.chars();
+
+    let per_char: Vec<CharDiff> = target
+        .chars()
+        .map(|expected| {
+            let got = typed_chars.next();
+            CharDiff {
+                expected,
+                got,
+                jamo_matches: got.map_or([false; 3], |g| jamo_matches(expected, g)),
+            }
+        })
+        .collect();
+    let extra: Vec<char> = typed_chars.collect();
+
+    Ok(GradeResult {
+        jamo_distance: jamo::jamo_edit_distance(&target, &typed),
+        correct: extra.is_empty() && per_char.iter().all(|d| d.got == Some(d.expected)),
+        per_char,
+        extra,
+    })
+}
+
+/// Partial credit for `typed` against `target`, from 0 to 100.
+///
+/// Each jamo edit needed to reach the target costs an equal share of the
+/// target's jamo, so one wrong final in a three-jamo syllable scores 67.
+/// An empty target scores 100 only for empty input.
+pub fn score(target: &str, typed: &str) -> u8 {
+    let total = jamo::jamo_count(target);
+    if total == 0 {
+        return if typed.trim().is_empty() { 100 } else { 0 };
+    }
+    let distance = jamo::jamo_edit_distance(target, typed).min(total);
+    (100.0 * (total - distance) as f32 / total as f32).round() as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exact_match_is_correct() {
+        let result = grade("한글", "한글").unwrap();
+        assert!(result.correct);
+        assert!(result.per_char.iter().all(|d| d.jamo_matches == [true; 3]));
+    }
+
+    #[test]
+    fn wrong_final_is_flagged_on_final_only() {
+        let result = grade("한글", "할글").unwrap();
+        assert!(!result.correct);
+        assert_eq!(
+            result.per_char[0],
+            CharDiff {
+                expected: '한',
+                got: Some('할'),
+                jamo_matches: [true, true, false],
+            }
+        );
+        assert_eq!(result.per_char[1].jamo_matches, [true; 3]);
+        assert_eq!(result.jamo_distance, 1);
+    }
+
+    #[test]
+    fn decomposed_input_grades_as_composed() {
+        // ᄀ + ᅡ typed against 가
+        assert!(grade("가", "\u{1100}\u{1161}").unwrap().correct);
+    }
+
+    #[test]
+    fn missing_syllable_has_no_match() {
+        let result = grade("안녕", "안").unwrap();
+        assert!(!result.correct);
+        assert_eq!(result.per_char[1].got, None);
+        assert_eq!(result.per_char[1].jamo_matches, [false; 3]);
+    }
+
+    #[test]
+    fn extra_syllables_are_reported() {
+        let result = grade("안", "안녕").unwrap();
+        assert!(!result.correct);
+        assert_eq!(result.extra, vec!['녕']);
+    }
+
+    #[test]
+    fn hanja_target_is_rejected() {
+        let err = grade("韓國", "한국").unwrap_err();
+        assert_eq!(err.hanja, "韓國");
+    }
+
+    #[test]
+    fn perfect_match_scores_full_marks() {
+        assert_eq!(score("한글", "한글"), 100);
+    }
+
+    #[test]
+    fn wrong_final_gets_partial_credit() {
+        let partial = score("간", "감");
+        assert!(partial > 0 && partial < 100, "{partial}");
+        assert_eq!(partial, 67);
+    }
+
+    #[test]
+    fn empty_and_unrelated_input_score_zero() {
+        assert_eq!(score("한글", ""), 0);
+        assert_eq!(score("가", "hello world"), 0);
+        assert_eq!(score("", ""), 100);
+    }
+}
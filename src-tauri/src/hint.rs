@@ -0,0 +1,255 @@
+//! Offline hint engine.
+//!
+//! Computes "which key next" hints from the local layout tables so learners
+//! get help even when GitHub Copilot is not installed.
+
+use crate::copilot::AssistantResponse;
+use crate::jamo::{self, MistakeKind};
+use crate::layout::{KeyPress, Layout, PhysicalLayout, Position};
+use serde::Serialize;
+
+/// Tool name reported for responses produced without Copilot
+pub const LOCAL_TOOL: &str = "local";
+
+/// Keys still to press for a target, for on-screen key highlighting
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NextKeysResult {
+    /// `None` once the whole target has been typed
+    pub next_key: Option<KeyPress>,
+    /// Every key still to press, starting with `next_key`
+    pub remaining: Vec<KeyPress>,
+    /// Fraction of the target's keystrokes already typed (0.0 - 1.0)
+    pub progress: f32,
+}
+
+/// Find the next key the learner should press to continue typing `target`.
+///
+/// Compares the keystrokes for `target` against those implied by
+/// `user_input` and returns the first key that has not been typed yet.
+/// Returns `None` once the whole target has been typed.
+pub fn next_key(layout: Layout, target: &str, user_input: &str) -> Option<KeyPress> {
+    next_keys(layout, PhysicalLayout::Qwerty, target, user_input).next_key
+}
+
+/// Work out every key left to type `target` after `user_input`.
+///
+/// Keystrokes are matched up to the first one that differs from the
+/// target, so a mistake counts as the point to resume from. Keys are
+/// labelled for the learner's `physical` keyboard.
+pub fn next_keys(
+    layout: Layout,
+    physical: PhysicalLayout,
+    target: &str,
+    user_input: &str,
+) -> NextKeysResult {
+    let target_keys = layout.keystrokes_for_text(target);
+    let typed_keys = layout.keystrokes_for_text(user_input);
+
+    let matched = target_keys
+        .iter()
+        .zip(&typed_keys)
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let remaining = physical.translate_all(&target_keys[matched..]);
+    let progress = if target_keys.is_empty() {
+        1.0
+    } else {
+        matched as f32 / target_keys.len() as f32
+    };
+
+    NextKeysResult {
+        next_key: remaining.first().copied(),
+        remaining,
+        progress,
+    }
+}
+
+/// The next 2-Bulsik key to press after `typed`, as shown by a live
+/// composer, to reach `target`.
+///
+/// Works at the level of keys, so a half-built syllable counts for the
+/// keys already pressed (가 on the way to 간 leaves ㄴ). Returns `None`
+/// once the target is typed, and also once the learner has gone off it,
+/// since no key press gets them back without deleting first.
+pub fn live_next_key(target: &str, typed: &str) -> Option<KeyPress> {
+    let target_keys = Layout::TwoBulsik.keystrokes_for_text(target);
+    let typed_keys = Layout::TwoBulsik.keystrokes_for_text(typed);
+    if !target_keys.starts_with(&typed_keys) {
+        return None;
+    }
+    target_keys.get(typed_keys.len()).copied()
+}
+
+/// A targeted hint when the learner typed only the first vowel of a
+/// compound vowel (오 for 와), naming the key that completes it.
+///
+/// Looks at the first character of `user_input` that differs from
+/// `target`; returns `None` for any other kind of mistake.
+pub fn compound_vowel_hint(
+    layout: Layout,
+    physical: PhysicalLayout,
+    target: &str,
+    user_input: &str,
+) -> Option<String> {
+    let (expected, actual) = target
+        .chars()
+        .zip(user_input.chars())
+        .find(|(expected, actual)| expected != actual)?;
+    if !jamo::classify(expected, actual).contains(&MistakeKind::IncompleteCompoundVowel) {
+        return None;
+    }
+
+    let vowel = jamo::decompose(expected).map_or(expected, |(_, jung, _)| jung);
+    let key = *layout.jamo_to_keys_at(vowel, Position::Medial)?.last()?;
+    let key = physical.translate(key);
+    let key = if key.shift {
+        key.to_string()
+    } else {
+        key.key.to_string()
+    };
+    Some(format!(
+        "You need to also press {} to complete the {} vowel.",
+        key, vowel
+    ))
+}
+
+/// Build an encouraging hint without calling Copilot
+pub fn local_hint(
+    layout: Layout,
+    physical: PhysicalLayout,
+    target: &str,
+    user_input: &str,
+) -> AssistantResponse {
+    if let Some(content) = compound_vowel_hint(layout, physical, target, user_input) {
+        return AssistantResponse {
+            content,
+            tool_used: Some(LOCAL_TOOL.to_string()),
+        };
+    }
+
+    let content = match next_key(layout, target, user_input).map(|k| physical.translate(k)) {
+        Some(key) if key.shift => format!("Try pressing {} next.", key),
+        Some(key) => format!("Try pressing the {} key next.", key.key),
+        None => format!("You've typed \"{}\" completely. Nice work!", target),
+    };
+
+    AssistantResponse {
+        content,
+        tool_used: Some(LOCAL_TOOL.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ime::LiveComposer;
+
+    #[test]
+    fn next_key_after_first_jamo() {
+        assert_eq!(
+            next_key(Layout::TwoBulsik, "가", "ㄱ"),
+            Some(KeyPress::plain('k'))
+        );
+    }
+
+    #[test]
+    fn live_next_key_follows_composition() {
+        let mut live = LiveComposer::new();
+        live.step(KeyPress::plain('r'));
+        live.step(KeyPress::plain('k'));
+        // 가 is pending on the way to 간, so ㄴ is next
+        assert_eq!(live.text(), "가");
+        assert_eq!(
+            live_next_key("간", &live.text()),
+            Some(KeyPress::plain('s'))
+        );
+
+        live.step(KeyPress::plain('s'));
+        assert_eq!(live_next_key("간", &live.text()), None);
+    }
+
+    #[test]
+    fn live_next_key_is_none_after_diverging() {
+        assert_eq!(live_next_key("간", "거"), None);
+        assert_eq!(live_next_key("가나", "간"), Some(KeyPress::plain('k')));
+    }
+
+    #[test]
+    fn next_key_from_empty_input() {
+        assert_eq!(
+            next_key(Layout::TwoBulsik, "까", ""),
+            Some(KeyPress::shifted('r'))
+        );
+    }
+
+    #[test]
+    fn next_key_none_when_complete() {
+        assert_eq!(next_key(Layout::TwoBulsik, "한글", "한글"), None);
+    }
+
+    #[test]
+    fn next_key_follows_active_layout() {
+        assert_eq!(
+            next_key(Layout::ThreeBulsikFinal, "한", "하"),
+            Some(KeyPress::plain('s'))
+        );
+    }
+
+    #[test]
+    fn next_keys_lists_remaining_keys_for_partial_input() {
+        // 한글 = g k s / r m f; 한 is typed, 글 is not
+        let result = next_keys(Layout::TwoBulsik, PhysicalLayout::Qwerty, "한글", "한");
+        assert_eq!(result.next_key, Some(KeyPress::plain('r')));
+        assert_eq!(
+            result.remaining,
+            vec![
+                KeyPress::plain('r'),
+                KeyPress::plain('m'),
+                KeyPress::plain('f')
+            ]
+        );
+        assert_eq!(result.progress, 0.5);
+    }
+
+    #[test]
+    fn next_keys_empty_when_complete() {
+        let result = next_keys(Layout::TwoBulsik, PhysicalLayout::Qwerty, "한글", "한글");
+        assert_eq!(result.next_key, None);
+        assert!(result.remaining.is_empty());
+        assert_eq!(result.progress, 1.0);
+    }
+
+    #[test]
+    fn next_keys_uses_physical_labels() {
+        let result = next_keys(Layout::TwoBulsik, PhysicalLayout::Dvorak, "가", "ㄱ");
+        assert_eq!(result.next_key, Some(KeyPress::plain('t')));
+    }
+
+    #[test]
+    fn compound_vowel_hint_names_second_key() {
+        assert_eq!(
+            compound_vowel_hint(Layout::TwoBulsik, PhysicalLayout::Qwerty, "와요", "오"),
+            Some("You need to also press k to complete the ㅘ vowel.".to_string())
+        );
+        assert_eq!(
+            compound_vowel_hint(Layout::TwoBulsik, PhysicalLayout::Qwerty, "의", "으"),
+            Some("You need to also press l to complete the ㅢ vowel.".to_string())
+        );
+    }
+
+    #[test]
+    fn no_compound_vowel_hint_for_other_mistakes() {
+        assert_eq!(
+            compound_vowel_hint(Layout::TwoBulsik, PhysicalLayout::Qwerty, "와", "아"),
+            None
+        );
+    }
+
+    #[test]
+    fn local_hint_names_next_key() {
+        let hint = local_hint(Layout::TwoBulsik, PhysicalLayout::Qwerty, "가", "ㄱ");
+        assert_eq!(hint.content, "Try pressing the k key next.");
+        assert_eq!(hint.tool_used.as_deref(), Some(LOCAL_TOOL));
+    }
+}
@@ -0,0 +1,317 @@
+//! 2-Bulsik input method state machine.
+//!
+//! `Composer` turns a raw stream of key presses into Hangul text the same
+//! way a Korean IME does, so a learner's keystrokes can be graded against
+//! the syllables they were meant to produce. Composers can also be kept
+//! alive between commands, keyed by id, to show live composition in the UI;
+//! only the most recently used few are kept.
+
+use crate::cache::LruCache;
+use crate::jamo;
+use crate::layout::{self, KeyPress};
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+/// Composers driven key by key from the frontend
+static COMPOSERS: OnceCell<Mutex<LruCache<Uuid, LiveComposer>>> = OnceCell::new();
+
+/// Live composers kept before the least recently used is dropped, for
+/// frontends that never close theirs
+pub const COMPOSER_CAPACITY: usize = 32;
+
+/// What the learner sees after one key press, as a real IME shows it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Composition {
+    /// Characters finished by this key press
+    pub committed: Vec<char>,
+    /// The syllable still being assembled
+    pub pending: Option<char>,
+}
+
+/// Assembles key presses into syllable blocks
+#[derive(Debug, Clone, Default)]
+pub struct Composer {
+    cho: Option<char>,
+    jung: Option<char>,
+    jong: Option<char>,
+}
+
+impl Composer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed one key press and return any characters committed as a result.
+    ///
+    /// A syllable is committed once the next key can no longer extend it,
+    /// e.g. ㄱ ㅏ ㄴ ㄱ commits 간 and starts a new syllable with ㄱ. A vowel
+    /// after a final consonant moves that consonant to the next syllable
+    /// (단 + ㅏ → 다나); of a compound final only the last part moves
+    /// (닭 + ㅏ → 달가). Keys that produce no jamo commit the pending
+    /// syllable and pass through unchanged.
+    pub fn feed(&mut self, key: KeyPress) -> Vec<char> {
+        let mut committed = Vec::new();
+
+        match layout::key_to_jamo(key) {
+            Some(j) if jamo::is_vowel(j) => self.feed_vowel(j, &mut committed),
+            Some(j) => self.feed_consonant(j, &mut committed),
+            None => {
+                committed.extend(self.flush());
+                committed.push(key.key);
+            }
+        }
+
+        committed
+    }
+
+    /// Feed one key press and report both the committed characters and the
+    /// syllable still being assembled
+    pub fn step(&mut self, key: KeyPress) -> Composition {
+        let committed = self.feed(key);
+        Composition {
+            committed,
+            pending: self.pending(),
+        }
+    }
+
+    /// Commit whatever is still being composed
+    pub fn flush(&mut self) -> Option<char> {
+        let pending = self.pending();
+        *self = Self::default();
+        pending
+    }
+
+    /// The character currently being composed, if any.
+    ///
+    /// A lone initial or vowel is shown as its compatibility jamo.
+    pub fn pending(&self) -> Option<char> {
+        match (self.cho, self.jung, self.jong) {
+            (Some(cho), Some(jung), jong) => jamo::compose(cho, jung, jong),
+            (Some(cho), None, _) => Some(cho),
+            (None, Some(jung), _) => Some(jung),
+            _ => None,
+        }
+    }
+
+    fn feed_consonant(&mut self, c: char, committed: &mut Vec<char>) {
+        match (self.cho, self.jung, self.jong) {
+            // Initial + vowel: the consonant becomes the final if it can be one
+            (Some(_), Some(_), None) if jamo::JONGSEONG.contains(&c) => {
+                self.jong = Some(c);
+            }
+            // Extend a final into a compound final (ㄹ + ㄱ → ㄺ)
+            (Some(_), Some(_), Some(jong)) if jamo::combine(jong, c).is_some() => {
+                self.jong = jamo::combine(jong, c);
+            }
+            // Nothing pending: start a new syllable
+            (None, None, _) => {
+                self.cho = Some(c);
+            }
+            // Anything else completes the pending syllable or jamo
+            _ => {
+                committed.extend(self.flush());
+                self.cho = Some(c);
+            }
+        }
+    }
+
+    fn feed_vowel(&mut self, v: char, committed: &mut Vec<char>) {
+        match (self.cho, self.jung, self.jong) {
+            // A final consonant moves over to start the next syllable;
+            // of a compound final only the second part moves (값 + ㅏ → 갑사)
+            (Some(_), Some(_), Some(jong)) => {
+                let (kept, moved) = match jamo::split_compound(jong) {
+                    Some([first, second]) => (Some(first), second),
+                    None => (None, jong),
+                };
+                self.jong = kept;
+                committed.extend(self.flush());
+                self.cho = Some(moved);
+                self.jung = Some(v);
+            }
+            // Extend a simple vowel into a compound vowel (ㅗ + ㅏ → ㅘ)
+            (_, Some(jung), None) => match jamo::combine(jung, v) {
+                Some(compound) => self.jung = Some(compound),
+                None => {
+                    committed.extend(self.flush());
+                    self.jung = Some(v);
+                }
+            },
+            // Initial alone, or nothing pending
+            _ => {
+                self.jung = Some(v);
+            }
+        }
+    }
+}
+
+/// A composer driven from the frontend, remembering everything it has
+/// committed so far
+#[derive(Debug, Clone, Default)]
+pub struct LiveComposer {
+    composer: Composer,
+    committed: String,
+}
+
+impl LiveComposer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed one key press; see [`Composer::step`]
+    pub fn step(&mut self, key: KeyPress) -> Composition {
+        let composition = self.composer.step(key);
+        self.committed.extend(&composition.committed);
+        composition
+    }
+
+    /// Everything typed so far, including the syllable still being composed
+    pub fn text(&self) -> String {
+        let mut text = self.committed.clone();
+        text.extend(self.composer.pending());
+        text
+    }
+}
+
+/// Get the composers kept alive between commands
+pub fn composers() -> &'static Mutex<LruCache<Uuid, LiveComposer>> {
+    COMPOSERS.get_or_init(|| Mutex::new(LruCache::new(COMPOSER_CAPACITY)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Feed a string of plain keys and return everything committed,
+    /// including the final flush
+    fn type_keys(keys: &str) -> String {
+        let mut composer = Composer::new();
+        let mut out: String = keys
+            .chars()
+            .flat_map(|k| composer.feed(KeyPress::plain(k)))
+            .collect();
+        out.extend(composer.flush());
+        out
+    }
+
+    #[test]
+    fn step_shows_pending_syllable_before_final() {
+        let mut composer = Composer::new();
+
+        let after_g = composer.step(KeyPress::plain('r'));
+        assert_eq!(
+            after_g,
+            Composition {
+                committed: vec![],
+                pending: Some('ㄱ'),
+            }
+        );
+
+        let after_a = composer.step(KeyPress::plain('k'));
+        assert!(after_a.committed.is_empty());
+        assert_eq!(after_a.pending, Some('가'));
+
+        let after_n = composer.step(KeyPress::plain('s'));
+        assert!(after_n.committed.is_empty());
+        assert_eq!(after_n.pending, Some('간'));
+    }
+
+    #[test]
+    fn step_commits_when_next_syllable_starts() {
+        let mut composer = Composer::new();
+        for k in "rks".chars() {
+            composer.step(KeyPress::plain(k));
+        }
+        assert_eq!(
+            composer.step(KeyPress::plain('k')),
+            Composition {
+                committed: vec!['가'],
+                pending: Some('나'),
+            }
+        );
+    }
+
+    #[test]
+    fn live_composer_keeps_committed_text() {
+        let mut live = LiveComposer::new();
+        for k in "rksk".chars() {
+            live.step(KeyPress::plain(k));
+        }
+        assert_eq!(live.text(), "가나");
+    }
+
+    #[test]
+    fn composes_gamsa() {
+        assert_eq!(type_keys("rkatk"), "감사");
+    }
+
+    #[test]
+    fn composes_compound_finals() {
+        assert_eq!(type_keys("rkqt"), "값");
+        assert_eq!(type_keys("dksw"), "앉");
+        // A vowel takes only the second part of the compound
+        assert_eq!(type_keys("rkqtk"), "갑사");
+    }
+
+    #[test]
+    fn vowel_after_compound_final_moves_its_last_part() {
+        let mut composer = Composer::new();
+        for k in "ekfr".chars() {
+            composer.step(KeyPress::plain(k));
+        }
+        assert_eq!(composer.pending(), Some('닭'));
+
+        // 닭 + ㅏ → 달가
+        assert_eq!(
+            composer.step(KeyPress::plain('k')),
+            Composition {
+                committed: vec!['달'],
+                pending: Some('가'),
+            }
+        );
+        // A consonant that can't extend the compound starts a new syllable
+        assert_eq!(type_keys("ekfrr"), "닭ㄱ");
+    }
+
+    #[test]
+    fn commits_syllable_when_next_initial_starts() {
+        let mut composer = Composer::new();
+        let mut committed = Vec::new();
+        for k in "rksr".chars() {
+            committed.extend(composer.feed(KeyPress::plain(k)));
+        }
+        assert_eq!(committed, vec!['간']);
+        assert_eq!(composer.pending(), Some('ㄱ'));
+    }
+
+    #[test]
+    fn migrates_final_to_next_syllable_on_vowel() {
+        assert_eq!(type_keys("eksk"), "다나");
+    }
+
+    #[test]
+    fn composes_compound_vowel() {
+        assert_eq!(type_keys("dhk"), "와");
+    }
+
+    #[test]
+    fn composes_double_consonant_with_shift() {
+        let mut composer = Composer::new();
+        composer.feed(KeyPress::shifted('r'));
+        composer.feed(KeyPress::plain('k'));
+        assert_eq!(composer.flush(), Some('까'));
+    }
+
+    #[test]
+    fn leaves_uncombinable_jamo_as_orphans() {
+        assert_eq!(type_keys("rr"), "ㄱㄱ");
+        assert_eq!(type_keys("k"), "ㅏ");
+    }
+
+    #[test]
+    fn passes_through_non_jamo_keys() {
+        assert_eq!(type_keys("rk rk"), "가 가");
+    }
+}
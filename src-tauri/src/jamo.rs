@@ -0,0 +1,831 @@
+//! Hangul jamo utilities.
+//!
+//! Splits precomposed syllable blocks into their component jamo using the
+//! Unicode Hangul syllable formula, so the backend can reason about typing
+//! targets without round-tripping through Copilot.
+//!
+//! Old Hangul syllables with archaic jamo (ㅿ, ㆁ, ㆆ, ㅸ, ㆍ) have no
+//! precomposed form. They are handled as conjoining jamo sequences by
+//! [`compose_old`] and [`decompose_old`] once enabled with [`set_archaic`];
+//! [`compose`] and [`decompose`] only ever deal in modern syllables.
+
+use crate::layout;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use thiserror::Error;
+
+/// Whether old Hangul syllables with archaic jamo are recognised
+static ARCHAIC: AtomicBool = AtomicBool::new(false);
+
+/// First precomposed Hangul syllable (가)
+const SYLLABLE_BASE: u32 = 0xAC00;
+
+/// Last precomposed Hangul syllable (힣)
+const SYLLABLE_END: u32 = 0xD7A3;
+
+/// Hangul Compatibility Jamo block, used for standalone jamo (ㄱ, ㅏ)
+const COMPAT_JAMO_START: u32 = 0x3130;
+const COMPAT_JAMO_END: u32 = 0x318F;
+
+/// Conjoining jamo used by decomposed (NFD) text: 19 leading consonants,
+/// 21 vowels and 27 trailing consonants, in the same order as the
+/// compatibility jamo tables below
+const LEADING_START: u32 = 0x1100;
+const VOWEL_START: u32 = 0x1161;
+const TRAILING_START: u32 = 0x11A8;
+
+/// Number of syllables sharing one initial consonant (21 medials * 28 finals)
+const INITIAL_STRIDE: u32 = 588;
+
+/// Number of syllables sharing one initial + medial pair
+const MEDIAL_STRIDE: u32 = 28;
+
+/// Initial consonants (초성) in syllable order
+pub const CHOSEONG: [char; 19] = [
+    'ㄱ', 'ㄲ', 'ㄴ', 'ㄷ', 'ㄸ', 'ㄹ', 'ㅁ', 'ㅂ', 'ㅃ', 'ㅅ', 'ㅆ', 'ㅇ', 'ㅈ', 'ㅉ', 'ㅊ', 'ㅋ',
+    'ㅌ', 'ㅍ', 'ㅎ',
+];
+
+/// Medial vowels (중성) in syllable order
+pub const JUNGSEONG: [char; 21] = [
+    'ㅏ', 'ㅐ', 'ㅑ', 'ㅒ', 'ㅓ', 'ㅔ', 'ㅕ', 'ㅖ', 'ㅗ', 'ㅘ', 'ㅙ', 'ㅚ', 'ㅛ', 'ㅜ', 'ㅝ', 'ㅞ',
+    'ㅟ', 'ㅠ', 'ㅡ', 'ㅢ', 'ㅣ',
+];
+
+/// Final consonants (종성) in syllable order, excluding the "no final" slot
+pub const JONGSEONG: [char; 27] = [
+    'ㄱ', 'ㄲ', 'ㄳ', 'ㄴ', 'ㄵ', 'ㄶ', 'ㄷ', 'ㄹ', 'ㄺ', 'ㄻ', 'ㄼ', 'ㄽ', 'ㄾ', 'ㄿ', 'ㅀ', 'ㅁ',
+    'ㅂ', 'ㅄ', 'ㅅ', 'ㅆ', 'ㅇ', 'ㅈ', 'ㅊ', 'ㅋ', 'ㅌ', 'ㅍ', 'ㅎ',
+];
+
+/// Split a precomposed syllable into (initial, medial, optional final).
+///
+/// Jamo are returned as compatibility jamo (e.g. 'ㅎ' U+314E), matching what
+/// the keyboard and frontend display. Returns `None` for anything that is not
+/// a precomposed syllable block, including standalone jamo and Latin letters.
+pub fn decompose(syllable: char) -> Option<(char, char, Option<char>)> {
+    let code = syllable as u32;
+    if !(SYLLABLE_BASE..=SYLLABLE_END).contains(&code) {
+        return None;
+    }
+
+    let offset = code - SYLLABLE_BASE;
+    let cho = (offset / INITIAL_STRIDE) as usize;
+    let jung = ((offset % INITIAL_STRIDE) / MEDIAL_STRIDE) as usize;
+    let jong = (offset % MEDIAL_STRIDE) as usize;
+
+    let final_jamo = if jong == 0 {
+        None
+    } else {
+        Some(JONGSEONG[jong - 1])
+    };
+
+    Some((CHOSEONG[cho], JUNGSEONG[jung], final_jamo))
+}
+
+/// Build a precomposed syllable from its component jamo.
+///
+/// `cho` must be one of the 19 initials, `jung` one of the 21 medials and
+/// `jong` (if present) one of the 27 finals. Returns `None` when any component
+/// falls outside its valid set.
+pub fn compose(cho: char, jung: char, jong: Option<char>) -> Option<char> {
+    let cho_index = CHOSEONG.iter().position(|&c| c == cho)? as u32;
+    let jung_index = JUNGSEONG.iter().position(|&c| c == jung)? as u32;
+    let jong_index = match jong {
+        Some(j) => JONGSEONG.iter().position(|&c| c == j)? as u32 + 1,
+        None => 0,
+    };
+
+    char::from_u32(
+        SYLLABLE_BASE + cho_index * INITIAL_STRIDE + jung_index * MEDIAL_STRIDE + jong_index,
+    )
+}
+
+/// Archaic initials as (compatibility jamo, conjoining leading jamo)
+const ARCHAIC_INITIALS: [(char, char); 4] = [
+    ('ㅿ', '\u{1140}'),
+    ('ㆁ', '\u{114C}'),
+    ('ㆆ', '\u{1159}'),
+    ('ㅸ', '\u{112B}'),
+];
+
+/// Archaic medials as (compatibility jamo, conjoining vowel)
+const ARCHAIC_MEDIALS: [(char, char); 2] = [('ㆍ', '\u{119E}'), ('ㆎ', '\u{11A1}')];
+
+/// Archaic finals as (compatibility jamo, conjoining trailing jamo)
+const ARCHAIC_FINALS: [(char, char); 3] =
+    [('ㅿ', '\u{11EB}'), ('ㆁ', '\u{11F0}'), ('ㆆ', '\u{11F9}')];
+
+/// Recognise old Hangul syllables with archaic jamo in [`compose_old`] and
+/// [`decompose_old`]. Off by default; modern text is unaffected either way.
+pub fn set_archaic(enabled: bool) {
+    ARCHAIC.store(enabled, Ordering::SeqCst);
+}
+
+pub fn archaic_enabled() -> bool {
+    ARCHAIC.load(Ordering::SeqCst)
+}
+
+/// Conjoining form of one syllable part, modern or archaic
+fn to_conjoining(
+    jamo: char,
+    modern: &[char],
+    start: u32,
+    archaic: &[(char, char)],
+) -> Option<char> {
+    match modern.iter().position(|&c| c == jamo) {
+        Some(i) => char::from_u32(start + i as u32),
+        None => archaic
+            .iter()
+            .find(|(compat, _)| *compat == jamo)
+            .map(|&(_, conjoining)| conjoining),
+    }
+}
+
+/// Compatibility form of one conjoining syllable part, modern or archaic
+fn from_conjoining(
+    jamo: char,
+    modern: &[char],
+    start: u32,
+    archaic: &[(char, char)],
+) -> Option<char> {
+    match (jamo as u32).checked_sub(start) {
+        Some(i) if (i as usize) < modern.len() => Some(modern[i as usize]),
+        _ => archaic
+            .iter()
+            .find(|(_, conjoining)| *conjoining == jamo)
+            .map(|&(compat, _)| compat),
+    }
+}
+
+/// Whether any part of a syllable is an archaic jamo
+fn has_archaic(cho: char, jung: char, jong: Option<char>) -> bool {
+    ARCHAIC_INITIALS.iter().any(|&(c, _)| c == cho)
+        || ARCHAIC_MEDIALS.iter().any(|&(c, _)| c == jung)
+        || jong.is_some_and(|j| ARCHAIC_FINALS.iter().any(|&(c, _)| c == j))
+}
+
+/// Build a syllable that may use archaic jamo.
+///
+/// Modern syllables come back precomposed, exactly as [`compose`] builds
+/// them. Syllables with archaic jamo come back as a conjoining jamo
+/// sequence, since Unicode has no precomposed form for them, or `None`
+/// unless [`set_archaic`] has enabled them.
+pub fn compose_old(cho: char, jung: char, jong: Option<char>) -> Option<String> {
+    compose_old_with(cho, jung, jong, archaic_enabled())
+}
+
+/// [`compose_old`] with archaic jamo explicitly allowed or not
+fn compose_old_with(cho: char, jung: char, jong: Option<char>, archaic: bool) -> Option<String> {
+    if !has_archaic(cho, jung, jong) {
+        return compose(cho, jung, jong).map(String::from);
+    }
+    if !archaic {
+        return None;
+    }
+
+    let mut syllable = String::new();
+    syllable.push(to_conjoining(
+        cho,
+        &CHOSEONG,
+        LEADING_START,
+        &ARCHAIC_INITIALS,
+    )?);
+    syllable.push(to_conjoining(
+        jung,
+        &JUNGSEONG,
+        VOWEL_START,
+        &ARCHAIC_MEDIALS,
+    )?);
+    if let Some(jong) = jong {
+        syllable.push(to_conjoining(
+            jong,
+            &JONGSEONG,
+            TRAILING_START,
+            &ARCHAIC_FINALS,
+        )?);
+    }
+    Some(syllable)
+}
+
+/// Split a syllable that may use archaic jamo into compatibility jamo.
+///
+/// Accepts one precomposed syllable, as [`decompose`] does, or, when
+/// [`set_archaic`] has enabled them, one old Hangul syllable written as
+/// conjoining jamo (leading consonant, vowel, optional trailing consonant).
+pub fn decompose_old(syllable: &str) -> Option<(char, char, Option<char>)> {
+    decompose_old_with(syllable, archaic_enabled())
+}
+
+/// [`decompose_old`] with archaic jamo explicitly allowed or not
+fn decompose_old_with(syllable: &str, archaic: bool) -> Option<(char, char, Option<char>)> {
+    let mut chars = syllable.chars();
+    match (chars.next(), chars.next(), chars.next(), chars.next()) {
+        (Some(c), None, None, None) => decompose(c),
+        (Some(cho), Some(jung), jong, None) if archaic => {
+            let cho = from_conjoining(cho, &CHOSEONG, LEADING_START, &ARCHAIC_INITIALS)?;
+            let jung = from_conjoining(jung, &JUNGSEONG, VOWEL_START, &ARCHAIC_MEDIALS)?;
+            let jong = match jong {
+                Some(j) => Some(from_conjoining(
+                    j,
+                    &JONGSEONG,
+                    TRAILING_START,
+                    &ARCHAIC_FINALS,
+                )?),
+                None => None,
+            };
+            has_archaic(cho, jung, jong).then_some((cho, jung, jong))
+        }
+        _ => None,
+    }
+}
+
+/// Compound vowels and finals with the two jamo typed to produce them
+const COMPOUNDS: [(char, [char; 2]); 18] = [
+    // Compound vowels
+    ('ㅘ', ['ㅗ', 'ㅏ']),
+    ('ㅙ', ['ㅗ', 'ㅐ']),
+    ('ㅚ', ['ㅗ', 'ㅣ']),
+    ('ㅝ', ['ㅜ', 'ㅓ']),
+    ('ㅞ', ['ㅜ', 'ㅔ']),
+    ('ㅟ', ['ㅜ', 'ㅣ']),
+    ('ㅢ', ['ㅡ', 'ㅣ']),
+    // Compound finals
+    ('ㄳ', ['ㄱ', 'ㅅ']),
+    ('ㄵ', ['ㄴ', 'ㅈ']),
+    ('ㄶ', ['ㄴ', 'ㅎ']),
+    ('ㄺ', ['ㄹ', 'ㄱ']),
+    ('ㄻ', ['ㄹ', 'ㅁ']),
+    ('ㄼ', ['ㄹ', 'ㅂ']),
+    ('ㄽ', ['ㄹ', 'ㅅ']),
+    ('ㄾ', ['ㄹ', 'ㅌ']),
+    ('ㄿ', ['ㄹ', 'ㅍ']),
+    ('ㅀ', ['ㄹ', 'ㅎ']),
+    ('ㅄ', ['ㅂ', 'ㅅ']),
+];
+
+/// Split a compound vowel or compound final into its two component jamo
+pub fn split_compound(jamo: char) -> Option<[char; 2]> {
+    COMPOUNDS
+        .iter()
+        .find(|(compound, _)| *compound == jamo)
+        .map(|(_, parts)| *parts)
+}
+
+/// Combine two jamo into a compound vowel or compound final, if they form one
+pub fn combine(first: char, second: char) -> Option<char> {
+    COMPOUNDS
+        .iter()
+        .find(|(_, parts)| *parts == [first, second])
+        .map(|(compound, _)| *compound)
+}
+
+/// Split a medial vowel into the simple vowels typed to produce it.
+///
+/// Compound vowels give two (ㅘ → ㅗ ㅏ), simple vowels themselves, and
+/// anything that is not a vowel nothing.
+pub fn decompose_medial(c: char) -> Vec<char> {
+    if !is_vowel(c) {
+        return Vec::new();
+    }
+    match split_compound(c) {
+        Some(parts) => parts.to_vec(),
+        None => vec![c],
+    }
+}
+
+/// Whether `actual` is the first half of the compound vowel `expected`
+/// (ㅗ typed for ㅘ)
+fn is_incomplete_compound_vowel(expected: char, actual: char) -> bool {
+    matches!(decompose_medial(expected)[..], [first, _] if first == actual)
+}
+
+/// Whether `c` is a medial vowel jamo
+pub fn is_vowel(c: char) -> bool {
+    JUNGSEONG.contains(&c)
+}
+
+/// Whether `c` is a consonant jamo (initial or final)
+pub fn is_consonant(c: char) -> bool {
+    CHOSEONG.contains(&c) || JONGSEONG.contains(&c)
+}
+
+/// Whether `c` is a precomposed syllable block (가), as opposed to a jamo
+pub fn is_complete_syllable(c: char) -> bool {
+    (SYLLABLE_BASE..=SYLLABLE_END).contains(&(c as u32))
+}
+
+/// Writing system a character belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Script {
+    /// Syllable blocks and jamo, composed or conjoining
+    Hangul,
+    /// Chinese characters as used in Korean (漢字)
+    Hanja,
+    /// Japanese hiragana and katakana
+    Kana,
+    Cyrillic,
+    Latin,
+    /// Digits, punctuation, spaces and other scripts
+    Other,
+}
+
+/// Which script `c` is written in
+pub fn script_of(c: char) -> Script {
+    match c as u32 {
+        SYLLABLE_BASE..=SYLLABLE_END
+        | 0x1100..=0x11FF
+        | COMPAT_JAMO_START..=COMPAT_JAMO_END
+        | 0xA960..=0xA97F
+        | 0xD7B0..=0xD7FF => Script::Hangul,
+        // CJK Unified Ideographs, Extension A and Compatibility Ideographs
+        0x4E00..=0x9FFF | 0x3400..=0x4DBF | 0xF900..=0xFAFF => Script::Hanja,
+        0x3040..=0x30FF => Script::Kana,
+        0x0400..=0x04FF => Script::Cyrillic,
+        0x00C0..=0x024F if c.is_alphabetic() => Script::Latin,
+        _ if c.is_ascii_alphabetic() => Script::Latin,
+        _ => Script::Other,
+    }
+}
+
+/// Text contained Hanja, which can't be typed as Hangul
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error(
+    "\"{hanja}\" is Hanja (Chinese characters), which can't be typed as Hangul. Use the Korean reading instead."
+)]
+pub struct HanjaError {
+    /// The Hanja found, in order
+    pub hanja: String,
+}
+
+/// Fail if `text` contains any Hanja
+pub fn reject_hanja(text: &str) -> Result<(), HanjaError> {
+    let hanja: String = text
+        .chars()
+        .filter(|&c| script_of(c) == Script::Hanja)
+        .collect();
+    if hanja.is_empty() {
+        Ok(())
+    } else {
+        Err(HanjaError { hanja })
+    }
+}
+
+/// Extra difficulty for a compound vowel or compound final
+const COMPOUND_PENALTY: u8 = 2;
+
+/// Score how hard a syllable block is to type on 2-Bulsik.
+///
+/// The score is the number of keystrokes, plus one for each keystroke that
+/// needs shift, plus [`COMPOUND_PENALTY`] each for a compound vowel and a
+/// compound final. So 가 scores 2, 까 3, 값 6 and 뷁 9. Anything that is
+/// not a syllable block scores 0.
+pub fn difficulty(syllable: char) -> u8 {
+    let (Some((_, jung, jong)), Some(keys)) =
+        (decompose(syllable), layout::keystrokes_for(syllable))
+    else {
+        return 0;
+    };
+    let shifted = keys.iter().filter(|k| k.shift).count();
+    let compounds = [Some(jung), jong]
+        .into_iter()
+        .flatten()
+        .filter(|&j| split_compound(j).is_some())
+        .count() as u8;
+    (keys.len() + shifted) as u8 + compounds * COMPOUND_PENALTY
+}
+
+/// Whether `c` is a standalone compatibility jamo that did not combine
+/// into a syllable block
+pub fn is_orphan_jamo(c: char) -> bool {
+    (COMPAT_JAMO_START..=COMPAT_JAMO_END).contains(&(c as u32))
+}
+
+/// Whether text contains jamo that did not combine into syllables, such as
+/// "ㄱㅏ" typed instead of "가"
+pub fn contains_orphan_jamo(s: &str) -> bool {
+    s.chars().any(is_orphan_jamo)
+}
+
+/// Compose decomposed (NFD) Hangul into precomposed syllable blocks (NFC).
+///
+/// Runs of conjoining jamo (ᄀ U+1100 + ᅡ U+1161) become syllables (가),
+/// and a trailing jamo after an open syllable becomes its final. Text that
+/// is already composed is returned unchanged. Compatibility jamo (ㄱ ㅏ)
+/// are left alone, since separate jamo are meaningful in typed input.
+pub fn normalize(s: &str) -> String {
+    let mut out: Vec<char> = Vec::with_capacity(s.len());
+
+    for c in s.chars() {
+        let code = c as u32;
+        let last = out.last().map(|&l| l as u32);
+
+        let composed = match last {
+            // Leading consonant + vowel
+            Some(l)
+                if (LEADING_START..LEADING_START + 19).contains(&l)
+                    && (VOWEL_START..VOWEL_START + 21).contains(&code) =>
+            {
+                let (cho, jung) = (l - LEADING_START, code - VOWEL_START);
+                char::from_u32(SYLLABLE_BASE + cho * INITIAL_STRIDE + jung * MEDIAL_STRIDE)
+            }
+            // Open syllable + trailing consonant
+            Some(l)
+                if (SYLLABLE_BASE..=SYLLABLE_END).contains(&l)
+                    && (l - SYLLABLE_BASE).is_multiple_of(MEDIAL_STRIDE)
+                    && (TRAILING_START..TRAILING_START + 27).contains(&code) =>
+            {
+                char::from_u32(l + code - TRAILING_START + 1)
+            }
+            _ => None,
+        };
+
+        match composed {
+            Some(syllable) => *out.last_mut().expect("checked above") = syllable,
+            None => out.push(c),
+        }
+    }
+
+    out.into_iter().collect()
+}
+
+/// Flatten text into jamo: each syllable block becomes its initial, medial
+/// and final, and any other character stands for itself
+fn flat_jamo(s: &str) -> Vec<char> {
+    normalize(s)
+        .chars()
+        .flat_map(|c| match decompose(c) {
+            Some((cho, jung, jong)) => [Some(cho), Some(jung), jong],
+            None => [Some(c), None, None],
+        })
+        .flatten()
+        .collect()
+}
+
+/// Number of jamo in `text`, counting each non-Hangul character as one
+pub fn jamo_count(text: &str) -> usize {
+    flat_jamo(text).len()
+}
+
+/// Levenshtein distance between `target` and `typed`, counted in jamo.
+///
+/// A wrong or missing final costs 1 rather than a whole syllable, so
+/// mostly-correct input can earn partial credit.
+pub fn jamo_edit_distance(target: &str, typed: &str) -> usize {
+    let target = flat_jamo(target);
+    let typed = flat_jamo(typed);
+
+    // One row of the DP table at a time
+    let mut previous: Vec<usize> = (0..=typed.len()).collect();
+    for (i, t) in target.iter().enumerate() {
+        let mut current = vec![i + 1; typed.len() + 1];
+        for (j, g) in typed.iter().enumerate() {
+            let substitute = previous[j] + usize::from(t != g);
+            current[j + 1] = substitute.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[typed.len()]
+}
+
+/// The kinds of mistake a learner can make when typing a syllable
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MistakeKind {
+    WrongInitial,
+    WrongMedial,
+    WrongFinal,
+    MissingFinal,
+    ExtraFinal,
+    TransposedJamo,
+    ShiftOmitted,
+    /// Only the first vowel of a compound vowel was typed (ㅗ for ㅘ)
+    IncompleteCompoundVowel,
+}
+
+/// Whether `expected` is the shifted form of `actual` (ㄲ typed as ㄱ)
+fn is_shift_omitted(expected: char, actual: char) -> bool {
+    matches!(
+        (expected, actual),
+        ('ㄲ', 'ㄱ')
+            | ('ㄸ', 'ㄷ')
+            | ('ㅃ', 'ㅂ')
+            | ('ㅆ', 'ㅅ')
+            | ('ㅉ', 'ㅈ')
+            | ('ㅒ', 'ㅐ')
+            | ('ㅖ', 'ㅔ')
+    )
+}
+
+/// Classify how `actual` differs from `expected`.
+///
+/// Compares the decomposed initial, medial and final of both syllables and
+/// reports one entry per differing component. An initial and final that
+/// were swapped are reported once as `TransposedJamo`, and a missing Shift
+/// (ㄲ typed as ㄱ) as `ShiftOmitted`, and a compound vowel typed as just
+/// its first vowel (오 for 와) as `IncompleteCompoundVowel`. Standalone
+/// jamo are compared directly. Returns an empty list when the characters match or cannot be
+/// compared.
+pub fn classify(expected: char, actual: char) -> Vec<MistakeKind> {
+    if expected == actual {
+        return Vec::new();
+    }
+
+    let (Some(exp), Some(act)) = (decompose(expected), decompose(actual)) else {
+        if is_shift_omitted(expected, actual) {
+            return vec![MistakeKind::ShiftOmitted];
+        }
+        if is_incomplete_compound_vowel(expected, actual) {
+            return vec![MistakeKind::IncompleteCompoundVowel];
+        }
+        return Vec::new();
+    };
+    let (exp_cho, exp_jung, exp_jong) = exp;
+    let (act_cho, act_jung, act_jong) = act;
+
+    let mut mistakes = Vec::new();
+
+    let transposed = exp_cho != act_cho && exp_jong == Some(act_cho) && act_jong == Some(exp_cho);
+    if transposed {
+        mistakes.push(MistakeKind::TransposedJamo);
+    } else if exp_cho != act_cho {
+        mistakes.push(if is_shift_omitted(exp_cho, act_cho) {
+            MistakeKind::ShiftOmitted
+        } else {
+            MistakeKind::WrongInitial
+        });
+    }
+
+    if exp_jung != act_jung {
+        mistakes.push(if is_shift_omitted(exp_jung, act_jung) {
+            MistakeKind::ShiftOmitted
+        } else if is_incomplete_compound_vowel(exp_jung, act_jung) {
+            MistakeKind::IncompleteCompoundVowel
+        } else {
+            MistakeKind::WrongMedial
+        });
+    }
+
+    if !transposed {
+        match (exp_jong, act_jong) {
+            (Some(_), None) => mistakes.push(MistakeKind::MissingFinal),
+            (None, Some(_)) => mistakes.push(MistakeKind::ExtraFinal),
+            (Some(e), Some(a)) if e != a => mistakes.push(if is_shift_omitted(e, a) {
+                MistakeKind::ShiftOmitted
+            } else {
+                MistakeKind::WrongFinal
+            }),
+            _ => {}
+        }
+    }
+
+    mistakes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_scripts() {
+        assert_eq!(script_of('한'), Script::Hangul);
+        assert_eq!(script_of('ㄱ'), Script::Hangul);
+        assert_eq!(script_of('漢'), Script::Hanja);
+        assert_eq!(script_of('か'), Script::Kana);
+        assert_eq!(script_of('д'), Script::Cyrillic);
+        assert_eq!(script_of('a'), Script::Latin);
+        assert_eq!(script_of('é'), Script::Latin);
+        assert_eq!(script_of('1'), Script::Other);
+    }
+
+    #[test]
+    fn reject_hanja_names_the_characters() {
+        assert_eq!(reject_hanja("한국어 text"), Ok(()));
+        assert_eq!(
+            reject_hanja("韓國語 한국어"),
+            Err(HanjaError {
+                hanja: "韓國語".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn difficulty_grows_with_compounds() {
+        assert_eq!(difficulty('가'), 2);
+        assert!(difficulty('가') < difficulty('값'));
+        assert!(difficulty('값') < difficulty('뷁'));
+    }
+
+    #[test]
+    fn shifted_keys_add_difficulty() {
+        assert_eq!(difficulty('까'), difficulty('가') + 1);
+        assert_eq!(difficulty('a'), 0);
+    }
+
+    #[test]
+    fn decompose_medial_splits_compound_vowels() {
+        assert_eq!(decompose_medial('ㅘ'), vec!['ㅗ', 'ㅏ']);
+        assert_eq!(decompose_medial('ㅝ'), vec!['ㅜ', 'ㅓ']);
+        assert_eq!(decompose_medial('ㅢ'), vec!['ㅡ', 'ㅣ']);
+        assert_eq!(decompose_medial('ㅏ'), vec!['ㅏ']);
+        assert!(decompose_medial('ㄱ').is_empty());
+    }
+
+    #[test]
+    fn classify_incomplete_compound_vowel() {
+        for (expected, actual) in [('와', '오'), ('워', '우'), ('의', '으')] {
+            assert_eq!(
+                classify(expected, actual),
+                vec![MistakeKind::IncompleteCompoundVowel],
+                "{} typed as {}",
+                expected,
+                actual
+            );
+        }
+        assert_eq!(
+            classify('ㅘ', 'ㅗ'),
+            vec![MistakeKind::IncompleteCompoundVowel]
+        );
+    }
+
+    #[test]
+    fn decomposes_open_syllable() {
+        assert_eq!(decompose('가'), Some(('ㄱ', 'ㅏ', None)));
+    }
+
+    #[test]
+    fn decomposes_syllable_with_final() {
+        assert_eq!(decompose('한'), Some(('ㅎ', 'ㅏ', Some('ㄴ'))));
+    }
+
+    #[test]
+    fn decomposes_compound_vowel_and_final() {
+        assert_eq!(decompose('뷁'), Some(('ㅂ', 'ㅞ', Some('ㄺ'))));
+    }
+
+    #[test]
+    fn distinguishes_composed_syllable_from_orphan_jamo() {
+        assert!(is_complete_syllable('가'));
+        assert!(!is_complete_syllable('ㄱ'));
+
+        assert!(!contains_orphan_jamo("가"));
+        assert!(contains_orphan_jamo("ㄱㅏ"));
+        assert!(contains_orphan_jamo("한ㄱ"));
+        assert!(!contains_orphan_jamo("hello 한글"));
+    }
+
+    #[test]
+    fn rejects_non_syllable_characters() {
+        assert_eq!(decompose('a'), None);
+        assert_eq!(decompose('ㄱ'), None);
+    }
+
+    #[test]
+    fn composes_first_syllable() {
+        assert_eq!(compose('ㄱ', 'ㅏ', None), Some('가'));
+    }
+
+    #[test]
+    fn composes_last_syllable() {
+        assert_eq!(compose('ㅎ', 'ㅣ', Some('ㅎ')), Some('힣'));
+    }
+
+    #[test]
+    fn rejects_components_outside_their_set() {
+        // ㄸ is never a final, ㅏ is never an initial
+        assert_eq!(compose('ㄱ', 'ㅏ', Some('ㄸ')), None);
+        assert_eq!(compose('ㅏ', 'ㅏ', None), None);
+        assert_eq!(compose('ㄱ', 'ㄱ', None), None);
+    }
+
+    #[test]
+    fn combine_inverts_split_compound() {
+        assert_eq!(split_compound('ㅘ'), Some(['ㅗ', 'ㅏ']));
+        assert_eq!(combine('ㄹ', 'ㄱ'), Some('ㄺ'));
+        assert_eq!(combine('ㄱ', 'ㄹ'), None);
+        assert_eq!(split_compound('ㄱ'), None);
+    }
+
+    #[test]
+    fn normalize_composes_nfd_syllables() {
+        // ᄀ + ᅡ
+        assert_eq!(normalize("\u{1100}\u{1161}"), "가");
+        // ᄒ + ᅡ + ᆫ, ᄀ + ᅳ + ᆯ
+        assert_eq!(
+            normalize("\u{1112}\u{1161}\u{11AB}\u{1100}\u{1173}\u{11AF}"),
+            "한글"
+        );
+    }
+
+    #[test]
+    fn normalize_leaves_composed_text_and_compat_jamo() {
+        assert_eq!(normalize("한글 ㄱㅏ abc"), "한글 ㄱㅏ abc");
+    }
+
+    #[test]
+    fn classify_matching_syllables_has_no_mistakes() {
+        assert!(classify('한', '한').is_empty());
+    }
+
+    #[test]
+    fn classify_wrong_initial() {
+        assert_eq!(classify('가', '나'), vec![MistakeKind::WrongInitial]);
+    }
+
+    #[test]
+    fn classify_wrong_medial() {
+        assert_eq!(classify('가', '거'), vec![MistakeKind::WrongMedial]);
+    }
+
+    #[test]
+    fn classify_wrong_final() {
+        assert_eq!(classify('간', '감'), vec![MistakeKind::WrongFinal]);
+    }
+
+    #[test]
+    fn classify_missing_final() {
+        assert_eq!(classify('간', '가'), vec![MistakeKind::MissingFinal]);
+    }
+
+    #[test]
+    fn classify_extra_final() {
+        assert_eq!(classify('가', '각'), vec![MistakeKind::ExtraFinal]);
+    }
+
+    #[test]
+    fn classify_transposed_initial_and_final() {
+        // 갓 = ㄱㅏㅅ, 삭 = ㅅㅏㄱ
+        assert_eq!(classify('갓', '삭'), vec![MistakeKind::TransposedJamo]);
+    }
+
+    #[test]
+    fn classify_shift_omitted() {
+        assert_eq!(classify('까', '가'), vec![MistakeKind::ShiftOmitted]);
+        assert_eq!(classify('ㄲ', 'ㄱ'), vec![MistakeKind::ShiftOmitted]);
+        assert_eq!(classify('얘', '애'), vec![MistakeKind::ShiftOmitted]);
+    }
+
+    #[test]
+    fn classify_reports_each_differing_component() {
+        assert_eq!(
+            classify('간', '너'),
+            vec![
+                MistakeKind::WrongInitial,
+                MistakeKind::WrongMedial,
+                MistakeKind::MissingFinal
+            ]
+        );
+    }
+
+    #[test]
+    fn compose_inverts_decompose() {
+        for c in ['한', '뷁', '값', '닭'] {
+            let (cho, jung, jong) = decompose(c).unwrap();
+            assert_eq!(compose(cho, jung, jong), Some(c));
+        }
+    }
+
+    #[test]
+    fn edit_distance_counts_jamo_not_syllables() {
+        assert_eq!(jamo_edit_distance("간", "감"), 1);
+        assert_eq!(jamo_edit_distance("간", "가"), 1);
+        assert_eq!(jamo_edit_distance("한글", "한글"), 0);
+        assert_eq!(jamo_edit_distance("한글", ""), 6);
+        assert_eq!(jamo_edit_distance("가", "\u{1100}\u{1161}"), 0);
+    }
+
+    #[test]
+    fn archaic_yesieung_only_when_enabled() {
+        // ㅂ ㅏ with a yesieung final, the old spelling of 방
+        let old = "\u{1107}\u{1161}\u{11F0}";
+
+        // The modern functions don't know archaic jamo
+        assert_eq!(compose('ㅂ', 'ㅏ', Some('ㆁ')), None);
+        assert_eq!(compose_old_with('ㅂ', 'ㅏ', Some('ㆁ'), false), None);
+        assert_eq!(decompose_old_with(old, false), None);
+        assert_eq!(
+            compose_old_with('ㅂ', 'ㅏ', Some('ㅇ'), false),
+            Some("방".to_string())
+        );
+
+        assert_eq!(
+            compose_old_with('ㅂ', 'ㅏ', Some('ㆁ'), true).as_deref(),
+            Some(old)
+        );
+        assert_eq!(
+            decompose_old_with(old, true),
+            Some(('ㅂ', 'ㅏ', Some('ㆁ')))
+        );
+        // ᅌ as an initial, with araea
+        let initial = compose_old_with('ㆁ', 'ㆍ', None, true).unwrap();
+        assert_eq!(decompose_old_with(&initial, true), Some(('ㆁ', 'ㆍ', None)));
+        // Modern syllables are unchanged
+        assert_eq!(
+            decompose_old_with("방", true),
+            Some(('ㅂ', 'ㅏ', Some('ㅇ')))
+        );
+        // A modern syllable spelled with conjoining jamo isn't old Hangul
+        assert_eq!(decompose_old_with("\u{1107}\u{1161}\u{11BC}", true), None);
+    }
+}
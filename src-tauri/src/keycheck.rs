@@ -0,0 +1,269 @@
+//! Cross-checking key claims in Copilot's answers.
+//!
+//! The AI sometimes names the wrong key in otherwise good prose ("press
+//! the E key for ㄱ"). Key claims are picked out of each sentence and
+//! compared with the layout tables; wrong ones get a correction appended,
+//! since which key to press is the one thing a typing tutor must get right.
+
+use crate::jamo;
+use crate::layout::{KeyPress, Layout, PhysicalLayout, Position};
+
+/// Verbs that introduce a key claim ("press r", "hit the K key")
+const PRESS_VERBS: [&str; 8] = [
+    "press", "presses", "pressing", "hit", "tap", "type", "typing", "use",
+];
+
+/// Words that can sit between keys in a claim ("r and then k")
+const SEPARATORS: [&str; 6] = ["and", "then", "followed", "by", "key", "keys"];
+
+/// One whitespace-separated word of a sentence
+struct Word<'a> {
+    raw: &'a str,
+    /// Lowercased, without surrounding punctuation or quotes
+    core: String,
+}
+
+impl<'a> Word<'a> {
+    fn new(raw: &'a str) -> Self {
+        Self {
+            raw,
+            core: raw
+                .trim_matches(|c: char| !c.is_alphanumeric())
+                .to_lowercase(),
+        }
+    }
+
+    /// The key this word names, if it is a single letter or digit
+    fn key(&self) -> Option<char> {
+        let mut chars = self.core.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) if c.is_ascii_alphanumeric() => Some(c),
+            _ => None,
+        }
+    }
+
+    /// Whether the key was written so it can't be the article "a"
+    fn is_marked(&self) -> bool {
+        self.raw.trim_matches(|c: char| !c.is_alphanumeric()) != "a"
+            || self.raw.starts_with(['\'', '"', '`', '['])
+    }
+}
+
+/// Keys a sentence claims should be pressed, in order, lowercased.
+///
+/// Recognises "press/hit/tap/type [the] X", lists after them ("r, k and
+/// then s", "Shift + R") and "the X key" anywhere. Only letter and digit
+/// keys are recognised; a bare lowercase "a" is taken as the article
+/// unless it follows "the" or is quoted.
+pub fn key_claims(sentence: &str) -> Vec<char> {
+    let spaced = sentence.replace('+', " + ");
+    let words: Vec<Word> = spaced.split_whitespace().map(Word::new).collect();
+    let after_the = |i: usize| i > 0 && words[i - 1].core == "the";
+    let mut claims = Vec::new();
+    let mut push = |c: char| {
+        if !claims.contains(&c) {
+            claims.push(c);
+        }
+    };
+
+    for (i, word) in words.iter().enumerate() {
+        // "the X key"
+        if word.core == "key"
+            && i > 0
+            && let Some(key) = words[i - 1].key()
+            && (words[i - 1].is_marked() || after_the(i - 1))
+        {
+            push(key);
+        }
+
+        if !PRESS_VERBS.contains(&word.core.as_str()) {
+            continue;
+        }
+        for (j, next) in words.iter().enumerate().skip(i + 1) {
+            if next.core.is_empty()
+                || next.core == "the"
+                || next.core == "shift"
+                || SEPARATORS.contains(&next.core.as_str())
+            {
+                continue;
+            }
+            match next.key() {
+                Some(key) if next.is_marked() || after_the(j) => push(key),
+                _ => break,
+            }
+        }
+    }
+    claims
+}
+
+/// Keys that can type `jamo` in any position on `layout`
+fn keys_for_jamo(layout: Layout, jamo: char) -> Vec<KeyPress> {
+    let positions: &[Position] = if jamo::is_vowel(jamo) {
+        &[Position::Medial]
+    } else {
+        &[Position::Initial, Position::Final]
+    };
+    positions
+        .iter()
+        .filter_map(|&p| layout.jamo_to_keys_at(jamo, p))
+        .flatten()
+        .collect()
+}
+
+/// Whether `claim` names one of `keys`, by QWERTY position or by its label
+/// on the learner's keyboard
+fn names_any(claim: char, keys: &[KeyPress], physical: PhysicalLayout) -> bool {
+    keys.iter()
+        .any(|&k| k.key == claim || physical.translate(k).key == claim)
+}
+
+fn key_list(keys: &[KeyPress], physical: PhysicalLayout) -> String {
+    physical
+        .translate_all(keys)
+        .iter()
+        .map(|k| k.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Corrections for wrong key claims in `content` about typing `target`.
+///
+/// A sentence that mentions exactly one jamo is checked against that
+/// jamo's keys; any other sentence against the keys for the whole target.
+/// Returns one note per wrong claim, empty when everything checks out.
+pub fn corrections(
+    content: &str,
+    target: &str,
+    layout: Layout,
+    physical: PhysicalLayout,
+) -> Vec<String> {
+    let target_keys = layout.keystrokes_for_text(target);
+    let mut notes = Vec::new();
+
+    for sentence in content.split(['.', '!', '?', '\n']) {
+        let claims = key_claims(sentence);
+        if claims.is_empty() {
+            continue;
+        }
+
+        let mut mentioned: Vec<char> = sentence
+            .chars()
+            .filter(|&c| jamo::is_vowel(c) || jamo::is_consonant(c))
+            .collect();
+        mentioned.sort_unstable();
+        mentioned.dedup();
+
+        let note = match mentioned[..] {
+            [jamo] => {
+                let keys = keys_for_jamo(layout, jamo);
+                let wrong: Vec<char> = claims
+                    .iter()
+                    .copied()
+                    .filter(|&c| !names_any(c, &keys, physical))
+                    .collect();
+                (!keys.is_empty() && !wrong.is_empty()).then(|| {
+                    format!(
+                        "Correction: {} is typed with {}, not {}.",
+                        jamo,
+                        key_list(&keys, physical),
+                        join_chars(&wrong)
+                    )
+                })
+            }
+            _ => {
+                let wrong: Vec<char> = claims
+                    .iter()
+                    .copied()
+                    .filter(|&c| !names_any(c, &target_keys, physical))
+                    .collect();
+                (!target_keys.is_empty() && !wrong.is_empty()).then(|| {
+                    format!(
+                        "Correction: {} isn't used to type {}; the keys are {}.",
+                        join_chars(&wrong),
+                        target,
+                        key_list(&target_keys, physical)
+                    )
+                })
+            }
+        };
+
+        if let Some(note) = note
+            && !notes.contains(&note)
+        {
+            notes.push(note);
+        }
+    }
+    notes
+}
+
+fn join_chars(chars: &[char]) -> String {
+    chars
+        .iter()
+        .map(char::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// `content` with a correction note appended for each wrong key claim
+pub fn with_corrections(
+    content: &str,
+    target: &str,
+    layout: Layout,
+    physical: PhysicalLayout,
+) -> String {
+    let notes = corrections(content, target, layout, physical);
+    if notes.is_empty() {
+        content.to_string()
+    } else {
+        format!("{}\n\n{}", content.trim_end(), notes.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_claims_in_common_phrasings() {
+        assert_eq!(key_claims("Press the R key, then K."), vec!['r', 'k']);
+        assert_eq!(key_claims("hit 'q' and then `k`"), vec!['q', 'k']);
+        assert_eq!(key_claims("Hold Shift + R"), Vec::<char>::new());
+        assert_eq!(key_claims("press Shift+R for ㄲ"), vec!['r']);
+        assert_eq!(key_claims("The A key types ㅁ"), vec!['a']);
+    }
+
+    #[test]
+    fn article_a_is_not_a_key() {
+        assert!(key_claims("Press a key to continue").is_empty());
+        assert_eq!(key_claims("press the a key"), vec!['a']);
+    }
+
+    #[test]
+    fn wrong_key_for_target_is_corrected() {
+        let content = "To type 가, press the E key and then K.";
+        let fixed = with_corrections(content, "가", Layout::TwoBulsik, PhysicalLayout::Qwerty);
+
+        assert!(fixed.starts_with(content));
+        assert!(
+            fixed.ends_with("Correction: e isn't used to type 가; the keys are r, k."),
+            "{fixed}"
+        );
+    }
+
+    #[test]
+    fn wrong_key_for_named_jamo_is_corrected() {
+        let content = "For ㄱ, press R. For ㅏ, press the J key.";
+        let notes = corrections(content, "가", Layout::TwoBulsik, PhysicalLayout::Qwerty);
+
+        assert_eq!(notes, vec!["Correction: ㅏ is typed with k, not j."]);
+    }
+
+    #[test]
+    fn correct_answer_is_unchanged() {
+        let content = "Press R for ㄱ, then press K for ㅏ.";
+        assert_eq!(
+            with_corrections(content, "가", Layout::TwoBulsik, PhysicalLayout::Qwerty),
+            content
+        );
+    }
+}
@@ -0,0 +1,138 @@
+//! Lightweight language detection.
+//!
+//! Guesses the language of a learner's text from its script and, for Latin
+//! text, a few common stopwords, so the frontend can localize help text to
+//! match. Only needs to be right for short, everyday prompts.
+
+use crate::jamo::{self, Script};
+
+/// BCP-47 tag returned when the language can't be determined
+pub const UNDETERMINED: &str = "und";
+
+/// Common words for each Latin-script language we recognise
+const STOPWORDS: [(&str, &[&str]); 5] = [
+    (
+        "en",
+        &[
+            "the", "and", "is", "are", "to", "of", "in", "it", "what", "how", "i", "you", "this",
+            "do", "does", "can",
+        ],
+    ),
+    (
+        "es",
+        &[
+            "el", "la", "los", "las", "de", "que", "y", "es", "en", "un", "una", "por", "cómo",
+            "qué", "para", "se",
+        ],
+    ),
+    (
+        "fr",
+        &[
+            "le", "la", "les", "de", "des", "et", "est", "un", "une", "que", "comment", "je",
+            "pour", "dans", "ce", "pas",
+        ],
+    ),
+    (
+        "de",
+        &[
+            "der", "die", "das", "und", "ist", "ein", "eine", "ich", "nicht", "wie", "was", "zu",
+            "mit", "den", "auf", "es",
+        ],
+    ),
+    (
+        "pt",
+        &[
+            "o", "os", "as", "de", "que", "e", "é", "um", "uma", "não", "como", "para", "em", "do",
+            "da", "você",
+        ],
+    ),
+];
+
+/// Guess the language of `text` as a BCP-47 tag ("ko", "ja", "es", ...).
+///
+/// Any Hangul means Korean and any kana means Japanese, since learners mix
+/// them with other scripts. Latin text is matched against stopwords and
+/// defaults to English. Returns [`UNDETERMINED`] for text with no letters.
+pub fn detect(text: &str) -> &'static str {
+    let (mut hangul, mut kana, mut han, mut cyrillic, mut latin) = (0, 0, 0, 0, 0);
+    for c in text.chars() {
+        match jamo::script_of(c) {
+            Script::Hangul => hangul += 1,
+            Script::Kana => kana += 1,
+            Script::Hanja => han += 1,
+            Script::Cyrillic => cyrillic += 1,
+            Script::Latin => latin += 1,
+            Script::Other => {}
+        }
+    }
+
+    if hangul > 0 {
+        return "ko";
+    }
+    if kana > 0 {
+        return "ja";
+    }
+    if han > 0 && han >= latin {
+        return "zh";
+    }
+    if cyrillic > 0 && cyrillic >= latin {
+        return "ru";
+    }
+    if latin == 0 {
+        return UNDETERMINED;
+    }
+
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    STOPWORDS
+        .iter()
+        .map(|(tag, stopwords)| {
+            let hits = words
+                .iter()
+                .filter(|w| stopwords.contains(&w.as_str()))
+                .count();
+            (*tag, hits)
+        })
+        // First language wins ties, so ambiguous text falls back to English
+        .fold(("en", 0), |best, candidate| {
+            if candidate.1 > best.1 {
+                candidate
+            } else {
+                best
+            }
+        })
+        .0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_spanish() {
+        assert_eq!(detect("¿Cómo se escribe la palabra gracias?"), "es");
+    }
+
+    #[test]
+    fn detects_japanese() {
+        assert_eq!(detect("この文字はどうやって入力しますか"), "ja");
+    }
+
+    #[test]
+    fn detects_korean_even_with_latin() {
+        assert_eq!(detect("한글 typing 어떻게 해요?"), "ko");
+    }
+
+    #[test]
+    fn defaults_to_english_for_latin() {
+        assert_eq!(detect("How do I type this?"), "en");
+    }
+
+    #[test]
+    fn undetermined_without_letters() {
+        assert_eq!(detect("123 !?"), UNDETERMINED);
+    }
+}
@@ -0,0 +1,1030 @@
+//! Keyboard layout mapping for Hangul jamo.
+//!
+//! Maps jamo to the English keys that produce them on the 2-Bulsik (두벌식)
+//! and 3-Bulsik Final (세벌식 최종) layouts, so hints can be computed locally
+//! and the AI's keyboard claims can be checked. The free functions describe
+//! the default 2-Bulsik layout; [`Layout`] selects between layouts.
+//!
+//! Keys are named by their US QWERTY position. [`PhysicalLayout`] renames
+//! them to the labels printed on AZERTY, Dvorak or Colemak keyboards, since
+//! Korean IMEs map jamo to key positions, not labels.
+
+use crate::jamo;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A single physical key press
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct KeyPress {
+    pub key: char,
+    pub shift: bool,
+}
+
+impl KeyPress {
+    pub const fn plain(key: char) -> Self {
+        Self { key, shift: false }
+    }
+
+    pub const fn shifted(key: char) -> Self {
+        Self { key, shift: true }
+    }
+}
+
+impl fmt::Display for KeyPress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.shift {
+            write!(f, "Shift + {}", self.key)
+        } else {
+            write!(f, "{}", self.key)
+        }
+    }
+}
+
+/// Every jamo that is typed with a single key press
+const SINGLE_KEY_JAMO: [char; 33] = [
+    'ㅂ', 'ㅈ', 'ㄷ', 'ㄱ', 'ㅅ', 'ㅁ', 'ㄴ', 'ㅇ', 'ㄹ', 'ㅎ', 'ㅋ', 'ㅌ', 'ㅊ', 'ㅍ', 'ㅃ', 'ㅉ',
+    'ㄸ', 'ㄲ', 'ㅆ', 'ㅛ', 'ㅕ', 'ㅑ', 'ㅐ', 'ㅔ', 'ㅗ', 'ㅓ', 'ㅏ', 'ㅣ', 'ㅠ', 'ㅜ', 'ㅡ', 'ㅒ',
+    'ㅖ',
+];
+
+/// Map a jamo that is typed with a single key press to that key.
+///
+/// Covers the basic consonants and vowels, the shifted double consonants
+/// (ㄲ ㄸ ㅃ ㅆ ㅉ) and the shifted vowels ㅒ and ㅖ. Compound vowels need two
+/// key presses and return `None` here; use [`jamo_to_keys`] for those.
+pub fn jamo_to_key(jamo: char) -> Option<KeyPress> {
+    let key = match jamo {
+        // Consonants (left hand)
+        'ㅂ' => KeyPress::plain('q'),
+        'ㅈ' => KeyPress::plain('w'),
+        'ㄷ' => KeyPress::plain('e'),
+        'ㄱ' => KeyPress::plain('r'),
+        'ㅅ' => KeyPress::plain('t'),
+        'ㅁ' => KeyPress::plain('a'),
+        'ㄴ' => KeyPress::plain('s'),
+        'ㅇ' => KeyPress::plain('d'),
+        'ㄹ' => KeyPress::plain('f'),
+        'ㅎ' => KeyPress::plain('g'),
+        'ㅋ' => KeyPress::plain('z'),
+        'ㅌ' => KeyPress::plain('x'),
+        'ㅊ' => KeyPress::plain('c'),
+        'ㅍ' => KeyPress::plain('v'),
+        // Double consonants (Shift + base consonant)
+        'ㅃ' => KeyPress::shifted('q'),
+        'ㅉ' => KeyPress::shifted('w'),
+        'ㄸ' => KeyPress::shifted('e'),
+        'ㄲ' => KeyPress::shifted('r'),
+        'ㅆ' => KeyPress::shifted('t'),
+        // Vowels (right hand)
+        'ㅛ' => KeyPress::plain('y'),
+        'ㅕ' => KeyPress::plain('u'),
+        'ㅑ' => KeyPress::plain('i'),
+        'ㅐ' => KeyPress::plain('o'),
+        'ㅔ' => KeyPress::plain('p'),
+        'ㅗ' => KeyPress::plain('h'),
+        'ㅓ' => KeyPress::plain('j'),
+        'ㅏ' => KeyPress::plain('k'),
+        'ㅣ' => KeyPress::plain('l'),
+        'ㅠ' => KeyPress::plain('b'),
+        'ㅜ' => KeyPress::plain('n'),
+        'ㅡ' => KeyPress::plain('m'),
+        // Shifted vowels
+        'ㅒ' => KeyPress::shifted('o'),
+        'ㅖ' => KeyPress::shifted('p'),
+        _ => return None,
+    };
+    Some(key)
+}
+
+/// Memory aids for where each single-key jamo sits on 2-Bulsik
+const MNEMONICS: [(char, &str); 33] = [
+    (
+        'ㅂ',
+        "ㅂ looks like a bucket sitting in the top-left corner, on Q, where the consonants begin.",
+    ),
+    (
+        'ㅈ',
+        "ㅈ follows ㅂ along the top row: ㅂ ㅈ ㄷ ㄱ ㅅ on Q W E R T, so ㅈ is W.",
+    ),
+    (
+        'ㄷ',
+        "ㄷ is an E with its middle bar taken out, and it lives on E.",
+    ),
+    (
+        'ㄱ',
+        "ㄱ is a bent rod: R for rod, under your left index finger.",
+    ),
+    (
+        'ㅅ',
+        "ㅅ is shaped like a tent: T for tent, the last consonant on the top row.",
+    ),
+    (
+        'ㅁ',
+        "ㅁ is a square box packed into the home-row corner, on A under your left pinky.",
+    ),
+    (
+        'ㄴ',
+        "ㄴ sounds like n and sits second on the home row, on S.",
+    ),
+    (
+        'ㅇ',
+        "ㅇ is a round donut: D for donut, under your left middle finger.",
+    ),
+    (
+        'ㄹ',
+        "ㄹ folds back and forth like a flag: F, the home key with the bump.",
+    ),
+    (
+        'ㅎ',
+        "ㅎ (h) wears a hat and sits on G, just left of where H is.",
+    ),
+    (
+        'ㅋ',
+        "ㅋ is ㄱ with an extra bar; the aspirated consonants fill the bottom row, starting with Z.",
+    ),
+    (
+        'ㅌ',
+        "ㅌ is ㄷ with an extra bar, second on the aspirated bottom row: X.",
+    ),
+    ('ㅊ', "ㅊ is ㅈ with a tick on top, on C for ch."),
+    (
+        'ㅍ',
+        "ㅍ looks like a little table and closes the consonant bottom row on V.",
+    ),
+    (
+        'ㅃ',
+        "ㅃ is ㅂ doubled: hold Shift and press Q, the ㅂ key.",
+    ),
+    (
+        'ㅉ',
+        "ㅉ is ㅈ doubled: hold Shift and press W, the ㅈ key.",
+    ),
+    (
+        'ㄸ',
+        "ㄸ is ㄷ doubled: hold Shift and press E, the ㄷ key.",
+    ),
+    (
+        'ㄲ',
+        "ㄲ is ㄱ doubled: hold Shift and press R, the ㄱ key.",
+    ),
+    (
+        'ㅆ',
+        "ㅆ is ㅅ doubled: hold Shift and press T, the ㅅ key.",
+    ),
+    (
+        'ㅛ',
+        "ㅛ raises two arms like a Y, and it sits on Y where the vowels begin.",
+    ),
+    ('ㅕ', "ㅕ is on U: the y-vowels ㅛ ㅕ ㅑ line up on Y U I."),
+    (
+        'ㅑ',
+        "ㅑ is ㅏ with a second tick, one row up from ㅏ, on I.",
+    ),
+    (
+        'ㅐ',
+        "ㅐ is ㅏ and ㅣ side by side, on O right above ㅣ's key.",
+    ),
+    (
+        'ㅔ',
+        "ㅔ sits next to its look-alike ㅐ, on P in the top-right corner.",
+    ),
+    (
+        'ㅗ',
+        "ㅗ points up from its bar and starts the home-row vowels, on H.",
+    ),
+    (
+        'ㅓ',
+        "ㅓ faces ㅏ across the home row, on J, the key with the bump.",
+    ),
+    ('ㅏ', "ㅏ points right, one key right of ㅓ, on K."),
+    (
+        'ㅣ',
+        "ㅣ is a single straight line, just like the letter L it sits on.",
+    ),
+    (
+        'ㅠ',
+        "ㅠ hangs two legs down and starts the bottom-row vowels ㅠ ㅜ ㅡ on B.",
+    ),
+    ('ㅜ', "ㅜ hangs one leg down, between ㅠ and ㅡ, on N."),
+    (
+        'ㅡ',
+        "ㅡ lies flat at the end of the bottom-row vowels, on M.",
+    ),
+    (
+        'ㅒ',
+        "ㅒ is ㅐ with an extra tick: hold Shift and press O, the ㅐ key.",
+    ),
+    (
+        'ㅖ',
+        "ㅖ is ㅔ with an extra tick: hold Shift and press P, the ㅔ key.",
+    ),
+];
+
+/// A short memory aid for where `jamo` is on 2-Bulsik.
+///
+/// Covers every jamo with its own key, shifted or not. Compound vowels and
+/// finals are typed as their parts and return `None`.
+pub fn mnemonic(jamo: char) -> Option<String> {
+    MNEMONICS
+        .iter()
+        .find(|(j, _)| *j == jamo)
+        .map(|(_, aid)| aid.to_string())
+}
+
+/// Map any jamo to the full sequence of key presses that produces it.
+///
+/// Single-key jamo yield one press; compound vowels such as ㅘ and compound
+/// finals such as ㄺ yield two (ㅗ then ㅏ → `h`, `k`).
+pub fn jamo_to_keys(jamo: char) -> Option<Vec<KeyPress>> {
+    if let Some(key) = jamo_to_key(jamo) {
+        return Some(vec![key]);
+    }
+
+    let [first, second] = jamo::split_compound(jamo)?;
+    Some(vec![jamo_to_key(first)?, jamo_to_key(second)?])
+}
+
+/// Map a key press back to the jamo it produces.
+///
+/// Shift only matters for keys with a shifted jamo (ㅃ ㅉ ㄸ ㄲ ㅆ ㅒ ㅖ);
+/// on other keys it produces the same jamo as the plain key, as on a
+/// standard 2-Bulsik IME. Returns `None` for keys with no jamo.
+pub fn key_to_jamo(key: KeyPress) -> Option<char> {
+    let key = KeyPress {
+        key: key.key.to_ascii_lowercase(),
+        shift: key.shift,
+    };
+    let find = |press: KeyPress| {
+        SINGLE_KEY_JAMO
+            .iter()
+            .copied()
+            .find(|&j| jamo_to_key(j) == Some(press))
+    };
+
+    find(key).or_else(|| {
+        if key.shift {
+            find(KeyPress::plain(key.key))
+        } else {
+            None
+        }
+    })
+}
+
+/// Produce the full 2-Bulsik keystroke sequence for a precomposed syllable.
+///
+/// Decomposes the syllable and concatenates the key presses for the initial,
+/// medial and optional final, expanding compound vowels and finals into their
+/// constituent keys. Returns `None` for anything that is not a syllable block.
+pub fn keystrokes_for(syllable: char) -> Option<Vec<KeyPress>> {
+    Layout::TwoBulsik.keystrokes_for(syllable)
+}
+
+/// Produce the 2-Bulsik keystroke sequence for a whole string.
+///
+/// Syllable blocks are expanded with [`keystrokes_for`], standalone jamo with
+/// [`jamo_to_keys`] (so partially-typed input still maps to keys), and spaces
+/// map to the space bar. Anything else is skipped.
+pub fn keystrokes_for_text(text: &str) -> Vec<KeyPress> {
+    Layout::TwoBulsik.keystrokes_for_text(text)
+}
+
+/// Whether `typed` is `target` typed with the OS keyboard left on English.
+///
+/// True when `typed` is non-empty Latin text matching the start of the
+/// 2-Bulsik keys for `target` as labelled on `physical` ("rkstk" for
+/// 간사 on QWERTY), so the UI can ask the learner to switch to Korean
+/// before they type the whole word. Case is ignored, since Shift doesn't
+/// change a key's position.
+pub fn looks_like_wrong_layout(typed: &str, target: &str, physical: PhysicalLayout) -> bool {
+    let typed: Vec<char> = typed.trim_end().chars().collect();
+    let keys = physical.translate_all(&keystrokes_for_text(target));
+    !typed.is_empty()
+        && typed.iter().any(char::is_ascii_alphabetic)
+        && typed.len() <= keys.len()
+        && typed
+            .iter()
+            .zip(&keys)
+            .all(|(t, k)| t.is_ascii() && t.eq_ignore_ascii_case(&k.key))
+}
+
+/// Where a jamo sits within a syllable.
+///
+/// 3-Bulsik layouts place initial and final consonants on different keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Position {
+    Initial,
+    Medial,
+    Final,
+}
+
+/// Supported Korean keyboard layouts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Layout {
+    /// 2-Bulsik (두벌식), the standard layout in Korea
+    #[default]
+    TwoBulsik,
+    /// 3-Bulsik Final (세벌식 최종), with separate initial and final keys
+    ThreeBulsikFinal,
+}
+
+/// Keys in keyboard order, row by row, as shown on a cheat sheet
+const KEYBOARD_ROWS: &str = "1234567890-=qwertyuiop[]asdfghjkl;'zxcvbnm,./";
+
+/// What one key types on a layout, for a printable reference card
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct KeyMapping {
+    pub key: char,
+    pub jamo: char,
+    /// What the key types with Shift, if that differs
+    pub shift_jamo: Option<char>,
+}
+
+impl Layout {
+    /// Human-readable layout name
+    pub fn name(self) -> &'static str {
+        match self {
+            Layout::TwoBulsik => "2-Bulsik (두벌식)",
+            Layout::ThreeBulsikFinal => "3-Bulsik Final (세벌식 최종)",
+        }
+    }
+
+    /// Map a single-key jamo to its key, treating consonants as initials
+    pub fn jamo_to_key(self, jamo: char) -> Option<KeyPress> {
+        let position = if jamo::is_vowel(jamo) {
+            Position::Medial
+        } else {
+            Position::Initial
+        };
+        self.jamo_to_key_at(jamo, position)
+    }
+
+    /// Map a single-key jamo in the given syllable position to its key
+    pub fn jamo_to_key_at(self, jamo: char, position: Position) -> Option<KeyPress> {
+        match self {
+            Layout::TwoBulsik => jamo_to_key(jamo),
+            Layout::ThreeBulsikFinal => match position {
+                Position::Initial => three_bulsik_initial(jamo),
+                Position::Medial => three_bulsik_medial(jamo),
+                Position::Final => three_bulsik_final(jamo),
+            },
+        }
+    }
+
+    /// Map any jamo in the given syllable position to its full key sequence
+    pub fn jamo_to_keys_at(self, jamo: char, position: Position) -> Option<Vec<KeyPress>> {
+        if let Some(key) = self.jamo_to_key_at(jamo, position) {
+            return Some(vec![key]);
+        }
+
+        match (self, position) {
+            // 3-Bulsik double initials are typed by pressing the key twice
+            (Layout::ThreeBulsikFinal, Position::Initial) => {
+                let base = double_consonant_base(jamo)?;
+                let key = three_bulsik_initial(base)?;
+                Some(vec![key, key])
+            }
+            // 3-Bulsik compound vowels start on the dedicated right-hand ㅗ/ㅜ keys
+            (Layout::ThreeBulsikFinal, Position::Medial) => {
+                let [first, second] = jamo::split_compound(jamo)?;
+                let first_key = match first {
+                    'ㅗ' => KeyPress::plain('/'),
+                    'ㅜ' => KeyPress::plain('9'),
+                    other => three_bulsik_medial(other)?,
+                };
+                Some(vec![first_key, three_bulsik_medial(second)?])
+            }
+            _ => {
+                let [first, second] = jamo::split_compound(jamo)?;
+                Some(vec![
+                    self.jamo_to_key_at(first, position)?,
+                    self.jamo_to_key_at(second, position)?,
+                ])
+            }
+        }
+    }
+
+    /// The jamo a single key press types in any syllable position
+    fn jamo_on_key(self, key: KeyPress) -> Option<char> {
+        // Every compatibility jamo from ㄱ to ㅣ
+        ('ㄱ'..='ㅣ').find(|&j| {
+            [Position::Initial, Position::Medial, Position::Final]
+                .into_iter()
+                .any(|p| self.jamo_to_key_at(j, p) == Some(key))
+        })
+    }
+
+    /// Every key that types a jamo, in keyboard order, for a cheat sheet
+    pub fn cheatsheet(self) -> Vec<KeyMapping> {
+        KEYBOARD_ROWS
+            .chars()
+            .filter_map(|key| {
+                let jamo = self.jamo_on_key(KeyPress::plain(key))?;
+                let shift_jamo = self
+                    .jamo_on_key(KeyPress::shifted(key))
+                    .filter(|&shifted| shifted != jamo);
+                Some(KeyMapping {
+                    key,
+                    jamo,
+                    shift_jamo,
+                })
+            })
+            .collect()
+    }
+
+    /// Produce the full keystroke sequence for a precomposed syllable
+    pub fn keystrokes_for(self, syllable: char) -> Option<Vec<KeyPress>> {
+        let (cho, jung, jong) = jamo::decompose(syllable)?;
+
+        let mut keys = self.jamo_to_keys_at(cho, Position::Initial)?;
+        keys.extend(self.jamo_to_keys_at(jung, Position::Medial)?);
+        if let Some(jong) = jong {
+            keys.extend(self.jamo_to_keys_at(jong, Position::Final)?);
+        }
+        Some(keys)
+    }
+
+    /// Produce the keystroke sequence for a whole string.
+    ///
+    /// Standalone consonants are treated as initials.
+    pub fn keystrokes_for_text(self, text: &str) -> Vec<KeyPress> {
+        let mut keys = Vec::new();
+        for c in text.chars() {
+            if c == ' ' {
+                keys.push(KeyPress::plain(' '));
+                continue;
+            }
+            let position = if jamo::is_vowel(c) {
+                Position::Medial
+            } else {
+                Position::Initial
+            };
+            if let Some(seq) = self
+                .keystrokes_for(c)
+                .or_else(|| self.jamo_to_keys_at(c, position))
+            {
+                keys.extend(seq);
+            }
+        }
+        keys
+    }
+
+    /// Describe the layout for the tutor's system prompt
+    pub fn prompt_section(self) -> String {
+        let line = |jamo: &[char], position: Position| {
+            jamo.iter()
+                .filter_map(|&j| {
+                    let keys = self.jamo_to_keys_at(j, position)?;
+                    let keys: Vec<String> = keys.iter().map(KeyPress::to_string).collect();
+                    Some(format!(
+                        "{}({})",
+                        j,
+                        keys.join(" ").replace("Shift + ", "Shift+")
+                    ))
+                })
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+
+        match self {
+            Layout::TwoBulsik => format!(
+                "The 2-Bulsik layout maps English keys to Korean jamo:\n\
+                 - Consonants (left hand): {}\n\
+                 - Vowels (right hand): {}\n\
+                 - Double consonants: Shift + base consonant (ㄲ=Shift+r, ㄸ=Shift+e, etc.)",
+                line(&TWO_BULSIK_CONSONANTS, Position::Initial),
+                line(&TWO_BULSIK_VOWELS, Position::Medial),
+            ),
+            Layout::ThreeBulsikFinal => format!(
+                "The 3-Bulsik Final layout maps English keys to Korean jamo, with separate keys for initial and final consonants:\n\
+                 - Initial consonants (right hand): {}\n\
+                 - Vowels (center): {}\n\
+                 - Final consonants (left hand): {}\n\
+                 - Double initials: press the initial key twice (ㄲ=k k, ㄸ=u u, etc.)",
+                line(&TWO_BULSIK_CONSONANTS, Position::Initial),
+                line(&THREE_BULSIK_VOWELS, Position::Medial),
+                line(&jamo::JONGSEONG, Position::Final),
+            ),
+        }
+    }
+}
+
+/// Keyboard the learner physically types on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PhysicalLayout {
+    #[default]
+    Qwerty,
+    /// French AZERTY
+    Azerty,
+    Dvorak,
+    Colemak,
+}
+
+/// Keys that differ between physical layouts, in US QWERTY order
+const QWERTY_KEYS: &str = "qwertyuiop[]asdfghjkl;'zxcvbnm,./-=";
+
+impl PhysicalLayout {
+    /// Human-readable layout name
+    pub fn name(self) -> &'static str {
+        match self {
+            PhysicalLayout::Qwerty => "QWERTY",
+            PhysicalLayout::Azerty => "AZERTY",
+            PhysicalLayout::Dvorak => "Dvorak",
+            PhysicalLayout::Colemak => "Colemak",
+        }
+    }
+
+    /// Labels of the keys at the positions listed in [`QWERTY_KEYS`]
+    fn labels(self) -> &'static str {
+        match self {
+            PhysicalLayout::Qwerty => QWERTY_KEYS,
+            PhysicalLayout::Azerty => "azertyuiop^$qsdfghjklmùwxcvbn,;:!)=",
+            PhysicalLayout::Dvorak => "',.pyfgcrl/=aoeuidhtns-;qjkxbmwvz[]",
+            PhysicalLayout::Colemak => "qwfpgjluy;[]arstdhneio'zxcvbkm,./-=",
+        }
+    }
+
+    /// Rename a QWERTY-position key press to the label on this keyboard.
+    ///
+    /// Shift is kept as is. Keys outside the letter block (digits, space)
+    /// carry the same label on every supported layout and pass through.
+    pub fn translate(self, key: KeyPress) -> KeyPress {
+        let label = QWERTY_KEYS
+            .chars()
+            .position(|c| c == key.key)
+            .and_then(|i| self.labels().chars().nth(i))
+            .unwrap_or(key.key);
+        KeyPress { key: label, ..key }
+    }
+
+    /// Rename a whole key sequence with [`PhysicalLayout::translate`]
+    pub fn translate_all(self, keys: &[KeyPress]) -> Vec<KeyPress> {
+        keys.iter().map(|&key| self.translate(key)).collect()
+    }
+}
+
+/// Finger that presses a key in standard touch typing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Finger {
+    LeftPinky,
+    LeftRing,
+    LeftMiddle,
+    LeftIndex,
+    /// Either thumb, on the space bar
+    Thumb,
+    RightIndex,
+    RightMiddle,
+    RightRing,
+    RightPinky,
+}
+
+impl Finger {
+    /// Touch-typing finger for a key, named by its US QWERTY position.
+    ///
+    /// Fingers follow key positions, so this holds whatever labels the
+    /// physical keyboard prints. Returns `None` for unknown keys.
+    pub fn for_key(key: char) -> Option<Finger> {
+        let finger = match key.to_ascii_lowercase() {
+            '`' | '1' | 'q' | 'a' | 'z' => Finger::LeftPinky,
+            '2' | 'w' | 's' | 'x' => Finger::LeftRing,
+            '3' | 'e' | 'd' | 'c' => Finger::LeftMiddle,
+            '4' | '5' | 'r' | 't' | 'f' | 'g' | 'v' | 'b' => Finger::LeftIndex,
+            ' ' => Finger::Thumb,
+            '6' | '7' | 'y' | 'u' | 'h' | 'j' | 'n' | 'm' => Finger::RightIndex,
+            '8' | 'i' | 'k' | ',' => Finger::RightMiddle,
+            '9' | 'o' | 'l' | '.' => Finger::RightRing,
+            '0' | '-' | '=' | 'p' | '[' | ']' | '\\' | ';' | '\'' | '/' => Finger::RightPinky,
+            _ => return None,
+        };
+        Some(finger)
+    }
+
+    /// Pinky that holds shift while this finger presses its key, which is
+    /// the pinky of the other hand
+    pub fn shift_finger(self) -> Finger {
+        match self {
+            Finger::LeftPinky | Finger::LeftRing | Finger::LeftMiddle | Finger::LeftIndex => {
+                Finger::RightPinky
+            }
+            _ => Finger::LeftPinky,
+        }
+    }
+}
+
+/// Consonants in 2-Bulsik key order, as listed in the tutor prompt
+const TWO_BULSIK_CONSONANTS: [char; 14] = [
+    'ㅂ', 'ㅈ', 'ㄷ', 'ㄱ', 'ㅅ', 'ㅁ', 'ㄴ', 'ㅇ', 'ㄹ', 'ㅎ', 'ㅋ', 'ㅌ', 'ㅊ', 'ㅍ',
+];
+
+/// Vowels in 2-Bulsik key order, as listed in the tutor prompt
+const TWO_BULSIK_VOWELS: [char; 12] = [
+    'ㅛ', 'ㅕ', 'ㅑ', 'ㅐ', 'ㅔ', 'ㅗ', 'ㅓ', 'ㅏ', 'ㅣ', 'ㅠ', 'ㅜ', 'ㅡ',
+];
+
+/// Vowels with their own key on 3-Bulsik Final
+const THREE_BULSIK_VOWELS: [char; 15] = [
+    'ㅏ', 'ㅐ', 'ㅑ', 'ㅒ', 'ㅓ', 'ㅔ', 'ㅕ', 'ㅖ', 'ㅗ', 'ㅛ', 'ㅜ', 'ㅠ', 'ㅡ', 'ㅢ', 'ㅣ',
+];
+
+/// Base consonant of a double consonant (ㄲ → ㄱ)
+fn double_consonant_base(jamo: char) -> Option<char> {
+    match jamo {
+        'ㄲ' => Some('ㄱ'),
+        'ㄸ' => Some('ㄷ'),
+        'ㅃ' => Some('ㅂ'),
+        'ㅆ' => Some('ㅅ'),
+        'ㅉ' => Some('ㅈ'),
+        _ => None,
+    }
+}
+
+/// 3-Bulsik Final initial consonants (right hand)
+fn three_bulsik_initial(jamo: char) -> Option<KeyPress> {
+    let key = match jamo {
+        'ㄱ' => 'k',
+        'ㄴ' => 'h',
+        'ㄷ' => 'u',
+        'ㄹ' => 'y',
+        'ㅁ' => 'i',
+        'ㅂ' => ';',
+        'ㅅ' => 'n',
+        'ㅇ' => 'j',
+        'ㅈ' => 'l',
+        'ㅊ' => 'o',
+        'ㅋ' => '0',
+        'ㅌ' => '\'',
+        'ㅍ' => 'p',
+        'ㅎ' => 'm',
+        _ => return None,
+    };
+    Some(KeyPress::plain(key))
+}
+
+/// 3-Bulsik Final vowels (center)
+fn three_bulsik_medial(jamo: char) -> Option<KeyPress> {
+    let key = match jamo {
+        'ㅏ' => KeyPress::plain('f'),
+        'ㅐ' => KeyPress::plain('r'),
+        'ㅑ' => KeyPress::plain('6'),
+        'ㅒ' => KeyPress::shifted('g'),
+        'ㅓ' => KeyPress::plain('t'),
+        'ㅔ' => KeyPress::plain('c'),
+        'ㅕ' => KeyPress::plain('e'),
+        'ㅖ' => KeyPress::plain('7'),
+        'ㅗ' => KeyPress::plain('v'),
+        'ㅛ' => KeyPress::plain('4'),
+        'ㅜ' => KeyPress::plain('b'),
+        'ㅠ' => KeyPress::plain('5'),
+        'ㅡ' => KeyPress::plain('g'),
+        'ㅢ' => KeyPress::plain('8'),
+        'ㅣ' => KeyPress::plain('d'),
+        _ => return None,
+    };
+    Some(key)
+}
+
+/// 3-Bulsik Final final consonants (left hand)
+fn three_bulsik_final(jamo: char) -> Option<KeyPress> {
+    let key = match jamo {
+        'ㄱ' => KeyPress::plain('x'),
+        'ㄲ' => KeyPress::shifted('1'),
+        'ㄳ' => KeyPress::shifted('v'),
+        'ㄴ' => KeyPress::plain('s'),
+        'ㄵ' => KeyPress::shifted('e'),
+        'ㄶ' => KeyPress::shifted('s'),
+        'ㄷ' => KeyPress::shifted('a'),
+        'ㄹ' => KeyPress::plain('w'),
+        'ㄺ' => KeyPress::shifted('2'),
+        'ㄻ' => KeyPress::shifted('f'),
+        'ㄼ' => KeyPress::shifted('d'),
+        'ㄽ' => KeyPress::shifted('t'),
+        'ㄾ' => KeyPress::shifted('5'),
+        'ㄿ' => KeyPress::shifted('4'),
+        'ㅀ' => KeyPress::shifted('r'),
+        'ㅁ' => KeyPress::plain('z'),
+        'ㅂ' => KeyPress::plain('3'),
+        'ㅄ' => KeyPress::shifted('x'),
+        'ㅅ' => KeyPress::plain('q'),
+        'ㅆ' => KeyPress::plain('2'),
+        'ㅇ' => KeyPress::plain('a'),
+        'ㅈ' => KeyPress::shifted('3'),
+        'ㅊ' => KeyPress::shifted('z'),
+        'ㅋ' => KeyPress::shifted('c'),
+        'ㅌ' => KeyPress::shifted('w'),
+        'ㅍ' => KeyPress::shifted('q'),
+        'ㅎ' => KeyPress::plain('1'),
+        _ => return None,
+    };
+    Some(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn physical_layout_relabels_logical_key() {
+        // ㅏ sits on the QWERTY 'k' position
+        let key = jamo_to_key('ㅏ').unwrap();
+        assert_eq!(PhysicalLayout::Qwerty.translate(key), KeyPress::plain('k'));
+        assert_eq!(PhysicalLayout::Dvorak.translate(key), KeyPress::plain('t'));
+        assert_eq!(PhysicalLayout::Colemak.translate(key), KeyPress::plain('e'));
+
+        // ㅃ keeps its Shift on the QWERTY 'q' position
+        let key = jamo_to_key('ㅃ').unwrap();
+        assert_eq!(
+            PhysicalLayout::Azerty.translate(key),
+            KeyPress::shifted('a')
+        );
+    }
+
+    #[test]
+    fn latin_keys_for_target_look_like_wrong_layout() {
+        assert!(looks_like_wrong_layout(
+            "rkstk",
+            "간사",
+            PhysicalLayout::Qwerty
+        ));
+        assert!(looks_like_wrong_layout(
+            "rks",
+            "간사",
+            PhysicalLayout::Qwerty
+        ));
+        assert!(looks_like_wrong_layout("Rk", "까", PhysicalLayout::Qwerty));
+        assert!(looks_like_wrong_layout("pto", "간", PhysicalLayout::Dvorak));
+
+        assert!(!looks_like_wrong_layout(
+            "간사",
+            "간사",
+            PhysicalLayout::Qwerty
+        ));
+        assert!(!looks_like_wrong_layout(
+            "gansa",
+            "간사",
+            PhysicalLayout::Qwerty
+        ));
+        assert!(!looks_like_wrong_layout("", "간사", PhysicalLayout::Qwerty));
+    }
+
+    #[test]
+    fn cheatsheet_lists_plain_and_shifted_jamo() {
+        let sheet = Layout::TwoBulsik.cheatsheet();
+        assert_eq!(
+            sheet[0],
+            KeyMapping {
+                key: 'q',
+                jamo: 'ㅂ',
+                shift_jamo: Some('ㅃ'),
+            }
+        );
+        let a = sheet.iter().find(|m| m.key == 'a').unwrap();
+        assert_eq!((a.jamo, a.shift_jamo), ('ㅁ', None));
+        // Every jamo with its own key appears
+        assert_eq!(
+            sheet.len() + sheet.iter().filter(|m| m.shift_jamo.is_some()).count(),
+            SINGLE_KEY_JAMO.len()
+        );
+
+        let three = Layout::ThreeBulsikFinal.cheatsheet();
+        assert!(three.iter().any(|m| m.key == 'k' && m.jamo == 'ㄱ'));
+        assert!(three.iter().any(|m| m.key == 'x' && m.jamo == 'ㄱ'));
+    }
+
+    #[test]
+    fn fingers_follow_touch_typing_home_row() {
+        assert_eq!(Finger::for_key('q'), Some(Finger::LeftPinky));
+        assert_eq!(Finger::for_key('f'), Some(Finger::LeftIndex));
+        assert_eq!(Finger::for_key('j'), Some(Finger::RightIndex));
+        assert_eq!(Finger::for_key(' '), Some(Finger::Thumb));
+        assert_eq!(Finger::for_key('€'), None);
+    }
+
+    #[test]
+    fn every_letter_key_has_a_finger() {
+        assert!(QWERTY_KEYS.chars().all(|k| Finger::for_key(k).is_some()));
+    }
+
+    #[test]
+    fn physical_layout_tables_cover_every_key() {
+        for layout in [
+            PhysicalLayout::Azerty,
+            PhysicalLayout::Dvorak,
+            PhysicalLayout::Colemak,
+        ] {
+            assert_eq!(
+                layout.labels().chars().count(),
+                QWERTY_KEYS.chars().count(),
+                "{}",
+                layout.name()
+            );
+        }
+    }
+
+    #[test]
+    fn maps_basic_consonants() {
+        let expected = [
+            ('ㄱ', 'r'),
+            ('ㄴ', 's'),
+            ('ㄷ', 'e'),
+            ('ㄹ', 'f'),
+            ('ㅁ', 'a'),
+            ('ㅂ', 'q'),
+            ('ㅅ', 't'),
+            ('ㅇ', 'd'),
+            ('ㅈ', 'w'),
+            ('ㅊ', 'c'),
+            ('ㅋ', 'z'),
+            ('ㅌ', 'x'),
+            ('ㅍ', 'v'),
+            ('ㅎ', 'g'),
+        ];
+        for (jamo, key) in expected {
+            assert_eq!(jamo_to_key(jamo), Some(KeyPress::plain(key)), "{jamo}");
+        }
+    }
+
+    #[test]
+    fn maps_basic_vowels() {
+        let expected = [
+            ('ㅏ', 'k'),
+            ('ㅑ', 'i'),
+            ('ㅓ', 'j'),
+            ('ㅕ', 'u'),
+            ('ㅗ', 'h'),
+            ('ㅛ', 'y'),
+            ('ㅜ', 'n'),
+            ('ㅠ', 'b'),
+            ('ㅡ', 'm'),
+            ('ㅣ', 'l'),
+        ];
+        for (jamo, key) in expected {
+            assert_eq!(jamo_to_key(jamo), Some(KeyPress::plain(key)), "{jamo}");
+        }
+    }
+
+    #[test]
+    fn maps_double_consonants_to_shifted_keys() {
+        let expected = [
+            ('ㄲ', 'r'),
+            ('ㄸ', 'e'),
+            ('ㅃ', 'q'),
+            ('ㅆ', 't'),
+            ('ㅉ', 'w'),
+        ];
+        for (jamo, key) in expected {
+            assert_eq!(jamo_to_key(jamo), Some(KeyPress::shifted(key)), "{jamo}");
+        }
+    }
+
+    #[test]
+    fn compound_vowels_need_two_keys() {
+        assert_eq!(jamo_to_key('ㅘ'), None);
+        assert_eq!(
+            jamo_to_keys('ㅘ'),
+            Some(vec![KeyPress::plain('h'), KeyPress::plain('k')])
+        );
+        assert_eq!(
+            jamo_to_keys('ㅢ'),
+            Some(vec![KeyPress::plain('m'), KeyPress::plain('l')])
+        );
+        assert_eq!(
+            jamo_to_keys('ㅞ'),
+            Some(vec![KeyPress::plain('n'), KeyPress::plain('p')])
+        );
+    }
+
+    #[test]
+    fn rejects_non_jamo() {
+        assert_eq!(jamo_to_key('a'), None);
+        assert_eq!(jamo_to_keys('가'), None);
+    }
+
+    fn plain_keys(keys: &str) -> Vec<KeyPress> {
+        keys.chars().map(KeyPress::plain).collect()
+    }
+
+    #[test]
+    fn key_to_jamo_inverts_jamo_to_key() {
+        for jamo in SINGLE_KEY_JAMO {
+            assert_eq!(key_to_jamo(jamo_to_key(jamo).unwrap()), Some(jamo));
+        }
+    }
+
+    #[test]
+    fn key_to_jamo_ignores_shift_on_keys_without_shifted_jamo() {
+        assert_eq!(key_to_jamo(KeyPress::shifted('a')), Some('ㅁ'));
+        assert_eq!(key_to_jamo(KeyPress::shifted('k')), Some('ㅏ'));
+        assert_eq!(key_to_jamo(KeyPress::plain('1')), None);
+    }
+
+    #[test]
+    fn keystrokes_for_open_syllable() {
+        assert_eq!(keystrokes_for('가'), Some(plain_keys("rk")));
+    }
+
+    #[test]
+    fn keystrokes_for_compound_final_bs() {
+        // 값 = ㄱ + ㅏ + ㅄ(ㅂ+ㅅ)
+        assert_eq!(keystrokes_for('값'), Some(plain_keys("rkqt")));
+    }
+
+    #[test]
+    fn keystrokes_for_compound_final_lg() {
+        // 닭 = ㄷ + ㅏ + ㄺ(ㄹ+ㄱ)
+        assert_eq!(keystrokes_for('닭'), Some(plain_keys("ekfr")));
+    }
+
+    #[test]
+    fn keystrokes_for_compound_vowel_and_shift() {
+        // 꽝 = ㄲ + ㅘ(ㅗ+ㅏ) + ㅇ
+        assert_eq!(
+            keystrokes_for('꽝'),
+            Some(vec![
+                KeyPress::shifted('r'),
+                KeyPress::plain('h'),
+                KeyPress::plain('k'),
+                KeyPress::plain('d'),
+            ])
+        );
+    }
+
+    #[test]
+    fn keystrokes_for_text_handles_partial_input() {
+        assert_eq!(keystrokes_for_text("한 ㄱ"), plain_keys("gks r"));
+    }
+
+    #[test]
+    fn h_key_differs_between_layouts() {
+        assert_eq!(
+            Layout::TwoBulsik.jamo_to_key('ㅎ'),
+            Some(KeyPress::plain('g'))
+        );
+        assert_eq!(
+            Layout::ThreeBulsikFinal.jamo_to_key('ㅎ'),
+            Some(KeyPress::plain('m'))
+        );
+    }
+
+    #[test]
+    fn three_bulsik_uses_separate_final_keys() {
+        // 한 = ㅎ(initial m) + ㅏ(f) + ㄴ(final s)
+        assert_eq!(
+            Layout::ThreeBulsikFinal.keystrokes_for('한'),
+            Some(plain_keys("mfs"))
+        );
+        // 닭 = ㄷ(u) + ㅏ(f) + ㄺ(Shift+2)
+        assert_eq!(
+            Layout::ThreeBulsikFinal.keystrokes_for('닭'),
+            Some(vec![
+                KeyPress::plain('u'),
+                KeyPress::plain('f'),
+                KeyPress::shifted('2'),
+            ])
+        );
+    }
+
+    #[test]
+    fn three_bulsik_doubles_initials_by_repeating_key() {
+        assert_eq!(
+            Layout::ThreeBulsikFinal.keystrokes_for('까'),
+            Some(plain_keys("kkf"))
+        );
+    }
+
+    #[test]
+    fn every_final_has_a_three_bulsik_key() {
+        for jong in jamo::JONGSEONG {
+            assert!(
+                Layout::ThreeBulsikFinal
+                    .jamo_to_key_at(jong, Position::Final)
+                    .is_some(),
+                "{jong}"
+            );
+        }
+    }
+
+    #[test]
+    fn two_bulsik_prompt_lists_key_mappings() {
+        let section = Layout::TwoBulsik.prompt_section();
+        assert!(section.contains("- Consonants (left hand): ㅂ(q) ㅈ(w) ㄷ(e) ㄱ(r)"));
+        assert!(section.contains("- Vowels (right hand): ㅛ(y) ㅕ(u)"));
+    }
+
+    #[test]
+    fn keystrokes_for_rejects_non_syllables() {
+        assert_eq!(keystrokes_for('ㄱ'), None);
+        assert_eq!(keystrokes_for('a'), None);
+    }
+
+    #[test]
+    fn every_single_key_jamo_has_a_mnemonic_naming_its_key() {
+        for jamo in SINGLE_KEY_JAMO {
+            let aid = mnemonic(jamo).unwrap_or_default();
+            let key = jamo_to_key(jamo).unwrap().key.to_ascii_uppercase();
+            assert!(aid.contains(&format!("{key}")), "{jamo}: {aid}");
+        }
+        assert_eq!(mnemonic('ㅘ'), None);
+    }
+}
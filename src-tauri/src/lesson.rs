@@ -0,0 +1,444 @@
+//! Offline lesson content.
+//!
+//! Generates practice syllables from the jamo a learner has been introduced
+//! to so far, following the active [`Curriculum`](crate::curriculum::Curriculum),
+//! so lessons work without the frontend hardcoding targets and
+//! without asking Copilot. Minimal-pair drills contrast jamo that learners
+//! tend to confuse.
+
+use crate::curriculum;
+use crate::frequency;
+use crate::jamo;
+use crate::progress::Progress;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+
+/// Jamo that learners often mix up, by sound or by shape.
+///
+/// Consonant groups contrast plain, aspirated and tense initials; vowel
+/// groups contrast vowels that sound alike in modern Seoul speech.
+const CONFUSABLE_GROUPS: [&[char]; 10] = [
+    &['ㄱ', 'ㅋ', 'ㄲ'],
+    &['ㄷ', 'ㅌ', 'ㄸ'],
+    &['ㅂ', 'ㅍ', 'ㅃ'],
+    &['ㅈ', 'ㅊ', 'ㅉ'],
+    &['ㅅ', 'ㅆ'],
+    &['ㅐ', 'ㅔ'],
+    &['ㅒ', 'ㅖ'],
+    &['ㅓ', 'ㅗ'],
+    &['ㅙ', 'ㅚ', 'ㅞ'],
+    &['ㅕ', 'ㅛ'],
+];
+
+/// Vowels a confusable initial is paired with
+const PAIR_VOWELS: [char; 5] = ['ㅏ', 'ㅓ', 'ㅗ', 'ㅜ', 'ㅣ'];
+
+/// Initials a confusable vowel is paired with
+const PAIR_INITIALS: [char; 5] = ['ㄱ', 'ㄴ', 'ㄷ', 'ㅂ', 'ㅅ'];
+
+/// Syllable pairs that differ only in `jamo` and a jamo confused with it.
+///
+/// The first syllable of each pair contains `jamo`. Consonants are
+/// contrasted as initials and vowels as medials, in open syllables.
+/// Returns an empty list for jamo with no confusable partner.
+pub fn minimal_pairs(jamo: char) -> Vec<(String, String)> {
+    let Some(group) = CONFUSABLE_GROUPS.iter().find(|g| g.contains(&jamo)) else {
+        return Vec::new();
+    };
+
+    let syllable = |j: char, frame: char| {
+        if jamo::is_vowel(j) {
+            jamo::compose(frame, j, None)
+        } else {
+            jamo::compose(j, frame, None)
+        }
+    };
+    let frames: &[char] = if jamo::is_vowel(jamo) {
+        &PAIR_INITIALS
+    } else {
+        &PAIR_VOWELS
+    };
+
+    let mut pairs = Vec::new();
+    for &partner in group.iter().filter(|&&p| p != jamo) {
+        for &frame in frames {
+            if let (Some(a), Some(b)) = (syllable(jamo, frame), syllable(partner, frame)) {
+                pairs.push((a.to_string(), b.to_string()));
+            }
+        }
+    }
+    pairs
+}
+
+/// Jamo first introduced at `level`, empty past the end of the curriculum
+pub fn introduced_at(level: u32) -> Vec<char> {
+    curriculum::current().jamo_for_level(level)
+}
+
+/// Jamo introduced up to some level, by position in the syllable
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnlockedJamo {
+    pub initials: Vec<char>,
+    pub medials: Vec<char>,
+    pub finals: Vec<char>,
+}
+
+impl UnlockedJamo {
+    /// Jamo in `text` that have not been introduced yet, in order of first
+    /// appearance.
+    ///
+    /// Position matters: ㄱ may be unlocked as an initial before it is as a
+    /// final. Characters that are not syllable blocks are ignored.
+    pub fn locked_in(&self, text: &str) -> Vec<char> {
+        let mut locked = Vec::new();
+        for (cho, jung, jong) in text.chars().filter_map(jamo::decompose) {
+            let parts = [
+                (cho, self.initials.contains(&cho)),
+                (jung, self.medials.contains(&jung)),
+            ]
+            .into_iter()
+            .chain(jong.map(|j| (j, self.finals.contains(&j))));
+            for (j, unlocked) in parts {
+                if !unlocked && !locked.contains(&j) {
+                    locked.push(j);
+                }
+            }
+        }
+        locked
+    }
+}
+
+/// Jamo introduced up to and including `level`
+pub fn unlocked_jamo(level: u32) -> UnlockedJamo {
+    curriculum::current().unlocked(level)
+}
+
+/// Every syllable that can be built from the jamo introduced up to `level`
+fn candidates(level: u32) -> Vec<char> {
+    let UnlockedJamo {
+        initials,
+        medials,
+        finals,
+    } = unlocked_jamo(level);
+    let finals: Vec<Option<char>> = std::iter::once(None)
+        .chain(finals.into_iter().map(Some))
+        .collect();
+
+    let mut syllables = Vec::new();
+    for &cho in &initials {
+        for &jung in &medials {
+            for &jong in &finals {
+                syllables.extend(jamo::compose(cho, jung, jong));
+            }
+        }
+    }
+    syllables
+}
+
+/// Mistakes per jamo, attributing each mistyped syllable to all its jamo
+pub fn jamo_mistakes(progress: &Progress) -> HashMap<char, u32> {
+    per_jamo(&progress.per_char_mistakes)
+}
+
+/// Attempts per jamo, attributing each typed syllable to all its jamo
+pub fn jamo_attempts(progress: &Progress) -> HashMap<char, u32> {
+    per_jamo(&progress.per_char_attempts)
+}
+
+/// Spread per-character counts over the jamo of each character
+fn per_jamo(per_char: &HashMap<char, u32>) -> HashMap<char, u32> {
+    let mut counts = HashMap::new();
+    for (&c, &count) in per_char {
+        let parts = match jamo::decompose(c) {
+            Some((cho, jung, jong)) => [Some(cho), Some(jung), jong],
+            None => [Some(c), None, None],
+        };
+        for j in parts.into_iter().flatten() {
+            *counts.entry(j).or_insert(0) += count;
+        }
+    }
+    counts
+}
+
+/// Jamo that appear in any reviewed or mistyped character
+fn practised_jamo(progress: &Progress) -> HashSet<char> {
+    progress
+        .srs
+        .characters()
+        .chain(progress.per_char_mistakes.keys().copied())
+        .flat_map(|c| match jamo::decompose(c) {
+            Some((cho, jung, jong)) => vec![Some(cho), Some(jung), jong],
+            None => vec![Some(c)],
+        })
+        .flatten()
+        .collect()
+}
+
+/// Recommend one syllable that practises a single weak or new jamo.
+///
+/// The focus is the unlocked jamo with the most mistakes or, if none has
+/// been missed, the earliest one never practised. It is paired with the
+/// best-known jamo for the other slot, so only one thing is hard at a time.
+/// Returns `None` when every unlocked jamo is practised and error-free.
+pub fn recommend_next(progress: &Progress) -> Option<char> {
+    let level = progress.unlocked_levels.iter().copied().max().unwrap_or(1);
+    let UnlockedJamo {
+        initials, medials, ..
+    } = unlocked_jamo(level);
+
+    let mistakes = jamo_mistakes(progress);
+    let practised = practised_jamo(progress);
+    let misses = |j: char| mistakes.get(&j).copied().unwrap_or(0);
+
+    let unlocked = || initials.iter().chain(&medials).copied();
+    let focus = unlocked()
+        .filter(|&j| misses(j) > 0)
+        // First of the most-missed, in curriculum order
+        .min_by_key(|&j| Reverse(misses(j)))
+        .or_else(|| unlocked().find(|j| !practised.contains(j)))?;
+
+    // Pair the focus with the least-missed partner, preferring the syllable
+    // that is easiest to type
+    let syllable = |partner: char| {
+        if jamo::is_vowel(focus) {
+            jamo::compose(partner, focus, None)
+        } else {
+            jamo::compose(focus, partner, None)
+        }
+    };
+    let pool = if jamo::is_vowel(focus) {
+        &initials
+    } else {
+        &medials
+    };
+    pool.iter()
+        .copied()
+        .filter_map(|j| Some((misses(j), syllable(j)?)))
+        .min_by_key(|&(misses, s)| (misses, jamo::difficulty(s)))
+        .map(|(_, s)| s)
+}
+
+/// Pick up to `count` distinct practice syllables for a level.
+///
+/// Syllables are weighted by how common they are in written Korean. Returns
+/// fewer than `count` when the level does not have enough distinct
+/// syllables. Passing a `seed` makes the selection reproducible. Targets
+/// come back easiest first, by [`jamo::difficulty`].
+pub fn generate_targets(level: u32, count: usize, seed: Option<u64>) -> Vec<String> {
+    let mut rng = match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+
+    // Every candidate stays possible; common syllables come up more often
+    let mut targets: Vec<char> = candidates(level)
+        .choose_multiple_weighted(&mut rng, count, |&c| frequency::frequency(c) as f64 + 1.0)
+        .expect("weights are positive and finite")
+        .copied()
+        .collect();
+    targets.sort_by_key(|&c| jamo::difficulty(c));
+    targets.into_iter().map(|c| c.to_string()).collect()
+}
+
+/// Plausible wrong answers for `target` in a multiple-choice drill.
+///
+/// Each is `target` with one jamo of one syllable swapped for another jamo
+/// unlocked at `level` in the same position, or its final dropped. They are
+/// distinct, never `target` itself, and lean towards common syllables.
+/// Returns fewer than `n` when there aren't enough. Passing a `seed` makes
+/// the choice reproducible.
+pub fn distractors(target: &str, level: u32, n: usize, seed: Option<u64>) -> Vec<String> {
+    let mut rng = match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+    let UnlockedJamo {
+        initials,
+        medials,
+        finals,
+    } = unlocked_jamo(level);
+    let target: Vec<char> = jamo::normalize(target).chars().collect();
+
+    // Each candidate with the syllable that was changed, for weighting
+    let mut seen = HashSet::new();
+    let mut options: Vec<(String, char)> = Vec::new();
+    for (i, &original) in target.iter().enumerate() {
+        let Some((cho, jung, jong)) = jamo::decompose(original) else {
+            continue;
+        };
+        let swaps = initials
+            .iter()
+            .map(|&x| jamo::compose(x, jung, jong))
+            .chain(medials.iter().map(|&x| jamo::compose(cho, x, jong)))
+            .chain(
+                std::iter::once(None)
+                    .chain(finals.iter().copied().map(Some))
+                    .map(|x| jamo::compose(cho, jung, x)),
+            );
+        for syllable in swaps.flatten().filter(|&s| s != original) {
+            let mut text = target.clone();
+            text[i] = syllable;
+            let text: String = text.into_iter().collect();
+            if seen.insert(text.clone()) {
+                options.push((text, syllable));
+            }
+        }
+    }
+
+    options
+        .choose_multiple_weighted(&mut rng, n, |&(_, s)| frequency::frequency(s) as f64 + 1.0)
+        .expect("weights are positive and finite")
+        .map(|(text, _)| text.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    #[test]
+    fn level_one_uses_only_level_one_jamo() {
+        for target in generate_targets(1, 20, Some(7)) {
+            let c = target.chars().next().unwrap();
+            let (cho, jung, jong) = jamo::decompose(c).expect("not a syllable block");
+            assert!(unlocked_jamo(1).initials.contains(&cho), "{target}");
+            assert!(unlocked_jamo(1).medials.contains(&jung), "{target}");
+            assert_eq!(jong, None, "{target}");
+        }
+    }
+
+    #[test]
+    fn distractors_for_gan_differ_by_one_jamo() {
+        let options = distractors("간", 3, 10, Some(3));
+        assert_eq!(options.len(), 10);
+
+        let unique: HashSet<_> = options.iter().collect();
+        assert_eq!(unique.len(), options.len());
+        for option in &options {
+            assert_ne!(option, "간");
+            assert_eq!(jamo::jamo_edit_distance("간", option), 1, "{option}");
+        }
+    }
+
+    #[test]
+    fn targets_are_distinct_syllable_blocks() {
+        let targets = generate_targets(4, 50, Some(1));
+        assert_eq!(targets.len(), 50);
+
+        let unique: HashSet<_> = targets.iter().collect();
+        assert_eq!(unique.len(), targets.len());
+        assert!(
+            targets
+                .iter()
+                .all(|t| t.chars().all(|c| jamo::decompose(c).is_some()))
+        );
+    }
+
+    #[test]
+    fn same_seed_gives_same_targets() {
+        assert_eq!(
+            generate_targets(3, 10, Some(42)),
+            generate_targets(3, 10, Some(42))
+        );
+    }
+
+    #[test]
+    fn caps_count_at_available_syllables() {
+        // 3 initials x 2 vowels
+        assert_eq!(generate_targets(1, 100, Some(0)).len(), 6);
+    }
+
+    #[test]
+    fn minimal_pairs_contrast_plain_and_aspirated() {
+        let pairs = minimal_pairs('ㄱ');
+        assert!(pairs.contains(&("가".to_string(), "카".to_string())));
+
+        for (a, b) in &pairs {
+            let (a_cho, a_jung, a_jong) = jamo::decompose(a.chars().next().unwrap()).unwrap();
+            let (b_cho, b_jung, b_jong) = jamo::decompose(b.chars().next().unwrap()).unwrap();
+            assert_eq!(a_cho, 'ㄱ');
+            assert!(b_cho == 'ㅋ' || b_cho == 'ㄲ');
+            assert_eq!((a_jung, a_jong), (b_jung, b_jong));
+        }
+    }
+
+    #[test]
+    fn minimal_pairs_work_from_either_side() {
+        let pairs = minimal_pairs('ㅋ');
+        assert!(pairs.contains(&("카".to_string(), "가".to_string())));
+    }
+
+    #[test]
+    fn minimal_pairs_for_vowels() {
+        assert!(minimal_pairs('ㅐ').contains(&("개".to_string(), "게".to_string())));
+    }
+
+    #[test]
+    fn no_minimal_pairs_without_confusable_partner() {
+        assert!(minimal_pairs('ㅇ').is_empty());
+    }
+
+    /// Level 1 progress with every level 1 jamo practised
+    fn practised_level_one() -> Progress {
+        let mut progress = Progress::default();
+        for c in ['가', '나', '디'] {
+            progress.record_review(c, true, Utc::now());
+        }
+        progress
+    }
+
+    #[test]
+    fn locked_jamo_respects_position() {
+        let level_one = unlocked_jamo(1);
+        assert!(level_one.locked_in("가나 다!").is_empty());
+        // ㄱ is taught as an initial at level 1 but as a final only at level 4
+        assert_eq!(level_one.locked_in("각"), vec!['ㄱ']);
+        assert_eq!(level_one.locked_in("머리"), vec!['ㅁ', 'ㅓ', 'ㄹ']);
+        assert!(unlocked_jamo(4).locked_in("각").is_empty());
+    }
+
+    #[test]
+    fn targets_are_ordered_easiest_first() {
+        let targets = generate_targets(6, 30, Some(3));
+        let scores: Vec<u8> = targets
+            .iter()
+            .map(|t| jamo::difficulty(t.chars().next().unwrap()))
+            .collect();
+        assert!(scores.is_sorted(), "{targets:?}");
+    }
+
+    #[test]
+    fn weak_jamo_drives_recommendation() {
+        let mut progress = practised_level_one();
+        progress.per_char_mistakes.insert('ㄷ', 3);
+
+        assert_eq!(recommend_next(&progress), Some('다'));
+    }
+
+    #[test]
+    fn mistyped_syllables_count_against_their_jamo() {
+        let mut progress = practised_level_one();
+        progress.per_char_mistakes.insert('니', 2);
+        progress.per_char_mistakes.insert('기', 1);
+
+        // ㅣ is missed in both, so it is the focus, paired with the safest initial
+        assert_eq!(recommend_next(&progress), Some('디'));
+    }
+
+    #[test]
+    fn unpractised_jamo_is_recommended_next() {
+        let mut progress = Progress::default();
+        progress.record_review('가', true, Utc::now());
+
+        assert_eq!(recommend_next(&progress), Some('나'));
+    }
+
+    #[test]
+    fn nothing_to_recommend_when_mastered() {
+        assert_eq!(recommend_next(&practised_level_one()), None);
+    }
+}
@@ -1,9 +1,34 @@
-use tauri::Manager;
-use tracing::info;
-use tracing_subscriber::{EnvFilter, fmt, prelude::*};
+use tauri::{Manager, WindowEvent};
+use tracing::{info, warn};
 
+pub mod achievements;
+pub mod analytics;
+pub mod anki;
+pub mod bench;
+pub mod cache;
 mod commands;
 mod copilot;
+pub mod curriculum;
+pub mod explain;
+pub mod frequency;
+pub mod grade;
+pub mod hint;
+pub mod ime;
+pub mod jamo;
+pub mod keycheck;
+pub mod language;
+pub mod layout;
+pub mod lesson;
+pub mod logging;
+pub mod metrics;
+pub mod ngram;
+pub mod profiles;
+pub mod progress;
+pub mod quiz;
+pub mod rate_limit;
+pub mod romanize;
+pub mod srs;
+pub mod strokes;
 
 #[tauri::command]
 fn close_splash(window: tauri::Window) {
@@ -18,24 +43,128 @@ fn close_splash(window: tauri::Window) {
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    tracing_subscriber::registry()
-        .with(fmt::layer())
-        .with(EnvFilter::from_default_env().add_directive(tracing::Level::INFO.into()))
-        .init();
+    logging::init();
 
     info!("Starting Hangul Typing");
 
     tauri::Builder::default()
+        .on_window_event(|window, event| {
+            // Don't leave requests streaming into a window that is gone
+            if window.label() == "main"
+                && matches!(
+                    event,
+                    WindowEvent::CloseRequested { .. } | WindowEvent::Destroyed
+                )
+            {
+                tauri::async_runtime::spawn(async {
+                    if let Err(e) = copilot::abandon().await {
+                        warn!("Failed to stop Copilot after window closed: {}", e);
+                    }
+                });
+            }
+        })
         .invoke_handler(tauri::generate_handler![
             close_splash,
             commands::copilot_check,
             commands::copilot_init,
             commands::copilot_status,
+            commands::copilot_capabilities,
+            commands::copilot_ping,
             commands::copilot_ask,
+            commands::copilot_ask_blocking,
+            commands::copilot_new_conversation,
+            commands::copilot_end_conversation,
+            commands::copilot_transcript,
+            commands::copilot_set_conversation_ttl,
+            commands::copilot_ask_stream,
             commands::copilot_hint,
+            commands::copilot_prefetch_hints,
             commands::copilot_explain,
+            commands::copilot_compare,
+            commands::suggest_corrections,
+            commands::copilot_explain_batch,
+            commands::copilot_generate_sentence,
+            commands::copilot_explain_breakdown,
             commands::copilot_analyze_mistake,
+            commands::copilot_scenario,
+            commands::copilot_scenario_reply,
+            commands::copilot_quiz,
+            commands::copilot_check_answer,
+            commands::copilot_cancel,
+            commands::copilot_set_persona,
+            commands::copilot_list_models,
+            commands::copilot_set_cache_capacity,
+            commands::copilot_last_error,
+            commands::copilot_usage,
+            commands::copilot_reset_usage,
+            commands::copilot_set_model,
+            commands::copilot_reset_session,
+            commands::set_layout,
+            commands::set_physical_layout,
+            commands::copilot_set_timeout,
+            commands::copilot_set_hint_timeout,
+            commands::copilot_set_idle_timeout,
+            commands::copilot_set_rate_limit,
+            commands::copilot_set_max_concurrency,
+            commands::copilot_validate_prompt,
+            commands::copilot_set_prewarm,
+            commands::copilot_enable_mock,
+            commands::set_log_level,
             commands::copilot_shutdown,
+            commands::generate_targets,
+            commands::distractors,
+            commands::curriculum_load,
+            commands::ngram_load_corpus,
+            commands::likely_next,
+            commands::validate_wordlist,
+            commands::recommend_next,
+            commands::analytics_summary,
+            commands::mastery_map,
+            commands::minimal_pairs,
+            commands::stroke_order,
+            commands::jamo_mnemonic,
+            commands::layout_cheatsheet,
+            commands::looks_like_wrong_layout,
+            commands::composer_new,
+            commands::composer_feed,
+            commands::composer_reset,
+            commands::composer_close,
+            commands::live_next_key,
+            commands::bench_compose,
+            commands::grade,
+            commands::score,
+            commands::set_archaic,
+            commands::compose_syllable,
+            commands::decompose_syllable,
+            commands::next_keys,
+            commands::romanize,
+            commands::romanize_input,
+            commands::romaja_to_hangul,
+            commands::detect_prompt_language,
+            commands::session_start,
+            commands::replay,
+            commands::session_record,
+            commands::session_stats,
+            commands::session_snapshot,
+            commands::session_compare,
+            commands::expected_duration,
+            commands::session_pause,
+            commands::session_resume,
+            commands::session_key_heatmap,
+            commands::session_slow_transitions,
+            commands::session_finger_load,
+            commands::srs_due,
+            commands::srs_record,
+            commands::export_progress_csv,
+            commands::export_anki_tsv,
+            commands::progress_set_autosave,
+            commands::progress_load,
+            commands::progress_reset,
+            commands::progress_try_advance,
+            commands::profile_create,
+            commands::profile_switch,
+            commands::profile_list,
+            commands::profile_best_wpm,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,89 @@
+//! Tracing setup with a log level that can be changed at runtime.
+//!
+//! The filter is installed behind a reload layer, so support can ask a user
+//! to turn on debug logging while reproducing a problem, without setting
+//! `RUST_LOG` and restarting the app.
+
+use once_cell::sync::OnceCell;
+use thiserror::Error;
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::{EnvFilter, Registry, fmt, prelude::*, reload};
+
+/// Handle for swapping the installed filter
+static FILTER_HANDLE: OnceCell<reload::Handle<EnvFilter, Registry>> = OnceCell::new();
+
+/// Levels accepted by [`set_level`]
+const LEVELS: [&str; 6] = ["trace", "debug", "info", "warn", "error", "off"];
+
+/// Errors that can occur changing the log level
+#[derive(Debug, Error)]
+pub enum LoggingError {
+    #[error("Unknown log level '{0}', expected one of: trace, debug, info, warn, error, off")]
+    InvalidLevel(String),
+    #[error("Logging is not initialized")]
+    NotInitialized,
+    #[error("Failed to change log level: {0}")]
+    ReloadFailed(String),
+}
+
+/// Filter from `RUST_LOG`, with `level` as the default for everything else
+fn filter(level: LevelFilter) -> EnvFilter {
+    EnvFilter::from_default_env().add_directive(level.into())
+}
+
+/// Install the global subscriber at INFO level.
+///
+/// Safe to call more than once; only the first call installs anything.
+pub fn init() {
+    FILTER_HANDLE.get_or_init(|| {
+        let (layer, handle) = reload::Layer::new(filter(LevelFilter::INFO));
+        // Fails only if another subscriber is already set, e.g. in tests
+        let _ = tracing_subscriber::registry()
+            .with(layer)
+            .with(fmt::layer())
+            .try_init();
+        handle
+    });
+}
+
+/// Parse a standard level name, case-insensitively
+pub fn parse_level(level: &str) -> Result<LevelFilter, LoggingError> {
+    let normalized = level.trim().to_ascii_lowercase();
+    if !LEVELS.contains(&normalized.as_str()) {
+        return Err(LoggingError::InvalidLevel(level.to_string()));
+    }
+    normalized
+        .parse()
+        .map_err(|_| LoggingError::InvalidLevel(level.to_string()))
+}
+
+/// Change the default log level of the running app
+pub fn set_level(level: &str) -> Result<LevelFilter, LoggingError> {
+    let level = parse_level(level)?;
+    let handle = FILTER_HANDLE.get().ok_or(LoggingError::NotInitialized)?;
+    handle
+        .reload(filter(level))
+        .map_err(|e| LoggingError::ReloadFailed(e.to_string()))?;
+    Ok(level)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_unknown_level() {
+        init();
+        assert!(matches!(
+            set_level("verbose"),
+            Err(LoggingError::InvalidLevel(_))
+        ));
+    }
+
+    #[test]
+    fn accepts_standard_levels() {
+        init();
+        assert_eq!(set_level("DEBUG").unwrap(), LevelFilter::DEBUG);
+        assert_eq!(set_level("info").unwrap(), LevelFilter::INFO);
+    }
+}
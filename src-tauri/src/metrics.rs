@@ -0,0 +1,832 @@
+//! Typing metrics: words-per-minute and accuracy.
+//!
+//! A `TypingSession` records every keystroke the learner makes against the
+//! character they were expected to type, and derives live statistics from
+//! that timeline, including a per-key heat map of presses and errors.
+//! Time spent paused is left out of the timeline. Sessions can be saved as
+//! a `SavedSession` and compared later to show improvement, and recent
+//! sessions are kept so an attempt can be replayed key by key.
+
+use crate::jamo;
+use crate::layout::{Finger, KeyPress, Layout};
+use crate::progress::{MilestoneTracker, ProgressEvent};
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+/// The session currently being recorded
+static CURRENT_SESSION: OnceCell<Mutex<Option<TypingSession>>> = OnceCell::new();
+
+/// Finished attempts kept for replay
+static ATTEMPT_HISTORY: OnceCell<Mutex<AttemptHistory>> = OnceCell::new();
+
+/// Most finished attempts kept for replay; older ones are forgotten
+pub const MAX_RECENT_ATTEMPTS: usize = 20;
+
+/// Keystrokes that make up one "word" in the standard WPM convention
+const KEYSTROKES_PER_WORD: f32 = 5.0;
+
+/// Average keystrokes needed to type one Hangul syllable block
+const KEYSTROKES_PER_SYLLABLE: f32 = 2.5;
+
+/// One character typed by the learner
+#[derive(Debug, Clone, Copy)]
+pub struct Keystroke {
+    pub expected: char,
+    pub actual: char,
+    pub at: Instant,
+}
+
+impl Keystroke {
+    pub fn is_correct(&self) -> bool {
+        self.expected == self.actual
+    }
+}
+
+/// A timed sequence of keystrokes
+#[derive(Debug, Clone)]
+pub struct TypingSession {
+    /// Identifies the attempt for replay
+    pub id: Uuid,
+    pub started: Instant,
+    pub keystrokes: Vec<Keystroke>,
+    /// Milestones already reported for this session
+    pub milestones: MilestoneTracker,
+    /// Completed pauses, as (paused, resumed) instants
+    pauses: Vec<(Instant, Instant)>,
+    /// When the current pause began, if the session is paused
+    paused_at: Option<Instant>,
+    /// Keystrokes already flushed to saved progress
+    saved: usize,
+}
+
+/// Snapshot of a session's live statistics
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionStats {
+    pub wpm: f32,
+    pub accuracy: f32,
+    pub total_keystrokes: usize,
+    pub correct_keystrokes: usize,
+}
+
+/// A session in serializable form, for comparing against later sessions
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedSession {
+    pub keystrokes: Vec<SavedKeystroke>,
+}
+
+/// One keystroke of a `SavedSession`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SavedKeystroke {
+    pub expected: char,
+    pub actual: char,
+    /// Time since the session start, not counting pauses
+    pub elapsed_ms: u64,
+}
+
+/// One key press of a replayed attempt
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ReplayFrame {
+    pub key: KeyPress,
+    /// Time since the attempt started, less any time paused
+    pub at_ms: u64,
+    /// Whether this is the key the expected character needed here
+    pub correct: bool,
+}
+
+/// Recent finished attempts, oldest first
+#[derive(Debug, Default)]
+pub struct AttemptHistory {
+    attempts: VecDeque<(Uuid, SavedSession)>,
+}
+
+/// How a later session compares with an earlier one
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SessionDelta {
+    /// Positive when the later session was faster
+    pub wpm_delta: f32,
+    /// Positive when the later session was more accurate (-1.0 - 1.0)
+    pub accuracy_delta: f32,
+    /// Every character expected in either session, in code point order
+    pub per_char: Vec<CharDelta>,
+}
+
+/// Accuracy on one expected character in each of two sessions
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct CharDelta {
+    pub character: char,
+    /// `None` if the character wasn't practised in the earlier session
+    pub before: Option<f32>,
+    /// `None` if the character wasn't practised in the later session
+    pub after: Option<f32>,
+    /// Change in accuracy, when the character was practised in both
+    pub improvement: Option<f32>,
+}
+
+/// How often one key was needed and how often it was missed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct KeyStats {
+    pub presses: u32,
+    pub errors: u32,
+}
+
+impl TypingSession {
+    pub fn new() -> Self {
+        Self::starting_at(Instant::now())
+    }
+
+    pub fn starting_at(started: Instant) -> Self {
+        Self::for_level(1, started)
+    }
+
+    /// Start a session practising `level`, so passing it can be detected
+    pub fn for_level(level: u32, started: Instant) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            started,
+            keystrokes: Vec::new(),
+            milestones: MilestoneTracker::new(level),
+            pauses: Vec::new(),
+            paused_at: None,
+            saved: 0,
+        }
+    }
+
+    /// Keystrokes not yet flushed to saved progress
+    pub fn unsaved(&self) -> &[Keystroke] {
+        &self.keystrokes[self.saved.min(self.keystrokes.len())..]
+    }
+
+    /// Note that the first `count` keystrokes have been saved
+    pub fn mark_saved(&mut self, count: usize) {
+        self.saved = self.saved.max(count.min(self.keystrokes.len()));
+    }
+
+    /// Stop the clock now; does nothing if already paused
+    pub fn pause(&mut self) {
+        self.pause_at(Instant::now());
+    }
+
+    /// Stop the clock at a specific instant; does nothing if already paused
+    pub fn pause_at(&mut self, at: Instant) {
+        if self.paused_at.is_none() {
+            self.paused_at = Some(at);
+        }
+    }
+
+    /// Restart the clock now; does nothing if not paused
+    pub fn resume(&mut self) {
+        self.resume_at(Instant::now());
+    }
+
+    /// Restart the clock at a specific instant; does nothing if not paused
+    pub fn resume_at(&mut self, at: Instant) {
+        if let Some(paused) = self.paused_at.take() {
+            self.pauses.push((paused, at.max(paused)));
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused_at.is_some()
+    }
+
+    /// Time spent paused between the session start and `until`
+    fn paused_before(&self, until: Instant) -> Duration {
+        self.pauses
+            .iter()
+            .copied()
+            .chain(self.paused_at.map(|paused| (paused, until)))
+            .map(|(paused, resumed)| resumed.min(until).saturating_duration_since(paused))
+            .sum()
+    }
+
+    /// Record a keystroke made now
+    pub fn record(&mut self, expected: char, actual: char) {
+        self.record_at(expected, actual, Instant::now());
+    }
+
+    /// Record a keystroke made at a specific instant
+    pub fn record_at(&mut self, expected: char, actual: char, at: Instant) {
+        self.keystrokes.push(Keystroke {
+            expected,
+            actual,
+            at,
+        });
+    }
+
+    /// Number of correctly typed characters
+    pub fn correct_count(&self) -> usize {
+        self.keystrokes.iter().filter(|k| k.is_correct()).count()
+    }
+
+    /// Words per minute over correctly typed characters.
+    ///
+    /// Uses the standard 5-keystrokes-per-word convention, counting each
+    /// completed Hangul syllable as 2.5 keystrokes and anything else (jamo,
+    /// spaces) as one. Elapsed time runs from the session start to the last
+    /// keystroke, less any time spent paused.
+    pub fn wpm(&self) -> f32 {
+        let Some(last) = self.keystrokes.last() else {
+            return 0.0;
+        };
+
+        let elapsed = last
+            .at
+            .saturating_duration_since(self.started)
+            .saturating_sub(self.paused_before(last.at));
+        let minutes = elapsed.as_secs_f32() / 60.0;
+        if minutes <= 0.0 {
+            return 0.0;
+        }
+
+        let keystrokes: f32 = self
+            .keystrokes
+            .iter()
+            .filter(|k| k.is_correct())
+            .map(|k| keystroke_weight(k.expected))
+            .sum();
+
+        keystrokes / KEYSTROKES_PER_WORD / minutes
+    }
+
+    /// Fraction of characters typed correctly (0.0 - 1.0)
+    pub fn accuracy(&self) -> f32 {
+        if self.keystrokes.is_empty() {
+            return 0.0;
+        }
+        self.correct_count() as f32 / self.keystrokes.len() as f32
+    }
+
+    /// Milestones reached since the last check, each reported only once
+    pub fn check_milestones(&mut self) -> Vec<ProgressEvent> {
+        let stats = self.stats();
+        self.milestones.observe(&stats)
+    }
+
+    /// This session in serializable form
+    pub fn to_saved(&self) -> SavedSession {
+        SavedSession {
+            keystrokes: self
+                .keystrokes
+                .iter()
+                .map(|k| SavedKeystroke {
+                    expected: k.expected,
+                    actual: k.actual,
+                    elapsed_ms: k
+                        .at
+                        .saturating_duration_since(self.started)
+                        .saturating_sub(self.paused_before(k.at))
+                        .as_millis() as u64,
+                })
+                .collect(),
+        }
+    }
+
+    /// Rebuild a saved session as if it started at `started`, without pauses
+    pub fn from_saved(saved: &SavedSession, started: Instant) -> Self {
+        let mut session = Self::starting_at(started);
+        for k in &saved.keystrokes {
+            session.record_at(
+                k.expected,
+                k.actual,
+                started + Duration::from_millis(k.elapsed_ms),
+            );
+        }
+        session
+    }
+
+    pub fn stats(&self) -> SessionStats {
+        SessionStats {
+            wpm: self.wpm(),
+            accuracy: self.accuracy(),
+            total_keystrokes: self.keystrokes.len(),
+            correct_keystrokes: self.correct_count(),
+        }
+    }
+}
+
+impl Default for TypingSession {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Presses and errors per key over a session, for a keyboard overlay.
+///
+/// Each expected character is expanded into its key presses on `layout`
+/// and compared key by key with the keys for what was actually typed; a
+/// key counts as an error when the learner pressed something else in its
+/// place. Shifted keys (ㄲ = Shift + r) are tracked apart from their base.
+pub fn key_heatmap(session: &TypingSession, layout: Layout) -> HashMap<KeyPress, KeyStats> {
+    let mut heatmap: HashMap<KeyPress, KeyStats> = HashMap::new();
+
+    for keystroke in &session.keystrokes {
+        let expected = layout.keystrokes_for_text(&keystroke.expected.to_string());
+        let actual = if keystroke.is_correct() {
+            expected.clone()
+        } else {
+            layout.keystrokes_for_text(&keystroke.actual.to_string())
+        };
+
+        for (i, key) in expected.iter().enumerate() {
+            let stats = heatmap.entry(*key).or_default();
+            stats.presses += 1;
+            if actual.get(i) != Some(key) {
+                stats.errors += 1;
+            }
+        }
+    }
+
+    heatmap
+}
+
+/// Key presses made by each finger over a session.
+///
+/// Counts what the learner actually typed, expanded into keys on `layout`.
+/// A shifted key also counts a press for the opposite pinky on shift.
+pub fn finger_load(session: &TypingSession, layout: Layout) -> HashMap<Finger, u32> {
+    let mut load = HashMap::new();
+    let typed: String = session.keystrokes.iter().map(|k| k.actual).collect();
+
+    for key in layout.keystrokes_for_text(&typed) {
+        let Some(finger) = Finger::for_key(key.key) else {
+            continue;
+        };
+        *load.entry(finger).or_insert(0) += 1;
+        if key.shift {
+            *load.entry(finger.shift_finger()).or_insert(0) += 1;
+        }
+    }
+
+    load
+}
+
+/// The `top_n` key-to-key transitions that take longest on average,
+/// slowest first, as (from, to, mean time).
+///
+/// Keystrokes are recorded per character, so the time to type a character
+/// is split evenly over its keys on `layout`; the first of them is timed
+/// from the last key of the character before. Paused time is left out.
+pub fn slow_transitions(
+    session: &TypingSession,
+    layout: Layout,
+    top_n: usize,
+) -> Vec<(KeyPress, KeyPress, Duration)> {
+    let mut totals: HashMap<(KeyPress, KeyPress), (Duration, u32)> = HashMap::new();
+
+    for pair in session.keystrokes.windows(2) {
+        let (prev, cur) = (pair[0], pair[1]);
+        let Some(&last) = layout.keystrokes_for_text(&prev.actual.to_string()).last() else {
+            continue;
+        };
+        let keys = layout.keystrokes_for_text(&cur.actual.to_string());
+        if keys.is_empty() {
+            continue;
+        }
+
+        let paused = session
+            .paused_before(cur.at)
+            .saturating_sub(session.paused_before(prev.at));
+        let active = cur
+            .at
+            .saturating_duration_since(prev.at)
+            .saturating_sub(paused);
+        let per_key = active / keys.len() as u32;
+
+        let mut from = last;
+        for to in keys {
+            let (total, count) = totals.entry((from, to)).or_default();
+            *total += per_key;
+            *count += 1;
+            from = to;
+        }
+    }
+
+    let mut means: Vec<(KeyPress, KeyPress, Duration)> = totals
+        .into_iter()
+        .map(|((from, to), (total, count))| (from, to, total / count))
+        .collect();
+    // Ties in key order, so the list is stable between calls
+    means.sort_by_key(|&(from, to, mean)| {
+        (Reverse(mean), (from.key, from.shift), (to.key, to.shift))
+    });
+    means.truncate(top_n);
+    means
+}
+
+/// Accuracy on each expected character in a session
+fn char_accuracy(session: &TypingSession) -> BTreeMap<char, f32> {
+    let mut counts: BTreeMap<char, (u32, u32)> = BTreeMap::new();
+    for keystroke in &session.keystrokes {
+        let (attempts, correct) = counts.entry(keystroke.expected).or_default();
+        *attempts += 1;
+        if keystroke.is_correct() {
+            *correct += 1;
+        }
+    }
+    counts
+        .into_iter()
+        .map(|(c, (attempts, correct))| (c, correct as f32 / attempts as f32))
+        .collect()
+}
+
+/// Compare a later session `b` with an earlier session `a`.
+///
+/// Characters practised in only one of the sessions are still listed, with
+/// no accuracy for the other session and no improvement.
+pub fn compare(a: &TypingSession, b: &TypingSession) -> SessionDelta {
+    let before = char_accuracy(a);
+    let after = char_accuracy(b);
+
+    let mut characters: Vec<char> = before.keys().chain(after.keys()).copied().collect();
+    characters.sort_unstable();
+    characters.dedup();
+
+    let per_char = characters
+        .into_iter()
+        .map(|character| {
+            let before = before.get(&character).copied();
+            let after = after.get(&character).copied();
+            CharDelta {
+                character,
+                before,
+                after,
+                improvement: before.zip(after).map(|(before, after)| after - before),
+            }
+        })
+        .collect();
+
+    SessionDelta {
+        wpm_delta: b.wpm() - a.wpm(),
+        accuracy_delta: b.accuracy() - a.accuracy(),
+        per_char,
+    }
+}
+
+/// How long typing `target` on `layout` should take at `target_wpm`, for
+/// pacing.
+///
+/// Counts the real key presses, including shift and spaces, at the
+/// standard five keystrokes per word. Zero if `target_wpm` isn't a positive
+/// speed, and [`Duration::MAX`] if it is too slow to time.
+pub fn expected_duration(target: &str, layout: Layout, target_wpm: f32) -> Duration {
+    if !(target_wpm.is_finite() && target_wpm > 0.0) {
+        return Duration::ZERO;
+    }
+    let keystrokes = layout.keystrokes_for_text(target).len() as f32;
+    Duration::try_from_secs_f32(keystrokes / KEYSTROKES_PER_WORD / target_wpm * 60.0)
+        .unwrap_or(Duration::MAX)
+}
+
+/// Keystroke-equivalents represented by one typed character
+fn keystroke_weight(c: char) -> f32 {
+    if jamo::decompose(c).is_some() {
+        KEYSTROKES_PER_SYLLABLE
+    } else {
+        1.0
+    }
+}
+
+impl AttemptHistory {
+    /// Keep `session` for replay, forgetting the oldest attempt once there
+    /// are [`MAX_RECENT_ATTEMPTS`]. Sessions with no keystrokes are skipped.
+    pub fn push(&mut self, session: &TypingSession) {
+        if session.keystrokes.is_empty() {
+            return;
+        }
+        if self.attempts.len() == MAX_RECENT_ATTEMPTS {
+            self.attempts.pop_front();
+        }
+        self.attempts.push_back((session.id, session.to_saved()));
+    }
+
+    /// The timeline of attempt `id`, if it is still kept
+    pub fn get(&self, id: Uuid) -> Option<&SavedSession> {
+        self.attempts
+            .iter()
+            .find(|(attempt, _)| *attempt == id)
+            .map(|(_, saved)| saved)
+    }
+}
+
+/// Key presses of a recorded attempt on `layout`, in order.
+///
+/// Keystrokes are recorded per character, so each character's keys are
+/// spread evenly over the time since the character before, the last key
+/// landing when the character was recorded.
+pub fn replay(saved: &SavedSession, layout: Layout) -> Vec<ReplayFrame> {
+    let mut frames = Vec::new();
+    let mut previous_ms = 0;
+
+    for keystroke in &saved.keystrokes {
+        let keys = layout.keystrokes_for_text(&keystroke.actual.to_string());
+        let expected = layout.keystrokes_for_text(&keystroke.expected.to_string());
+        let span = keystroke.elapsed_ms.saturating_sub(previous_ms);
+        let count = keys.len() as u64;
+
+        for (i, &key) in keys.iter().enumerate() {
+            frames.push(ReplayFrame {
+                key,
+                at_ms: previous_ms + span * (i as u64 + 1) / count,
+                correct: expected.get(i) == Some(&key),
+            });
+        }
+        previous_ms = keystroke.elapsed_ms;
+    }
+    frames
+}
+
+/// Get the finished attempts kept for replay
+pub fn attempt_history() -> &'static Mutex<AttemptHistory> {
+    ATTEMPT_HISTORY.get_or_init(|| Mutex::new(AttemptHistory::default()))
+}
+
+/// Get the slot holding the session currently being recorded
+pub fn current_session() -> &'static Mutex<Option<TypingSession>> {
+    CURRENT_SESSION.get_or_init(|| Mutex::new(None))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wpm_counts_syllables_as_two_and_a_half_keystrokes() {
+        let start = Instant::now();
+        let mut session = TypingSession::starting_at(start);
+
+        // 4 syllables = 10 keystrokes = 2 words, typed over 30 seconds
+        for (i, c) in "안녕하세".chars().enumerate() {
+            session.record_at(c, c, start + Duration::from_millis(7_500 * (i as u64 + 1)));
+        }
+
+        assert!((session.wpm() - 4.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn slow_transitions_averages_each_key_pair() {
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        let mut session = TypingSession::starting_at(start);
+
+        // 가 (r k), then 가 again over 400ms: k→r and r→k take 200ms each
+        session.record_at('가', '가', at(0));
+        session.record_at('가', '가', at(400));
+        // 계 (r, Shift+p) over 1s: k→r and r→ㅖ take 500ms each
+        session.record_at('계', '계', at(1_400));
+        // 가 over 200ms, after a pause that doesn't count
+        session.pause_at(at(1_500));
+        session.resume_at(at(5_500));
+        session.record_at('가', '가', at(5_600));
+
+        let slow = slow_transitions(&session, Layout::TwoBulsik, 2);
+        let key = KeyPress::plain;
+        assert_eq!(
+            slow,
+            vec![
+                (key('r'), KeyPress::shifted('p'), Duration::from_millis(500)),
+                // Mean of 200ms and 500ms
+                (key('k'), key('r'), Duration::from_millis(350)),
+            ]
+        );
+    }
+
+    #[test]
+    fn wpm_excludes_paused_time() {
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        let mut session = TypingSession::starting_at(start);
+
+        // 2 words typed over 60 active seconds, with a 5 minute break
+        session.record_at('안', '안', at(20));
+        session.record_at('녕', '녕', at(30));
+        session.pause_at(at(30));
+        session.pause_at(at(100));
+        session.resume_at(at(330));
+        session.resume_at(at(400));
+        session.record_at('하', '하', at(350));
+        session.record_at('세', '세', at(360));
+
+        assert!(!session.is_paused());
+        assert!((session.wpm() - 2.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn resume_without_pause_is_ignored() {
+        let start = Instant::now();
+        let mut session = TypingSession::starting_at(start);
+        session.resume_at(start + Duration::from_secs(10));
+        session.record_at('ㄱ', 'ㄱ', start + Duration::from_secs(12));
+
+        assert!(!session.is_paused());
+        assert!((session.wpm() - 1.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn finger_load_counts_keys_and_shift() {
+        let start = Instant::now();
+        let mut session = TypingSession::starting_at(start);
+        // 가 = r k, 까 = Shift + r, k
+        session.record_at('가', '가', start);
+        session.record_at('까', '까', start);
+
+        let load = finger_load(&session, Layout::TwoBulsik);
+        assert_eq!(load.get(&Finger::LeftIndex), Some(&2));
+        assert_eq!(load.get(&Finger::RightMiddle), Some(&2));
+        assert_eq!(load.get(&Finger::RightPinky), Some(&1));
+        assert_eq!(load.get(&Finger::LeftPinky), None);
+    }
+
+    #[test]
+    fn wpm_ignores_incorrect_keystrokes() {
+        let start = Instant::now();
+        let mut session = TypingSession::starting_at(start);
+
+        // 5 correct jamo = 1 word over one minute, plus one mistake
+        for c in "ㄱㄴㄷㄹㅁ".chars() {
+            session.record_at(c, c, start + Duration::from_secs(10));
+        }
+        session.record_at('ㅂ', 'ㅈ', start + Duration::from_secs(60));
+
+        assert!((session.wpm() - 1.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn accuracy_is_fraction_of_correct_keystrokes() {
+        let start = Instant::now();
+        let mut session = TypingSession::starting_at(start);
+        session.record_at('가', '가', start);
+        session.record_at('나', '나', start);
+        session.record_at('다', '타', start);
+        session.record_at('라', '라', start);
+
+        assert!((session.accuracy() - 0.75).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn heatmap_counts_presses_and_errors_per_key() {
+        let start = Instant::now();
+        let mut session = TypingSession::starting_at(start);
+        session.record_at('가', '가', start);
+        session.record_at('가', '카', start);
+        session.record_at('까', '가', start);
+
+        let heatmap = key_heatmap(&session, Layout::TwoBulsik);
+
+        assert_eq!(
+            heatmap[&KeyPress::plain('r')],
+            KeyStats {
+                presses: 2,
+                errors: 1
+            }
+        );
+        assert_eq!(
+            heatmap[&KeyPress::plain('k')],
+            KeyStats {
+                presses: 3,
+                errors: 0
+            }
+        );
+        // ㄲ is tracked on Shift + r, apart from plain r
+        assert_eq!(
+            heatmap[&KeyPress::shifted('r')],
+            KeyStats {
+                presses: 1,
+                errors: 1
+            }
+        );
+    }
+
+    #[test]
+    fn empty_session_has_zero_stats() {
+        let session = TypingSession::new();
+        assert_eq!(session.wpm(), 0.0);
+        assert_eq!(session.accuracy(), 0.0);
+    }
+
+    #[test]
+    fn compare_reports_faster_but_less_accurate_session() {
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        let mut before = TypingSession::starting_at(start);
+        before.record_at('가', '가', at(30));
+        before.record_at('나', '나', at(60));
+
+        let mut after = TypingSession::starting_at(start);
+        after.record_at('가', '가', at(10));
+        after.record_at('나', '다', at(20));
+        after.record_at('다', '다', at(30));
+
+        let delta = compare(&before, &after);
+        assert!(delta.wpm_delta > 0.0, "{delta:?}");
+        assert!((delta.accuracy_delta - (2.0 / 3.0 - 1.0)).abs() < 0.001);
+
+        let chars: Vec<char> = delta.per_char.iter().map(|d| d.character).collect();
+        assert_eq!(chars, vec!['가', '나', '다']);
+        assert_eq!(delta.per_char[0].improvement, Some(0.0));
+        assert_eq!(delta.per_char[1].improvement, Some(-1.0));
+        // Only practised in the later session
+        assert_eq!(delta.per_char[2].before, None);
+        assert_eq!(delta.per_char[2].improvement, None);
+    }
+
+    #[test]
+    fn saved_session_round_trips_without_pauses() {
+        let start = Instant::now();
+        let mut session = TypingSession::starting_at(start);
+        session.record_at('가', '가', start + Duration::from_secs(10));
+        session.pause_at(start + Duration::from_secs(10));
+        session.resume_at(start + Duration::from_secs(70));
+        session.record_at('나', '나', start + Duration::from_secs(80));
+
+        let saved = session.to_saved();
+        assert_eq!(saved.keystrokes[1].elapsed_ms, 20_000);
+
+        let restored = TypingSession::from_saved(&saved, start);
+        assert!((restored.wpm() - session.wpm()).abs() < 0.001);
+    }
+
+    #[test]
+    fn recorded_attempt_replays_keys_in_order() {
+        let start = Instant::now();
+        let mut session = TypingSession::starting_at(start);
+        session.record_at('가', '가', start + Duration::from_millis(300));
+        // ㅓ (j) typed in place of ㅏ (k)
+        session.record_at('나', '너', start + Duration::from_millis(900));
+
+        let mut history = AttemptHistory::default();
+        history.push(&session);
+        let saved = history.get(session.id).unwrap();
+
+        let frames: Vec<(char, u64, bool)> = replay(saved, Layout::TwoBulsik)
+            .into_iter()
+            .map(|f| (f.key.key, f.at_ms, f.correct))
+            .collect();
+        assert_eq!(
+            frames,
+            [
+                ('r', 150, true),
+                ('k', 300, true),
+                ('s', 600, true),
+                ('j', 900, false)
+            ]
+        );
+    }
+
+    #[test]
+    fn attempt_history_is_bounded() {
+        let mut history = AttemptHistory::default();
+        let mut first = TypingSession::new();
+        first.record('가', '가');
+        history.push(&first);
+        for _ in 0..MAX_RECENT_ATTEMPTS {
+            let mut session = TypingSession::new();
+            session.record('가', '가');
+            history.push(&session);
+        }
+        history.push(&TypingSession::new());
+
+        assert_eq!(history.attempts.len(), MAX_RECENT_ATTEMPTS);
+        assert!(history.get(first.id).is_none());
+    }
+
+    #[test]
+    fn longer_words_take_longer_at_the_same_speed() {
+        // 2 keys vs 6 keys
+        let short = expected_duration("가", Layout::TwoBulsik, 30.0);
+        let long = expected_duration("한국", Layout::TwoBulsik, 30.0);
+        assert!(long > short);
+        // 2 keystrokes at 30 WPM = 150 keystrokes a minute
+        assert_eq!(short.as_millis(), 800);
+        assert_eq!(
+            expected_duration("가", Layout::TwoBulsik, 0.0),
+            Duration::ZERO
+        );
+    }
+
+    #[test]
+    fn expected_duration_counts_the_layouts_keys() {
+        // ㄲ is Shift+r on 2-Bulsik but two presses of ㄱ on 3-Bulsik
+        assert_eq!(
+            expected_duration("까", Layout::TwoBulsik, 30.0).as_millis(),
+            800
+        );
+        assert_eq!(
+            expected_duration("까", Layout::ThreeBulsikFinal, 30.0).as_millis(),
+            1200
+        );
+    }
+
+    #[test]
+    fn absurdly_slow_speed_does_not_panic() {
+        assert_eq!(
+            expected_duration("가", Layout::TwoBulsik, 1e-40),
+            Duration::MAX
+        );
+    }
+}
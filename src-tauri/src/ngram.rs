@@ -0,0 +1,169 @@
+//! Next-syllable prediction from syllable bigrams.
+//!
+//! A small bigram table is bundled with the app, so sentence practice can
+//! chain natural-looking syllables and highlight the likely next one.
+//! Teachers can replace it with a model counted from their own text.
+
+use crate::jamo;
+use once_cell::sync::OnceCell;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{PoisonError, RwLock};
+use thiserror::Error;
+
+/// Tab-separated `previous<TAB>next<TAB>count` lines
+const BUNDLED_TABLE: &str = include_str!("../data/syllable_bigrams.tsv");
+
+/// Most predictions returned for one syllable
+pub const MAX_PREDICTIONS: usize = 8;
+
+/// Model used for predictions. Read from sync code, so a blocking lock.
+static ACTIVE: OnceCell<RwLock<BigramModel>> = OnceCell::new();
+
+/// Errors that can occur building a model from a corpus
+#[derive(Debug, Error)]
+pub enum NgramError {
+    #[error("Failed to read corpus file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Corpus has no adjacent Hangul syllables")]
+    Empty,
+}
+
+/// Which syllables follow which, most common first
+#[derive(Debug, Clone, Default)]
+pub struct BigramModel {
+    next: HashMap<char, Vec<(char, u32)>>,
+}
+
+impl BigramModel {
+    /// Build a model from `(previous, next, count)` triples
+    fn from_counts(counts: HashMap<(char, char), u32>) -> Self {
+        let mut next: HashMap<char, Vec<(char, u32)>> = HashMap::new();
+        for ((prev, following), count) in counts {
+            next.entry(prev).or_default().push((following, count));
+        }
+        // Ties in code point order, so predictions are stable
+        for followers in next.values_mut() {
+            followers.sort_by_key(|&(c, count)| (std::cmp::Reverse(count), c));
+        }
+        Self { next }
+    }
+
+    /// Parse a bigram table; malformed lines and `#` comments are skipped
+    pub fn from_table(table: &str) -> Self {
+        let mut counts = HashMap::new();
+        for line in table.lines().filter(|line| !line.starts_with('#')) {
+            let mut fields = line.split('\t');
+            let (Some(prev), Some(next), Some(count), None) =
+                (fields.next(), fields.next(), fields.next(), fields.next())
+            else {
+                continue;
+            };
+            let (mut prev, mut next) = (prev.chars(), next.chars());
+            if let ((Some(p), None), (Some(n), None), Ok(count)) = (
+                (prev.next(), prev.next()),
+                (next.next(), next.next()),
+                count.trim().parse::<u32>(),
+            ) {
+                *counts.entry((p, n)).or_insert(0) += count;
+            }
+        }
+        Self::from_counts(counts)
+    }
+
+    /// Count the adjacent syllable pairs in `text`. Pairs never span a
+    /// space or punctuation, so words don't run into each other.
+    pub fn from_corpus(text: &str) -> Result<Self, NgramError> {
+        let text = jamo::normalize(text);
+        let mut counts = HashMap::new();
+        let chars: Vec<char> = text.chars().collect();
+        for pair in chars.windows(2) {
+            if jamo::decompose(pair[0]).is_some() && jamo::decompose(pair[1]).is_some() {
+                *counts.entry((pair[0], pair[1])).or_insert(0) += 1;
+            }
+        }
+        if counts.is_empty() {
+            return Err(NgramError::Empty);
+        }
+        Ok(Self::from_counts(counts))
+    }
+
+    /// Build a model from a UTF-8 text file
+    pub fn load_corpus(path: &Path) -> Result<Self, NgramError> {
+        Self::from_corpus(&std::fs::read_to_string(path)?)
+    }
+
+    /// The model bundled with the app
+    pub fn bundled() -> Self {
+        Self::from_table(BUNDLED_TABLE)
+    }
+
+    /// Syllables seen after `prev`, most common first, at most
+    /// [`MAX_PREDICTIONS`]
+    pub fn likely_next(&self, prev: char) -> Vec<char> {
+        self.next
+            .get(&prev)
+            .map(|followers| {
+                followers
+                    .iter()
+                    .take(MAX_PREDICTIONS)
+                    .map(|&(c, _)| c)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Number of syllables with at least one known follower
+    pub fn len(&self) -> usize {
+        self.next.len()
+    }
+
+    /// Whether the model can predict nothing
+    pub fn is_empty(&self) -> bool {
+        self.next.is_empty()
+    }
+}
+
+/// Get the model predictions come from
+pub fn active() -> &'static RwLock<BigramModel> {
+    ACTIVE.get_or_init(|| RwLock::new(BigramModel::bundled()))
+}
+
+/// Replace the active model
+pub fn set_active(model: BigramModel) {
+    *active().write().unwrap_or_else(PoisonError::into_inner) = model;
+}
+
+/// Common syllables following `prev` in the active model, most common first
+pub fn likely_next(prev: char) -> Vec<char> {
+    active()
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .likely_next(prev)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nyeong_ranks_highly_after_an() {
+        let next = BigramModel::bundled().likely_next('안');
+        assert_eq!(next.first(), Some(&'녕'));
+        assert_eq!(BigramModel::bundled().likely_next('똠'), Vec::<char>::new());
+    }
+
+    #[test]
+    fn corpus_pairs_stay_within_words() {
+        let model = BigramModel::from_corpus("안녕하세요. 안녕히 가세요, 안경").unwrap();
+        assert_eq!(model.likely_next('안'), ['녕', '경']);
+        assert_eq!(model.likely_next('세'), ['요']);
+        // "요. 안" and "요, 안" span punctuation
+        assert!(model.likely_next('요').is_empty());
+
+        assert!(matches!(
+            BigramModel::from_corpus("hello 가 나"),
+            Err(NgramError::Empty)
+        ));
+    }
+}
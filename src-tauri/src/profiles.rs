@@ -0,0 +1,265 @@
+//! Local learner profiles for shared devices.
+//!
+//! Each profile has its own progress file and best WPM under the app data
+//! directory, so a family can share one install. Without an active profile
+//! progress lives in the shared `progress.json`, as it did before profiles
+//! existed. The list is read once and then kept in memory, since every
+//! progress update needs to know the active profile.
+
+use crate::progress::PROGRESS_FILE;
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+/// File name of the profile list inside the app data directory
+pub const PROFILES_FILE: &str = "profiles.json";
+
+/// Directory holding one subdirectory of saved progress per profile
+const PROFILES_DIR: &str = "profiles";
+
+/// Longest profile name accepted, in characters
+pub const MAX_NAME_CHARS: usize = 32;
+
+/// The profile list as last loaded or saved
+static LOADED: OnceCell<Mutex<Option<Profiles>>> = OnceCell::new();
+
+/// Errors that can occur managing profiles
+#[derive(Debug, Error)]
+pub enum ProfileError {
+    #[error("Failed to access profiles file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Profiles file is corrupt: {0}")]
+    Parse(#[from] serde_json::Error),
+    #[error("Profile name is empty")]
+    EmptyName,
+    #[error("Profile name is longer than {MAX_NAME_CHARS} characters")]
+    NameTooLong,
+    #[error("A profile named \"{0}\" already exists")]
+    Duplicate(String),
+    #[error("No profile named \"{0}\"")]
+    NotFound(String),
+}
+
+/// One learner sharing the device
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Profile {
+    /// Stable identifier naming the profile's progress directory
+    pub id: String,
+    pub name: String,
+    /// Fastest session so far, once one has been long enough to count
+    pub best_wpm: Option<f32>,
+}
+
+/// A profile as listed for the frontend
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfileSummary {
+    pub name: String,
+    pub best_wpm: Option<f32>,
+    pub active: bool,
+}
+
+/// Every profile on the device and which one is in use
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Profiles {
+    /// Id of the active profile
+    pub active: Option<String>,
+    pub profiles: Vec<Profile>,
+}
+
+impl Profiles {
+    /// Load profiles from `path`, with none if the file does not exist
+    pub fn load(path: &Path) -> Result<Self, ProfileError> {
+        match std::fs::read_to_string(path) {
+            Ok(json) => Ok(serde_json::from_str(&json)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Save profiles to `path` atomically, like [`crate::progress::Progress::save`]
+    pub fn save(&self, path: &Path) -> Result<(), ProfileError> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// Add a profile called `name` without switching to it. Names are
+    /// trimmed and must be unique, ignoring case.
+    pub fn create(&mut self, name: &str) -> Result<&Profile, ProfileError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(ProfileError::EmptyName);
+        }
+        if name.chars().count() > MAX_NAME_CHARS {
+            return Err(ProfileError::NameTooLong);
+        }
+        if self.find(name).is_some() {
+            return Err(ProfileError::Duplicate(name.to_string()));
+        }
+
+        self.profiles.push(Profile {
+            id: Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            best_wpm: None,
+        });
+        Ok(&self.profiles[self.profiles.len() - 1])
+    }
+
+    /// Make the profile called `name` (ignoring case) the active one
+    pub fn switch(&mut self, name: &str) -> Result<(), ProfileError> {
+        let id = self
+            .find(name.trim())
+            .ok_or_else(|| ProfileError::NotFound(name.trim().to_string()))?
+            .id
+            .clone();
+        self.active = Some(id);
+        Ok(())
+    }
+
+    /// The active profile, or `None` if there isn't one or it has been
+    /// removed from the file
+    pub fn active(&self) -> Option<&Profile> {
+        let id = self.active.as_deref()?;
+        self.profiles.iter().find(|p| p.id == id)
+    }
+
+    /// Count `wpm` towards the active profile's best, returning whether it
+    /// beat the previous best
+    pub fn record_wpm(&mut self, wpm: f32) -> bool {
+        let Some(id) = self.active.clone() else {
+            return false;
+        };
+        let Some(profile) = self.profiles.iter_mut().find(|p| p.id == id) else {
+            return false;
+        };
+        if profile.best_wpm.is_some_and(|best| best >= wpm) {
+            return false;
+        }
+        profile.best_wpm = Some(wpm);
+        true
+    }
+
+    /// Where the active profile's progress is saved under `data_dir`,
+    /// falling back to the shared file when no profile is active
+    pub fn progress_path(&self, data_dir: &Path) -> PathBuf {
+        match self.active() {
+            Some(profile) => data_dir
+                .join(PROFILES_DIR)
+                .join(&profile.id)
+                .join(PROGRESS_FILE),
+            None => data_dir.join(PROGRESS_FILE),
+        }
+    }
+
+    /// Every profile, fastest first; profiles without a best come last
+    pub fn leaderboard(&self) -> Vec<ProfileSummary> {
+        let active = self.active().map(|p| p.id.as_str());
+        let mut board: Vec<ProfileSummary> = self
+            .profiles
+            .iter()
+            .map(|p| ProfileSummary {
+                name: p.name.clone(),
+                best_wpm: p.best_wpm,
+                active: Some(p.id.as_str()) == active,
+            })
+            .collect();
+        // Stable, so ties stay in the order the profiles were created
+        let wpm = |s: &ProfileSummary| s.best_wpm.unwrap_or(-1.0);
+        board.sort_by(|a, b| wpm(b).total_cmp(&wpm(a)));
+        board
+    }
+
+    fn find(&self, name: &str) -> Option<&Profile> {
+        let name = name.to_lowercase();
+        self.profiles.iter().find(|p| p.name.to_lowercase() == name)
+    }
+}
+
+/// Get the profile list kept in memory; `None` until first loaded
+pub fn loaded() -> &'static Mutex<Option<Profiles>> {
+    LOADED.get_or_init(|| Mutex::new(None))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn switching_profiles_isolates_best_wpm() {
+        let mut profiles = Profiles::default();
+        profiles.create("Mina").unwrap();
+        profiles.create("Joon").unwrap();
+
+        profiles.switch("Mina").unwrap();
+        assert!(profiles.record_wpm(40.0));
+        assert!(!profiles.record_wpm(35.0));
+
+        profiles.switch("joon").unwrap();
+        assert_eq!(profiles.active().unwrap().best_wpm, None);
+        assert!(profiles.record_wpm(20.0));
+
+        profiles.switch("Mina").unwrap();
+        assert_eq!(profiles.active().unwrap().best_wpm, Some(40.0));
+
+        let board: Vec<(String, Option<f32>)> = profiles
+            .leaderboard()
+            .into_iter()
+            .map(|s| (s.name, s.best_wpm))
+            .collect();
+        assert_eq!(
+            board,
+            [
+                ("Mina".to_string(), Some(40.0)),
+                ("Joon".to_string(), Some(20.0))
+            ]
+        );
+    }
+
+    #[test]
+    fn duplicate_and_empty_names_are_rejected() {
+        let mut profiles = Profiles::default();
+        profiles.create("Mina").unwrap();
+
+        assert!(matches!(
+            profiles.create(" mina "),
+            Err(ProfileError::Duplicate(_))
+        ));
+        assert!(matches!(
+            profiles.create("  "),
+            Err(ProfileError::EmptyName)
+        ));
+        assert!(matches!(
+            profiles.switch("Joon"),
+            Err(ProfileError::NotFound(_))
+        ));
+        assert_eq!(profiles.profiles.len(), 1);
+    }
+
+    #[test]
+    fn missing_active_profile_uses_shared_progress() {
+        let dir = Path::new("data");
+        let mut profiles = Profiles::default();
+        assert_eq!(profiles.progress_path(dir), dir.join(PROGRESS_FILE));
+
+        let id = profiles.create("Mina").unwrap().id.clone();
+        profiles.switch("Mina").unwrap();
+        assert_eq!(
+            profiles.progress_path(dir),
+            dir.join(PROFILES_DIR).join(&id).join(PROGRESS_FILE)
+        );
+
+        // The active profile was removed by hand
+        profiles.profiles.clear();
+        assert!(profiles.active().is_none());
+        assert!(!profiles.record_wpm(30.0));
+        assert_eq!(profiles.progress_path(dir), dir.join(PROGRESS_FILE));
+    }
+}
@@ -0,0 +1,727 @@
+//! Persistent learner progress and milestones.
+//!
+//! `Progress` is saved as JSON in the app data directory so unlocked
+//! levels, mistake counts and review schedules survive restarts.
+//! `MilestoneTracker` watches a typing session, reports accuracy milestones
+//! as `ProgressEvent`s and notices when the session is good enough to pass
+//! its level, which [`Progress::level_up_after`] then checks for mastery.
+//! `Autosave` decides when typed keystrokes are flushed to disk mid-session,
+//! and `ProgressStore` keeps the loaded progress in memory between saves.
+
+use crate::achievements::Achievement;
+use crate::curriculum;
+use crate::lesson;
+use crate::metrics::{Keystroke, SessionStats};
+use crate::romanize;
+use crate::srs::Scheduler;
+use chrono::{DateTime, Utc};
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tokio::sync::Mutex;
+
+/// File name of the saved progress inside the app data directory
+pub const PROGRESS_FILE: &str = "progress.json";
+
+/// Event emitted with a `ProgressEvent` payload when a learner hits a milestone
+pub const EVENT_PROGRESS: &str = "progress";
+
+/// Event emitted after all saved progress has been erased
+pub const EVENT_PROGRESS_RESET: &str = "progress-reset";
+
+/// Keystrokes needed before a session can pass a level or hit a milestone
+pub const MIN_KEYSTROKES: usize = 20;
+
+/// Default recent accuracy needed by [`Progress::try_advance_level`]
+pub const DEFAULT_ADVANCE_THRESHOLD: f32 = 0.9;
+
+/// Times each jamo introduced at a level must be typed before the level
+/// can be passed with [`Progress::try_advance_level`]
+pub const MIN_JAMO_ATTEMPTS: u32 = 3;
+
+/// Accuracy needed to pass each level, starting from level 1; later
+/// levels need 0.80
+const LEVEL_THRESHOLDS: &[f32] = &[0.90, 0.90, 0.90, 0.90, 0.85, 0.85, 0.80, 0.80, 0.75];
+
+/// Session accuracies worth celebrating
+const ACCURACY_MILESTONES: [f32; 3] = [0.90, 0.95, 1.0];
+
+/// Default number of keystrokes between autosaves
+pub const DEFAULT_AUTOSAVE_KEYSTROKES: u32 = 20;
+
+/// Default longest time between autosaves while typing
+pub const DEFAULT_AUTOSAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Shortest wait between autosave timer checks
+const MIN_AUTOSAVE_WAIT: Duration = Duration::from_millis(100);
+
+/// Progress of the active profile, shared by every command
+static STORE: OnceCell<Mutex<ProgressStore>> = OnceCell::new();
+
+/// When typed keystrokes are next flushed to disk
+static AUTOSAVE: OnceCell<Mutex<Autosave>> = OnceCell::new();
+
+/// Errors that can occur loading or saving progress
+#[derive(Debug, Error)]
+pub enum ProgressError {
+    #[error("Failed to access progress file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Progress file is corrupt: {0}")]
+    Parse(#[from] serde_json::Error),
+}
+
+/// Everything remembered about a learner between sessions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Progress {
+    pub unlocked_levels: Vec<u32>,
+    /// How often each character has been mistyped
+    pub per_char_mistakes: HashMap<char, u32>,
+    /// How often each character has been reviewed or typed, right or wrong
+    pub per_char_attempts: HashMap<char, u32>,
+    /// Review schedule for practised characters
+    pub srs: Scheduler,
+    /// Achievements already awarded, in the order they were earned
+    pub achievements: Vec<Achievement>,
+}
+
+impl Default for Progress {
+    fn default() -> Self {
+        Self {
+            unlocked_levels: vec![1],
+            per_char_mistakes: HashMap::new(),
+            per_char_attempts: HashMap::new(),
+            srs: Scheduler::new(),
+            achievements: Vec::new(),
+        }
+    }
+}
+
+impl Progress {
+    /// Load progress from `path`, starting fresh if the file does not exist
+    pub fn load(path: &Path) -> Result<Self, ProgressError> {
+        match std::fs::read_to_string(path) {
+            Ok(json) => Ok(serde_json::from_str(&json)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Save progress to `path`, creating its directory if needed.
+    ///
+    /// Writes to a temporary file next to `path` and renames it into place,
+    /// so a crash mid-write leaves the previous save intact.
+    pub fn save(&self, path: &Path) -> Result<(), ProgressError> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// Erase the progress saved at `path`, including its review schedule and
+    /// achievements, so the next load starts fresh. A missing file is fine.
+    pub fn reset(path: &Path) -> Result<(), ProgressError> {
+        for file in [path.to_path_buf(), path.with_extension("json.tmp")] {
+            match std::fs::remove_file(&file) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Highest level unlocked so far
+    pub fn level(&self) -> u32 {
+        self.unlocked_levels.iter().copied().max().unwrap_or(1)
+    }
+
+    /// Unlock the next level if `recent_accuracy` meets `threshold` and
+    /// every jamo introduced at the current level has been typed at least
+    /// [`MIN_JAMO_ATTEMPTS`] times. Returns whether a level was unlocked.
+    ///
+    /// The last level of the active curriculum never advances, and neither
+    /// does a level that introduces no jamo, since there is nothing to
+    /// show it was mastered.
+    pub fn try_advance_level(&mut self, recent_accuracy: f32, threshold: f32) -> bool {
+        let curriculum = curriculum::current();
+        let level = self.level();
+        if level as usize >= curriculum.len() || recent_accuracy < threshold {
+            return false;
+        }
+        let introduced = curriculum.jamo_for_level(level);
+        let attempts = lesson::jamo_attempts(self);
+        let practised = !introduced.is_empty()
+            && introduced
+                .iter()
+                .all(|j| attempts.get(j).copied().unwrap_or(0) >= MIN_JAMO_ATTEMPTS);
+        if !practised {
+            return false;
+        }
+
+        self.unlocked_levels.push(level + 1);
+        true
+    }
+
+    /// Unlock the next level once a session has passed it, counting the
+    /// session's `unsaved` keystrokes towards the jamo practice
+    /// [`Self::try_advance_level`] asks for. Returns the unlocked level.
+    pub fn level_up_after(
+        &mut self,
+        tracker: &MilestoneTracker,
+        stats: &SessionStats,
+        unsaved: &[Keystroke],
+    ) -> Option<u32> {
+        if !tracker.level_passed(stats) {
+            return None;
+        }
+        let mut practised = self.clone();
+        for keystroke in unsaved {
+            practised.record_typed(keystroke.expected, keystroke.is_correct());
+        }
+        if !practised.try_advance_level(stats.accuracy, level_threshold(self.level())) {
+            return None;
+        }
+        self.unlocked_levels = practised.unlocked_levels;
+        Some(self.level())
+    }
+
+    /// Count a character typed during a session, without rescheduling it
+    pub fn record_typed(&mut self, c: char, correct: bool) {
+        *self.per_char_attempts.entry(c).or_insert(0) += 1;
+        if !correct {
+            *self.per_char_mistakes.entry(c).or_insert(0) += 1;
+        }
+    }
+
+    /// Record a review of `c`, counting misses and rescheduling it
+    pub fn record_review(&mut self, c: char, correct: bool, now: DateTime<Utc>) {
+        *self.per_char_attempts.entry(c).or_insert(0) += 1;
+        if !correct {
+            *self.per_char_mistakes.entry(c).or_insert(0) += 1;
+        }
+        self.srs.record_review_at(c, correct, now);
+    }
+
+    /// Per-character results as CSV, one row per practised character in
+    /// character order.
+    ///
+    /// Columns are character, romanization, attempts, errors and error_rate
+    /// (0.0 - 1.0). Empty progress gives just the header.
+    pub fn to_csv(&self) -> String {
+        let mut chars: Vec<char> = self
+            .per_char_attempts
+            .keys()
+            .chain(self.per_char_mistakes.keys())
+            .copied()
+            .collect();
+        chars.sort_unstable();
+        chars.dedup();
+
+        let mut csv = String::from("character,romanization,attempts,errors,error_rate\n");
+        for c in chars {
+            let errors = self.per_char_mistakes.get(&c).copied().unwrap_or(0);
+            // Mistakes saved before attempts were tracked still count as attempts
+            let attempts = self
+                .per_char_attempts
+                .get(&c)
+                .copied()
+                .unwrap_or(0)
+                .max(errors);
+            csv.push_str(&format!(
+                "{},{},{},{},{:.3}\n",
+                c,
+                romanize::revised(&c.to_string()),
+                attempts,
+                errors,
+                errors as f32 / attempts as f32
+            ));
+        }
+        csv
+    }
+}
+
+/// A learning milestone, emitted as the payload of `progress` events
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ProgressEvent {
+    /// The learner passed a level and unlocked `level`
+    LevelUp { level: u32 },
+    /// A character introduced by a newly unlocked level
+    CharacterUnlocked { character: char },
+    /// Session accuracy reached `threshold` (0.0 - 1.0)
+    AccuracyThreshold { threshold: f32 },
+}
+
+/// Events announcing that `level` was unlocked: the level-up, then each
+/// character it introduces
+pub fn level_up_events(level: u32) -> Vec<ProgressEvent> {
+    std::iter::once(ProgressEvent::LevelUp { level })
+        .chain(
+            lesson::introduced_at(level)
+                .into_iter()
+                .map(|character| ProgressEvent::CharacterUnlocked { character }),
+        )
+        .collect()
+}
+
+/// Accuracy needed to pass a level
+pub fn level_threshold(level: u32) -> f32 {
+    LEVEL_THRESHOLDS
+        .get(level.saturating_sub(1) as usize)
+        .copied()
+        .unwrap_or(0.80)
+}
+
+/// Watches one session's statistics and reports each milestone once
+#[derive(Debug, Clone)]
+pub struct MilestoneTracker {
+    level: u32,
+    leveled_up: bool,
+    accuracy_reached: Vec<f32>,
+}
+
+impl MilestoneTracker {
+    pub fn new(level: u32) -> Self {
+        Self {
+            level,
+            leveled_up: false,
+            accuracy_reached: Vec::new(),
+        }
+    }
+
+    /// Check the latest statistics and return any accuracy milestones
+    /// newly reached
+    pub fn observe(&mut self, stats: &SessionStats) -> Vec<ProgressEvent> {
+        let mut events = Vec::new();
+        if stats.total_keystrokes < MIN_KEYSTROKES {
+            return events;
+        }
+
+        for threshold in ACCURACY_MILESTONES {
+            if stats.accuracy >= threshold && !self.accuracy_reached.contains(&threshold) {
+                self.accuracy_reached.push(threshold);
+                events.push(ProgressEvent::AccuracyThreshold { threshold });
+            }
+        }
+
+        events
+    }
+
+    /// Whether the session is accurate enough to pass its level, which
+    /// still needs [`Progress::level_up_after`] to unlock the next one.
+    /// False once [`Self::record_level_up`] is called and on the last level.
+    pub fn level_passed(&self, stats: &SessionStats) -> bool {
+        !self.leveled_up
+            && stats.total_keystrokes >= MIN_KEYSTROKES
+            && (self.level as usize) < curriculum::current().len()
+            && stats.accuracy >= level_threshold(self.level)
+    }
+
+    /// Note that the session unlocked a level, so it unlocks at most one
+    pub fn record_level_up(&mut self) {
+        self.leveled_up = true;
+    }
+}
+
+/// Flushes typed keystrokes every `keystrokes` keystrokes, or sooner once
+/// `interval` has passed since the last flush
+#[derive(Debug, Clone)]
+pub struct Autosave {
+    enabled: bool,
+    keystrokes: u32,
+    interval: Duration,
+    pending: u32,
+    last_flush: Instant,
+}
+
+impl Autosave {
+    pub fn new() -> Self {
+        Self::starting_at(Instant::now())
+    }
+
+    pub fn starting_at(now: Instant) -> Self {
+        Self {
+            enabled: true,
+            keystrokes: DEFAULT_AUTOSAVE_KEYSTROKES,
+            interval: DEFAULT_AUTOSAVE_INTERVAL,
+            pending: 0,
+            last_flush: now,
+        }
+    }
+
+    /// Turn autosave on or off and change how often it runs.
+    ///
+    /// A keystroke count of 0 leaves only the time limit, and a zero
+    /// interval only the keystroke count.
+    pub fn configure(&mut self, enabled: bool, keystrokes: u32, interval: Duration) {
+        self.enabled = enabled;
+        self.keystrokes = keystrokes;
+        self.interval = interval;
+    }
+
+    /// Note a keystroke at `now`, returning whether it is time to flush
+    pub fn record_at(&mut self, now: Instant) -> bool {
+        self.pending += 1;
+        if !self.enabled {
+            return false;
+        }
+        let by_count = self.keystrokes > 0 && self.pending >= self.keystrokes;
+        let by_time =
+            !self.interval.is_zero() && now.duration_since(self.last_flush) >= self.interval;
+        by_count || by_time
+    }
+
+    /// Whether unsaved keystrokes have waited the full interval at `now`,
+    /// for the timer that saves a session nobody is typing in
+    pub fn is_due(&self, now: Instant) -> bool {
+        self.enabled
+            && self.pending > 0
+            && !self.interval.is_zero()
+            && now.duration_since(self.last_flush) >= self.interval
+    }
+
+    /// How long from `now` until [`Self::is_due`] could next be true; the
+    /// default interval when saving by time is off
+    pub fn time_until_due(&self, now: Instant) -> Duration {
+        if !self.enabled || self.interval.is_zero() {
+            return DEFAULT_AUTOSAVE_INTERVAL;
+        }
+        self.interval
+            .saturating_sub(now.duration_since(self.last_flush))
+            .max(MIN_AUTOSAVE_WAIT)
+    }
+
+    /// Note that pending keystrokes were saved at `now`
+    pub fn flushed_at(&mut self, now: Instant) {
+        self.pending = 0;
+        self.last_flush = now;
+    }
+}
+
+impl Default for Autosave {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Get the autosave schedule shared by all sessions
+pub fn autosave() -> &'static Mutex<Autosave> {
+    AUTOSAVE.get_or_init(|| Mutex::new(Autosave::new()))
+}
+
+/// Progress read from one file and kept in memory, so frequent updates
+/// such as typed keystrokes don't reread the file each time.
+///
+/// Changes stay in memory until [`ProgressStore::save`].
+#[derive(Debug, Default)]
+pub struct ProgressStore {
+    loaded: Option<(PathBuf, Progress)>,
+}
+
+impl ProgressStore {
+    /// The progress saved at `path`, read from disk unless it is already
+    /// loaded. Loading another file drops the previous one, unsaved
+    /// changes included.
+    pub fn get(&mut self, path: &Path) -> Result<&mut Progress, ProgressError> {
+        if !matches!(&self.loaded, Some((loaded, _)) if loaded == path) {
+            self.loaded = Some((path.to_path_buf(), Progress::load(path)?));
+        }
+        Ok(&mut self.loaded.as_mut().expect("loaded above").1)
+    }
+
+    /// Write the loaded progress back to the file it came from
+    pub fn save(&self) -> Result<(), ProgressError> {
+        match &self.loaded {
+            Some((path, progress)) => progress.save(path),
+            None => Ok(()),
+        }
+    }
+
+    /// Forget the loaded progress, so the next [`Self::get`] reads the file
+    pub fn clear(&mut self) {
+        self.loaded = None;
+    }
+}
+
+/// Get the progress store; holding its lock also serializes saves
+pub fn store() -> &'static Mutex<ProgressStore> {
+    STORE.get_or_init(|| Mutex::new(ProgressStore::default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(total: usize, correct: usize) -> SessionStats {
+        SessionStats {
+            wpm: 0.0,
+            accuracy: correct as f32 / total as f32,
+            total_keystrokes: total,
+            correct_keystrokes: correct,
+        }
+    }
+
+    #[test]
+    fn level_is_passed_until_a_level_up_is_recorded() {
+        let mut tracker = MilestoneTracker::new(1);
+        assert!(!tracker.level_passed(&stats(19, 19)));
+        assert!(tracker.level_passed(&stats(20, 19)));
+
+        tracker.record_level_up();
+        assert!(!tracker.level_passed(&stats(30, 30)));
+    }
+
+    #[test]
+    fn last_curriculum_level_is_never_passed() {
+        let last = curriculum::current().len() as u32;
+        assert!(!MilestoneTracker::new(last).level_passed(&stats(20, 20)));
+    }
+
+    #[test]
+    fn no_milestones_below_minimum_keystrokes() {
+        let mut tracker = MilestoneTracker::new(1);
+        assert!(tracker.observe(&stats(5, 5)).is_empty());
+    }
+
+    #[test]
+    fn level_up_unlocks_next_level_characters() {
+        let events = level_up_events(2);
+        assert_eq!(events[0], ProgressEvent::LevelUp { level: 2 });
+        assert!(events.contains(&ProgressEvent::CharacterUnlocked { character: 'ㄹ' }));
+    }
+
+    /// Progress with every jamo of level 1 typed often enough to pass it
+    fn practised_level_one() -> Progress {
+        let mut progress = Progress::default();
+        for j in lesson::introduced_at(1) {
+            for _ in 0..MIN_JAMO_ATTEMPTS {
+                progress.record_typed(j, true);
+            }
+        }
+        progress
+    }
+
+    #[test]
+    fn accurate_session_without_jamo_practice_does_not_unlock() {
+        let tracker = MilestoneTracker::new(1);
+        let perfect = stats(20, 20);
+
+        let mut progress = Progress::default();
+        assert_eq!(progress.level_up_after(&tracker, &perfect, &[]), None);
+        assert_eq!(progress.unlocked_levels, vec![1]);
+
+        let mut progress = practised_level_one();
+        assert_eq!(progress.level_up_after(&tracker, &perfect, &[]), Some(2));
+    }
+
+    #[test]
+    fn unsaved_keystrokes_count_as_practice() {
+        let tracker = MilestoneTracker::new(1);
+        let now = Instant::now();
+        let unsaved: Vec<Keystroke> = lesson::introduced_at(1)
+            .into_iter()
+            .flat_map(|j| std::iter::repeat_n(j, MIN_JAMO_ATTEMPTS as usize))
+            .map(|j| Keystroke {
+                expected: j,
+                actual: j,
+                at: now,
+            })
+            .collect();
+
+        let mut progress = Progress::default();
+        assert_eq!(
+            progress.level_up_after(&tracker, &stats(20, 20), &unsaved),
+            Some(2)
+        );
+        // Only the level is kept; the keystrokes are saved separately
+        assert!(progress.per_char_attempts.is_empty());
+    }
+
+    #[test]
+    fn advances_at_exactly_the_threshold() {
+        let mut progress = practised_level_one();
+        assert!(progress.try_advance_level(0.9, DEFAULT_ADVANCE_THRESHOLD));
+        assert_eq!(progress.level(), 2);
+        assert_eq!(progress.unlocked_levels, vec![1, 2]);
+    }
+
+    #[test]
+    fn stays_below_the_threshold() {
+        let mut progress = practised_level_one();
+        assert!(!progress.try_advance_level(0.89, DEFAULT_ADVANCE_THRESHOLD));
+        assert_eq!(progress.level(), 1);
+    }
+
+    #[test]
+    fn unpractised_jamo_block_advancing() {
+        let mut progress = Progress::default();
+        assert!(!progress.try_advance_level(1.0, DEFAULT_ADVANCE_THRESHOLD));
+
+        // One jamo short of practised
+        let last = *lesson::introduced_at(1).last().unwrap();
+        let mut progress = practised_level_one();
+        progress
+            .per_char_attempts
+            .insert(last, MIN_JAMO_ATTEMPTS - 1);
+        assert!(!progress.try_advance_level(1.0, DEFAULT_ADVANCE_THRESHOLD));
+        assert_eq!(progress.level(), 1);
+    }
+
+    #[test]
+    fn last_curriculum_level_does_not_advance() {
+        let last = curriculum::current().len() as u32;
+        let mut progress = Progress {
+            unlocked_levels: (1..=last).collect(),
+            ..Progress::default()
+        };
+        for level in 1..=last {
+            for j in lesson::introduced_at(level) {
+                for _ in 0..MIN_JAMO_ATTEMPTS {
+                    progress.record_typed(j, true);
+                }
+            }
+        }
+
+        assert!(!progress.try_advance_level(1.0, DEFAULT_ADVANCE_THRESHOLD));
+        assert_eq!(progress.level(), last);
+    }
+
+    #[test]
+    fn csv_has_header_and_row_per_character() {
+        let mut progress = Progress::default();
+        let now = Utc::now();
+        progress.record_review('가', true, now);
+        progress.record_review('가', false, now);
+
+        assert_eq!(
+            progress.to_csv(),
+            "character,romanization,attempts,errors,error_rate\n가,ga,2,1,0.500\n"
+        );
+    }
+
+    #[test]
+    fn empty_progress_csv_is_header_only() {
+        assert_eq!(
+            Progress::default().to_csv(),
+            "character,romanization,attempts,errors,error_rate\n"
+        );
+    }
+
+    #[test]
+    fn missing_file_loads_default_progress() {
+        let path = std::env::temp_dir().join(format!("{}.json", uuid::Uuid::new_v4()));
+        let progress = Progress::load(&path).unwrap();
+        assert_eq!(progress.unlocked_levels, vec![1]);
+    }
+
+    #[test]
+    fn autosave_triggers_after_keystroke_count() {
+        let start = Instant::now();
+        let mut autosave = Autosave::starting_at(start);
+
+        for _ in 1..DEFAULT_AUTOSAVE_KEYSTROKES {
+            assert!(!autosave.record_at(start));
+        }
+        assert!(autosave.record_at(start));
+
+        autosave.flushed_at(start);
+        assert!(!autosave.record_at(start));
+    }
+
+    #[test]
+    fn autosave_triggers_after_interval() {
+        let start = Instant::now();
+        let mut autosave = Autosave::starting_at(start);
+
+        assert!(!autosave.record_at(start + Duration::from_secs(5)));
+        assert!(autosave.record_at(start + DEFAULT_AUTOSAVE_INTERVAL));
+    }
+
+    #[test]
+    fn autosave_is_due_after_interval_without_typing() {
+        let start = Instant::now();
+        let mut autosave = Autosave::starting_at(start);
+        let later = start + DEFAULT_AUTOSAVE_INTERVAL;
+        // Nothing typed, nothing to save
+        assert!(!autosave.is_due(later));
+
+        autosave.record_at(start);
+        assert!(!autosave.is_due(start));
+        assert_eq!(autosave.time_until_due(start), DEFAULT_AUTOSAVE_INTERVAL);
+        assert!(autosave.is_due(later));
+
+        autosave.flushed_at(later);
+        assert!(!autosave.is_due(later + DEFAULT_AUTOSAVE_INTERVAL));
+    }
+
+    #[test]
+    fn disabled_autosave_never_triggers() {
+        let start = Instant::now();
+        let mut autosave = Autosave::starting_at(start);
+        autosave.configure(false, 1, DEFAULT_AUTOSAVE_INTERVAL);
+
+        assert!(!autosave.record_at(start + Duration::from_secs(3600)));
+    }
+
+    #[test]
+    fn saved_progress_round_trips() {
+        let dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        let path = dir.join(PROGRESS_FILE);
+
+        let mut progress = Progress::default();
+        progress.record_review('ㄱ', false, Utc::now());
+        progress.save(&path).unwrap();
+
+        let loaded = Progress::load(&path).unwrap();
+        assert_eq!(loaded.per_char_mistakes.get(&'ㄱ'), Some(&1));
+        assert!(loaded.srs.card('ㄱ').is_some());
+        // The temporary file was renamed into place
+        assert!(!path.with_extension("json.tmp").exists());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn store_keeps_changes_until_saved() {
+        let dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        let path = dir.join(PROGRESS_FILE);
+        let mut store = ProgressStore::default();
+
+        store.get(&path).unwrap().unlocked_levels.push(2);
+        assert_eq!(store.get(&path).unwrap().unlocked_levels, vec![1, 2]);
+        assert_eq!(Progress::load(&path).unwrap().unlocked_levels, vec![1]);
+
+        store.save().unwrap();
+        assert_eq!(Progress::load(&path).unwrap().unlocked_levels, vec![1, 2]);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn reset_progress_loads_as_default() {
+        let dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        let path = dir.join(PROGRESS_FILE);
+
+        let mut progress = Progress::default();
+        progress.unlocked_levels.push(2);
+        progress.record_review('ㄱ', false, Utc::now());
+        progress.achievements.push(Achievement::Wpm50);
+        progress.save(&path).unwrap();
+
+        Progress::reset(&path).unwrap();
+        let loaded = Progress::load(&path).unwrap();
+        assert_eq!(loaded.unlocked_levels, vec![1]);
+        assert!(loaded.per_char_mistakes.is_empty());
+        assert!(loaded.srs.card('ㄱ').is_none());
+        assert!(loaded.achievements.is_empty());
+
+        // Resetting again with nothing saved is not an error
+        Progress::reset(&path).unwrap();
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,153 @@
+//! Active-recall quizzes: "Which keys type 밥?"
+//!
+//! Copilot may word the question, but the answer is always graded against
+//! the layout tables, so grading is objective and works offline. Learners
+//! can answer with key names ("q k q", "Shift+r, k") or by typing the
+//! Hangul itself.
+
+use crate::jamo;
+use crate::layout::{KeyPress, Layout, PhysicalLayout};
+use serde::Serialize;
+use uuid::Uuid;
+
+/// Words allowed between keys in an answer ("q then k and q")
+const FILLER: [&str; 4] = ["then", "and", "key", "keys"];
+
+/// A question put to the learner
+#[derive(Debug, Clone, Serialize)]
+pub struct QuizQuestion {
+    pub id: Uuid,
+    pub question: String,
+    /// What the learner is asked to type
+    pub target: String,
+}
+
+/// Outcome of checking an answer
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct QuizResult {
+    pub correct: bool,
+    /// Keys that type the target, labelled for the learner's keyboard
+    pub expected: Vec<KeyPress>,
+    pub feedback: String,
+}
+
+/// Question wording used when Copilot is unavailable
+pub fn local_question(target: &str) -> String {
+    format!("Which keys type {}?", target)
+}
+
+/// Read an answer as a key sequence, labelled as on the learner's keyboard.
+///
+/// Keys are case-insensitive letters, digits or punctuation, separated by
+/// spaces, commas, "+" or filler words. A one-word answer may run keys
+/// together ("qkq"). "Shift" applies to the key after it. Returns `None`
+/// if any word isn't a key, so prose isn't misread as keys.
+pub fn parse_keys(answer: &str) -> Option<Vec<KeyPress>> {
+    let spaced = answer.replace(['+', ','], " ");
+    let words: Vec<&str> = spaced.split_whitespace().collect();
+    let mut keys = Vec::new();
+    let mut shift = false;
+
+    for word in &words {
+        let word = word.to_lowercase();
+        if word == "shift" {
+            shift = true;
+            continue;
+        }
+        if FILLER.contains(&word.as_str()) {
+            continue;
+        }
+        if !word.chars().all(|c| c.is_ascii_graphic())
+            || (words.len() > 1 && word.chars().count() > 1)
+        {
+            return None;
+        }
+        for c in word.chars() {
+            keys.push(KeyPress { key: c, shift });
+            shift = false;
+        }
+    }
+
+    (!keys.is_empty()).then_some(keys)
+}
+
+/// Grade `answer` to "which keys type `target`" without Copilot.
+///
+/// Returns `None` when the answer is neither a key sequence nor Hangul, so
+/// the caller can fall back to asking Copilot.
+pub fn grade_locally(
+    answer: &str,
+    target: &str,
+    layout: Layout,
+    physical: PhysicalLayout,
+) -> Option<QuizResult> {
+    let expected = physical.translate_all(&layout.keystrokes_for_text(target));
+    let keys = |keys: &[KeyPress]| {
+        keys.iter()
+            .map(|k| k.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    let answer = jamo::normalize(answer.trim());
+    let correct = if answer
+        .chars()
+        .any(|c| jamo::script_of(c) == jamo::Script::Hangul)
+    {
+        // Typed on a Korean IME, so the keys came out as Hangul
+        answer == target
+    } else {
+        parse_keys(&answer)? == expected
+    };
+
+    let feedback = if correct {
+        format!("Correct! {} is typed with {}.", target, keys(&expected))
+    } else {
+        format!("Not quite. {} is typed with {}.", target, keys(&expected))
+    };
+    Some(QuizResult {
+        correct,
+        expected,
+        feedback,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grade(answer: &str) -> Option<QuizResult> {
+        grade_locally(answer, "밥", Layout::TwoBulsik, PhysicalLayout::Qwerty)
+    }
+
+    #[test]
+    fn correct_keys_are_graded_without_copilot() {
+        assert!(grade("q k q").unwrap().correct);
+        assert!(grade("QKQ").unwrap().correct);
+        assert!(grade("q, then k and q").unwrap().correct);
+    }
+
+    #[test]
+    fn typing_the_hangul_counts_as_correct() {
+        assert!(grade("밥").unwrap().correct);
+        assert!(!grade("법").unwrap().correct);
+    }
+
+    #[test]
+    fn wrong_keys_get_the_answer() {
+        let result = grade("q j q").unwrap();
+        assert!(!result.correct);
+        assert_eq!(result.feedback, "Not quite. 밥 is typed with q, k, q.");
+    }
+
+    #[test]
+    fn shift_applies_to_the_next_key() {
+        let result = grade_locally("Shift+r k", "까", Layout::TwoBulsik, PhysicalLayout::Qwerty);
+        assert!(result.unwrap().correct);
+    }
+
+    #[test]
+    fn free_text_is_left_for_copilot() {
+        assert_eq!(grade("the b key twice with a vowel"), None);
+    }
+}
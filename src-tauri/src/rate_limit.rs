@@ -0,0 +1,111 @@
+//! A token-bucket rate limiter.
+//!
+//! Caps how many Copilot requests can be sent in a burst, so a frontend bug
+//! that fires requests in a loop can't use up the learner's Copilot quota.
+//! The bucket holds up to `capacity` tokens and refills evenly over `window`.
+
+use std::time::{Duration, Instant};
+
+/// Allows `capacity` requests per `window`, refilling continuously
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    capacity: u32,
+    window: Duration,
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    /// Create a full bucket. A capacity of 0 disables limiting.
+    pub fn new(capacity: u32, window: Duration) -> Self {
+        Self::starting_at(capacity, window, Instant::now())
+    }
+
+    pub fn starting_at(capacity: u32, window: Duration, now: Instant) -> Self {
+        Self {
+            capacity,
+            window,
+            tokens: capacity as f64,
+            refilled_at: now,
+        }
+    }
+
+    /// Take a token now, or return how long until one is available
+    pub fn try_acquire(&mut self) -> Result<(), Duration> {
+        self.try_acquire_at(Instant::now())
+    }
+
+    /// Take a token at `now`, or return how long until one is available
+    pub fn try_acquire_at(&mut self, now: Instant) -> Result<(), Duration> {
+        if self.capacity == 0 || self.window.is_zero() {
+            return Ok(());
+        }
+
+        self.refill(now);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            return Ok(());
+        }
+        Err(self.per_token().mul_f64(1.0 - self.tokens))
+    }
+
+    /// Change the limit, starting again with a full bucket
+    pub fn reconfigure(&mut self, capacity: u32, window: Duration) {
+        *self = Self::new(capacity, window);
+    }
+
+    pub fn capacity(&self) -> u32 {
+        self.capacity
+    }
+
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    /// Time for one token to refill
+    fn per_token(&self) -> Duration {
+        self.window / self.capacity
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.refilled_at);
+        let gained = elapsed.as_secs_f64() / self.per_token().as_secs_f64();
+        self.tokens = (self.tokens + gained).min(self.capacity as f64);
+        self.refilled_at = now;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_once_burst_is_spent() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::starting_at(2, Duration::from_secs(10), start);
+
+        assert!(bucket.try_acquire_at(start).is_ok());
+        assert!(bucket.try_acquire_at(start).is_ok());
+        assert_eq!(bucket.try_acquire_at(start), Err(Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn refills_over_the_window() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::starting_at(2, Duration::from_secs(10), start);
+        bucket.try_acquire_at(start).unwrap();
+        bucket.try_acquire_at(start).unwrap();
+
+        let later = start + Duration::from_secs(5);
+        assert!(bucket.try_acquire_at(later).is_ok());
+        assert!(bucket.try_acquire_at(later).is_err());
+    }
+
+    #[test]
+    fn zero_capacity_never_limits() {
+        let mut bucket = TokenBucket::new(0, Duration::from_secs(10));
+        for _ in 0..100 {
+            assert!(bucket.try_acquire().is_ok());
+        }
+    }
+}
@@ -0,0 +1,674 @@
+//! Romanization of Hangul text.
+//!
+//! Implements the Revised Romanization of Korean, including the common
+//! sound changes at syllable boundaries (liaison, nasalization, ㄹ
+//! assimilation, aspiration and palatalization), so pronunciation can be
+//! shown instantly without asking Copilot. Revised romanization can also be
+//! read back into Hangul, for learners who think in romaja first.
+
+use crate::jamo;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// A piece of input text: either a decomposed syllable or a passthrough char
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token {
+    Syllable {
+        cho: char,
+        jung: char,
+        jong: Option<char>,
+    },
+    Other(char),
+}
+
+/// Split text into decomposed syllables and passthrough characters
+fn tokenize(text: &str) -> Vec<Token> {
+    text.chars()
+        .map(|c| match jamo::decompose(c) {
+            Some((cho, jung, jong)) => Token::Syllable { cho, jung, jong },
+            None => Token::Other(c),
+        })
+        .collect()
+}
+
+/// Representative sound of a final consonant when nothing follows it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Coda {
+    K,
+    N,
+    T,
+    L,
+    M,
+    P,
+    Ng,
+}
+
+impl Coda {
+    fn of(jong: char) -> Coda {
+        match jong {
+            'ㄱ' | 'ㄲ' | 'ㅋ' | 'ㄳ' | 'ㄺ' => Coda::K,
+            'ㄴ' | 'ㄵ' | 'ㄶ' => Coda::N,
+            'ㄹ' | 'ㄼ' | 'ㄽ' | 'ㄾ' | 'ㅀ' => Coda::L,
+            'ㅁ' | 'ㄻ' => Coda::M,
+            'ㅂ' | 'ㅍ' | 'ㅄ' | 'ㄿ' => Coda::P,
+            'ㅇ' => Coda::Ng,
+            // ㄷ ㅅ ㅆ ㅈ ㅊ ㅌ ㅎ
+            _ => Coda::T,
+        }
+    }
+
+    fn revised(self) -> &'static str {
+        match self {
+            Coda::K => "k",
+            Coda::N => "n",
+            Coda::T => "t",
+            Coda::L => "l",
+            Coda::M => "m",
+            Coda::P => "p",
+            Coda::Ng => "ng",
+        }
+    }
+}
+
+/// Revised romanization of an initial consonant before a vowel
+fn initial_revised(cho: char) -> &'static str {
+    match cho {
+        'ㄱ' => "g",
+        'ㄲ' => "kk",
+        'ㄴ' => "n",
+        'ㄷ' => "d",
+        'ㄸ' => "tt",
+        'ㄹ' => "r",
+        'ㅁ' => "m",
+        'ㅂ' => "b",
+        'ㅃ' => "pp",
+        'ㅅ' => "s",
+        'ㅆ' => "ss",
+        'ㅈ' => "j",
+        'ㅉ' => "jj",
+        'ㅊ' => "ch",
+        'ㅋ' => "k",
+        'ㅌ' => "t",
+        'ㅍ' => "p",
+        'ㅎ' => "h",
+        // ㅇ is silent in initial position
+        _ => "",
+    }
+}
+
+/// Revised romanization of a medial vowel
+fn medial_revised(jung: char) -> &'static str {
+    match jung {
+        'ㅏ' => "a",
+        'ㅐ' => "ae",
+        'ㅑ' => "ya",
+        'ㅒ' => "yae",
+        'ㅓ' => "eo",
+        'ㅔ' => "e",
+        'ㅕ' => "yeo",
+        'ㅖ' => "ye",
+        'ㅗ' => "o",
+        'ㅘ' => "wa",
+        'ㅙ' => "wae",
+        'ㅚ' => "oe",
+        'ㅛ' => "yo",
+        'ㅜ' => "u",
+        'ㅝ' => "wo",
+        'ㅞ' => "we",
+        'ㅟ' => "wi",
+        'ㅠ' => "yu",
+        'ㅡ' => "eu",
+        'ㅢ' => "ui",
+        _ => "i",
+    }
+}
+
+/// McCune-Reischauer romanization of a medial vowel
+fn medial_mr(jung: char) -> &'static str {
+    match jung {
+        'ㅓ' => "ŏ",
+        'ㅕ' => "yŏ",
+        'ㅝ' => "wŏ",
+        'ㅡ' => "ŭ",
+        'ㅢ' => "ŭi",
+        other => medial_revised(other),
+    }
+}
+
+/// McCune-Reischauer romanization of an initial consonant before a vowel.
+///
+/// Plain stops are voiced between voiced sounds (vowels and ㄴ ㄹ ㅁ ㅇ)
+/// and voiceless elsewhere; aspirated consonants take an apostrophe.
+fn initial_mr(cho: char, before: Before) -> &'static str {
+    let voiced = matches!(
+        before,
+        Before::Vowel | Before::Coda(Coda::N | Coda::L | Coda::M | Coda::Ng)
+    );
+
+    match cho {
+        'ㄱ' if voiced => "g",
+        'ㄱ' => "k",
+        'ㄷ' if voiced => "d",
+        'ㄷ' => "t",
+        'ㅂ' if voiced => "b",
+        'ㅂ' => "p",
+        'ㅈ' if voiced => "j",
+        'ㅈ' => "ch",
+        'ㅉ' => "tch",
+        'ㅊ' => "ch'",
+        'ㅋ' => "k'",
+        'ㅌ' => "t'",
+        'ㅍ' => "p'",
+        'ㄹ' if before == Before::Coda(Coda::L) => "l",
+        other => initial_revised(other),
+    }
+}
+
+/// Aspirated counterpart of a plain stop merged with ㅎ
+fn aspirated(c: char) -> Option<char> {
+    match c {
+        'ㄱ' => Some('ㅋ'),
+        'ㄷ' => Some('ㅌ'),
+        'ㅂ' => Some('ㅍ'),
+        'ㅈ' => Some('ㅊ'),
+        _ => None,
+    }
+}
+
+/// What precedes a syllable's initial sound
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Before {
+    WordStart,
+    Vowel,
+    Coda(Coda),
+}
+
+/// Resolve the sounds on either side of a syllable boundary.
+///
+/// Returns the final sound of the first syllable, if any, and the initial
+/// consonant actually pronounced in the second, after applying the
+/// sound-change rules. Both romanization styles render from this.
+fn boundary(jong: Option<char>, next_cho: char, next_jung: char) -> (Option<Coda>, char) {
+    let Some(jong) = jong else {
+        return (None, next_cho);
+    };
+
+    // Split compound finals into the part that stays and the part that can
+    // move to the next syllable
+    let (stay, moving) = match jong {
+        'ㄳ' => (Some('ㄱ'), 'ㅅ'),
+        'ㄵ' => (Some('ㄴ'), 'ㅈ'),
+        'ㄶ' => (Some('ㄴ'), 'ㅎ'),
+        'ㄺ' => (Some('ㄹ'), 'ㄱ'),
+        'ㄻ' => (Some('ㄹ'), 'ㅁ'),
+        'ㄼ' => (Some('ㄹ'), 'ㅂ'),
+        'ㄽ' => (Some('ㄹ'), 'ㅅ'),
+        'ㄾ' => (Some('ㄹ'), 'ㅌ'),
+        'ㄿ' => (Some('ㄹ'), 'ㅍ'),
+        'ㅀ' => (Some('ㄹ'), 'ㅎ'),
+        'ㅄ' => (Some('ㅂ'), 'ㅅ'),
+        single => (None, single),
+    };
+    let stay_coda = stay.map(Coda::of);
+
+    match next_cho {
+        // Liaison: the final carries over into the vowel-initial syllable
+        'ㅇ' => {
+            let onset = match moving {
+                'ㅇ' => return (Some(Coda::Ng), 'ㅇ'),
+                // ㅀ drops its ㅎ and the ㄹ moves over
+                'ㅎ' if stay == Some('ㄹ') => return (None, 'ㄹ'),
+                'ㅎ' => 'ㅇ',
+                // Palatalization: ㄷ/ㅌ before ㅣ
+                'ㄷ' if next_jung == 'ㅣ' => 'ㅈ',
+                'ㅌ' if next_jung == 'ㅣ' => 'ㅊ',
+                other => other,
+            };
+            (stay_coda, onset)
+        }
+        // Aspiration: plain stop followed by ㅎ
+        'ㅎ' => match aspirated(moving) {
+            Some(onset) => (stay_coda, onset),
+            None => (Some(Coda::of(jong)), 'ㅎ'),
+        },
+        // Aspiration: ㅎ followed by a plain stop
+        'ㄱ' | 'ㄷ' | 'ㅈ' if moving == 'ㅎ' => {
+            (stay_coda, aspirated(next_cho).unwrap_or(next_cho))
+        }
+        // Nasalization before ㄴ/ㅁ
+        'ㄴ' | 'ㅁ' => {
+            let coda = match Coda::of(jong) {
+                Coda::K => Coda::Ng,
+                Coda::T => Coda::N,
+                Coda::P => Coda::M,
+                Coda::L if next_cho == 'ㄴ' => return (Some(Coda::L), 'ㄹ'),
+                other => other,
+            };
+            (Some(coda), next_cho)
+        }
+        // ㄹ assimilation
+        'ㄹ' => match Coda::of(jong) {
+            Coda::N | Coda::L => (Some(Coda::L), 'ㄹ'),
+            Coda::K => (Some(Coda::Ng), 'ㄴ'),
+            Coda::T => (Some(Coda::N), 'ㄴ'),
+            Coda::P => (Some(Coda::M), 'ㄴ'),
+            other => (Some(other), 'ㄴ'),
+        },
+        _ => (Some(Coda::of(jong)), next_cho),
+    }
+}
+
+/// Romanization system
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Style {
+    /// Revised Romanization of Korean, the official South Korean system
+    #[default]
+    Revised,
+    /// McCune-Reischauer, common in academic and older texts
+    McCuneReischauer,
+}
+
+impl Style {
+    fn initial(self, cho: char, before: Before) -> &'static str {
+        match self {
+            // ㄹ after an ㄹ final doubles as "ll"
+            Style::Revised if cho == 'ㄹ' && before == Before::Coda(Coda::L) => "l",
+            Style::Revised => initial_revised(cho),
+            Style::McCuneReischauer => initial_mr(cho, before),
+        }
+    }
+
+    fn medial(self, jung: char) -> &'static str {
+        match self {
+            Style::Revised => medial_revised(jung),
+            Style::McCuneReischauer => medial_mr(jung),
+        }
+    }
+
+    fn coda(self, coda: Coda, next_cho: Option<char>) -> &'static str {
+        match (self, coda, next_cho) {
+            // MR writes ㄹ before ㅎ as "r" (발해 → parhae)
+            (Style::McCuneReischauer, Coda::L, Some('ㅎ')) => "r",
+            // MR separates n + g from ng with an apostrophe (한국 → han'guk)
+            (Style::McCuneReischauer, Coda::N, Some('ㄱ')) => "n'",
+            _ => coda.revised(),
+        }
+    }
+}
+
+/// Place names McCune-Reischauer writes capitalized (부산 → Pusan)
+const PLACE_NAMES: [&str; 12] = [
+    "한국", "조선", "서울", "부산", "대구", "인천", "광주", "대전", "울산", "제주", "평양", "개성",
+];
+
+/// Uppercase the first letter written at or after `start`
+fn capitalize_from(out: &mut String, start: usize) {
+    if let Some(first) = out[start..].chars().next() {
+        let upper: String = first.to_uppercase().collect();
+        out.replace_range(start..start + first.len_utf8(), &upper);
+    }
+}
+
+/// Romanize Hangul text in the given style.
+///
+/// Sound changes are applied between adjacent syllables of the same word.
+/// Characters that are not precomposed syllables (spaces, punctuation,
+/// Latin letters) are passed through unchanged and break the word.
+/// McCune-Reischauer capitalizes words that are well-known place names.
+pub fn romanize(text: &str, style: Style) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let tokens = tokenize(text);
+    let mut out = String::new();
+    // Initial sound of the current syllable as resolved at the last boundary
+    let mut onset = None;
+    // Where the current word starts, in `tokens` and in `out`
+    let mut word_start = (0, 0);
+
+    for (i, token) in tokens.iter().enumerate() {
+        let (cho, jung, jong) = match *token {
+            Token::Syllable { cho, jung, jong } => (cho, jung, jong),
+            Token::Other(c) => {
+                out.push(c);
+                onset = None;
+                continue;
+            }
+        };
+
+        let (cho, before) = onset.take().unwrap_or((cho, Before::WordStart));
+        if before == Before::WordStart {
+            word_start = (i, out.len());
+        }
+        out.push_str(style.initial(cho, before));
+        out.push_str(style.medial(jung));
+
+        match tokens.get(i + 1) {
+            Some(Token::Syllable {
+                cho: next_cho,
+                jung: next_jung,
+                ..
+            }) => {
+                let (coda, next_onset) = boundary(jong, *next_cho, *next_jung);
+                if let Some(coda) = coda {
+                    out.push_str(style.coda(coda, Some(next_onset)));
+                }
+                onset = Some((next_onset, coda.map_or(Before::Vowel, Before::Coda)));
+            }
+            _ => {
+                if let Some(jong) = jong {
+                    out.push_str(style.coda(Coda::of(jong), None));
+                }
+                let word: String = chars[word_start.0..=i].iter().collect();
+                if style == Style::McCuneReischauer && PLACE_NAMES.contains(&word.as_str()) {
+                    capitalize_from(&mut out, word_start.1);
+                }
+            }
+        }
+    }
+
+    out
+}
+
+/// Romanize Hangul text using the Revised Romanization of Korean
+pub fn revised(text: &str) -> String {
+    romanize(text, Style::Revised)
+}
+
+/// Romanize what a learner actually typed, including half-typed syllables.
+///
+/// Like [`romanize`], but a lone jamo (가ㄴ, or a vowel typed without its
+/// consonant) is romanized by its own sound instead of passed through, so
+/// mistake feedback can say "you typed gan". A lone consonant reads as an
+/// initial, or as a final when it can't start a syllable (ㅇ, ㄳ).
+pub fn romanize_lenient(text: &str, style: Style) -> String {
+    let mut out = String::new();
+    let mut word = String::new();
+
+    for c in jamo::normalize(text).chars() {
+        let sound = if jamo::is_vowel(c) {
+            style.medial(c)
+        } else if jamo::is_consonant(c) {
+            match style.initial(c, Before::WordStart) {
+                "" => Coda::of(c).revised(),
+                initial => initial,
+            }
+        } else {
+            word.push(c);
+            continue;
+        };
+        // A lone jamo ends the word before it, as in `romanize`
+        out.push_str(&romanize(&word, style));
+        word.clear();
+        out.push_str(sound);
+    }
+
+    out.push_str(&romanize(&word, style));
+    out
+}
+
+/// Romanize Hangul text using McCune-Reischauer.
+///
+/// Output is lowercase apart from well-known place names (Pusan); other
+/// proper nouns are left to the caller.
+pub fn mccune_reischauer(text: &str) -> String {
+    romanize(text, Style::McCuneReischauer)
+}
+
+/// Revised romanization of initial consonants; ㅇ is silent and written ""
+const ROMAJA_INITIALS: [(&str, char); 19] = [
+    ("kk", 'ㄲ'),
+    ("tt", 'ㄸ'),
+    ("pp", 'ㅃ'),
+    ("ss", 'ㅆ'),
+    ("jj", 'ㅉ'),
+    ("ch", 'ㅊ'),
+    ("g", 'ㄱ'),
+    ("n", 'ㄴ'),
+    ("d", 'ㄷ'),
+    ("r", 'ㄹ'),
+    ("m", 'ㅁ'),
+    ("b", 'ㅂ'),
+    ("s", 'ㅅ'),
+    ("j", 'ㅈ'),
+    ("k", 'ㅋ'),
+    ("t", 'ㅌ'),
+    ("p", 'ㅍ'),
+    ("h", 'ㅎ'),
+    ("", 'ㅇ'),
+];
+
+/// Revised romanization of vowels, longest first so "eo" reads as ㅓ
+/// rather than ㅔ followed by ㅗ
+const ROMAJA_VOWELS: [(&str, char); 21] = [
+    ("yae", 'ㅒ'),
+    ("yeo", 'ㅕ'),
+    ("wae", 'ㅙ'),
+    ("ae", 'ㅐ'),
+    ("ya", 'ㅑ'),
+    ("eo", 'ㅓ'),
+    ("ye", 'ㅖ'),
+    ("wa", 'ㅘ'),
+    ("oe", 'ㅚ'),
+    ("yo", 'ㅛ'),
+    ("wo", 'ㅝ'),
+    ("we", 'ㅞ'),
+    ("wi", 'ㅟ'),
+    ("yu", 'ㅠ'),
+    ("eu", 'ㅡ'),
+    ("ui", 'ㅢ'),
+    ("a", 'ㅏ'),
+    ("e", 'ㅔ'),
+    ("o", 'ㅗ'),
+    ("u", 'ㅜ'),
+    ("i", 'ㅣ'),
+];
+
+/// Finals as written in revised romanization, which only uses the seven
+/// representative sounds. "ng" comes last so "ng" between vowels is read
+/// as ㄴ + ㄱ first
+const ROMAJA_FINALS: [(&str, char); 7] = [
+    ("k", 'ㄱ'),
+    ("n", 'ㄴ'),
+    ("t", 'ㄷ'),
+    ("l", 'ㄹ'),
+    ("m", 'ㅁ'),
+    ("p", 'ㅂ'),
+    ("ng", 'ㅇ'),
+];
+
+/// Parse one hyphen-free run of romaja into syllables.
+///
+/// Tries no final first, so a consonant between vowels starts the next
+/// syllable when it can (maximal onset): "hangeul" reads as 한글, not 항을.
+fn parse_romaja(input: &str) -> Option<Vec<char>> {
+    let mut syllables = Vec::new();
+    parse_romaja_from(input, &mut HashSet::new(), &mut syllables).then_some(syllables)
+}
+
+/// Backtracking step of [`parse_romaja`], appending to `out`.
+///
+/// `dead_ends` remembers the byte offsets whose remaining input can't be
+/// parsed, so each offset is only explored once and the search stays
+/// polynomial however many ways the input can be split.
+fn parse_romaja_from(input: &str, dead_ends: &mut HashSet<usize>, out: &mut Vec<char>) -> bool {
+    if input.is_empty() {
+        return true;
+    }
+    if dead_ends.contains(&input.len()) {
+        return false;
+    }
+    for (initial, cho) in ROMAJA_INITIALS {
+        let Some(rest) = input.strip_prefix(initial) else {
+            continue;
+        };
+        for (vowel, jung) in ROMAJA_VOWELS {
+            let Some(rest) = rest.strip_prefix(vowel) else {
+                continue;
+            };
+            let finals = std::iter::once(("", None))
+                .chain(ROMAJA_FINALS.iter().map(|&(text, jong)| (text, Some(jong))));
+            for (text, jong) in finals {
+                let Some(rest) = rest.strip_prefix(text) else {
+                    continue;
+                };
+                let Some(syllable) = jamo::compose(cho, jung, jong) else {
+                    continue;
+                };
+                out.push(syllable);
+                if parse_romaja_from(rest, dead_ends, out) {
+                    return true;
+                }
+                out.pop();
+            }
+        }
+    }
+    // Keyed by the remaining length, which identifies the offset within
+    // the original run
+    dead_ends.insert(input.len());
+    false
+}
+
+/// Read revised romanization back into Hangul ("hanguk" → 한국).
+///
+/// Words are separated by whitespace; a hyphen marks a syllable boundary
+/// where the reading would otherwise be ambiguous (jung-ang → 중앙).
+/// Sound changes are not undone, so the result spells the pronunciation:
+/// "gamsahamnida" gives 감사함니다. Returns `None` if any part of the input
+/// isn't valid romaja.
+pub fn romaja_to_hangul(input: &str) -> Option<String> {
+    let words: Vec<String> = input
+        .split_whitespace()
+        .map(|word| {
+            let word = word.to_ascii_lowercase();
+            let mut syllables = String::new();
+            for part in word.split('-') {
+                if part.is_empty() {
+                    return None;
+                }
+                syllables.extend(parse_romaja(part)?);
+            }
+            Some(syllables)
+        })
+        .collect::<Option<_>>()?;
+
+    (!words.is_empty()).then(|| words.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn romanizes_simple_word() {
+        assert_eq!(revised("한국"), "hanguk");
+    }
+
+    #[test]
+    fn assimilates_n_before_r() {
+        assert_eq!(revised("신라"), "silla");
+    }
+
+    #[test]
+    fn carries_final_into_vowel_syllable() {
+        assert_eq!(revised("한국어"), "hangugeo");
+        assert_eq!(revised("좋아요"), "joayo");
+        assert_eq!(revised("닭이"), "dalgi");
+    }
+
+    #[test]
+    fn nasalizes_stops_before_nasals() {
+        assert_eq!(revised("감사합니다"), "gamsahamnida");
+        assert_eq!(revised("국물"), "gungmul");
+    }
+
+    #[test]
+    fn assimilates_r_after_stops() {
+        assert_eq!(revised("독립"), "dongnip");
+        assert_eq!(revised("종로"), "jongno");
+    }
+
+    #[test]
+    fn aspirates_around_h() {
+        assert_eq!(revised("좋고"), "joko");
+    }
+
+    #[test]
+    fn palatalizes_before_i() {
+        assert_eq!(revised("같이"), "gachi");
+    }
+
+    #[test]
+    fn lenient_romanizes_orphan_jamo() {
+        assert_eq!(romanize_lenient("가ㄴ", Style::Revised), "gan");
+        assert_eq!(romanize_lenient("ㅏ", Style::Revised), "a");
+        assert_eq!(romanize_lenient("가ㅇ", Style::Revised), "gang");
+        // Complete words keep their sound changes
+        assert_eq!(romanize_lenient("한국ㅁ", Style::Revised), "hangukm");
+    }
+
+    #[test]
+    fn keeps_spaces_between_words() {
+        assert_eq!(revised("안녕 하세요"), "annyeong haseyo");
+    }
+
+    #[test]
+    fn mccune_reischauer_devoices_word_initial_stops() {
+        assert_eq!(mccune_reischauer("부산"), "Pusan");
+        assert_eq!(mccune_reischauer("부산에 가요"), "pusane kayo");
+    }
+
+    #[test]
+    fn mccune_reischauer_capitalizes_place_names() {
+        assert_eq!(mccune_reischauer("서울 부산"), "Sŏul Pusan");
+        assert_eq!(revised("부산"), "busan");
+    }
+
+    #[test]
+    fn mccune_reischauer_applies_sound_changes() {
+        assert_eq!(mccune_reischauer("독립"), "tongnip");
+    }
+
+    #[test]
+    fn mccune_reischauer_marks_breves_and_n_g_boundary() {
+        assert_eq!(mccune_reischauer("한국어"), "han'gugŏ");
+        assert_eq!(mccune_reischauer("한글"), "han'gŭl");
+    }
+
+    #[test]
+    fn reads_romaja_back_into_hangul() {
+        assert_eq!(romaja_to_hangul("hanguk").as_deref(), Some("한국"));
+        assert_eq!(
+            romaja_to_hangul("Annyeong haseyo").as_deref(),
+            Some("안녕 하세요")
+        );
+        assert_eq!(romaja_to_hangul("hangeul").as_deref(), Some("한글"));
+        assert_eq!(romaja_to_hangul("jung-ang").as_deref(), Some("중앙"));
+    }
+
+    #[test]
+    fn eo_reads_as_a_single_vowel() {
+        assert_eq!(romaja_to_hangul("eomeoni").as_deref(), Some("어머니"));
+    }
+
+    #[test]
+    fn rejects_input_that_is_not_romaja() {
+        assert_eq!(romaja_to_hangul("xyz"), None);
+        assert_eq!(romaja_to_hangul("hanguk!"), None);
+        assert_eq!(romaja_to_hangul("  "), None);
+    }
+
+    #[test]
+    fn long_unparsable_input_fails_quickly() {
+        // Every "ang" splits two ways, which used to make this exponential
+        let input = format!("{}q", "ang".repeat(200));
+        let start = std::time::Instant::now();
+        assert_eq!(romaja_to_hangul(&input), None);
+        assert!(start.elapsed() < std::time::Duration::from_secs(1));
+    }
+
+    #[test]
+    fn mccune_reischauer_marks_aspiration() {
+        assert_eq!(mccune_reischauer("김치"), "kimch'i");
+        assert_eq!(mccune_reischauer("발해"), "parhae");
+    }
+}
@@ -0,0 +1,166 @@
+//! Spaced-repetition scheduling for weak characters.
+//!
+//! A simplified SM-2 scheduler: each character carries an ease factor and
+//! a review interval. Correct reviews push the next review further out;
+//! misses bring the character back within minutes.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Ease factor given to a character on its first review
+const INITIAL_EASE: f32 = 2.5;
+
+/// Ease factor never drops below this, so intervals keep growing
+const MIN_EASE: f32 = 1.3;
+
+/// Longest gap between reviews; without it a long run of correct answers
+/// grows the interval past what a date can hold
+const MAX_INTERVAL_DAYS: f32 = 365.0;
+
+/// How soon a missed character is shown again
+const RELEARN_INTERVAL: Duration = Duration::minutes(10);
+
+/// Review state of one character
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Card {
+    pub ease: f32,
+    /// Days until the next review after a correct answer
+    pub interval_days: f32,
+    /// Correct reviews in a row
+    pub repetitions: u32,
+    pub due: DateTime<Utc>,
+}
+
+/// Tracks when each practised character should be reviewed next
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Scheduler {
+    cards: HashMap<char, Card>,
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Characters due for review at `now`, most overdue first
+    pub fn due_characters(&self, now: DateTime<Utc>) -> Vec<char> {
+        let mut due: Vec<(&char, &Card)> = self
+            .cards
+            .iter()
+            .filter(|(_, card)| card.due <= now)
+            .collect();
+        due.sort_by_key(|(c, card)| (card.due, **c));
+        due.into_iter().map(|(c, _)| *c).collect()
+    }
+
+    /// Record a review made now
+    pub fn record_review(&mut self, c: char, correct: bool) {
+        self.record_review_at(c, correct, Utc::now());
+    }
+
+    /// Record a review made at `now`, rescheduling the character.
+    ///
+    /// Correct answers follow the SM-2 interval sequence (1 day, 6 days,
+    /// then the previous interval times the ease), up to a year. A miss
+    /// resets the sequence, lowers the ease and makes the character due
+    /// again soon.
+    pub fn record_review_at(&mut self, c: char, correct: bool, now: DateTime<Utc>) {
+        let card = self.cards.entry(c).or_insert_with(|| Card {
+            ease: INITIAL_EASE,
+            interval_days: 0.0,
+            repetitions: 0,
+            due: now,
+        });
+
+        if correct {
+            card.repetitions += 1;
+            card.interval_days = match card.repetitions {
+                1 => 1.0,
+                2 => 6.0,
+                _ => (card.interval_days * card.ease).min(MAX_INTERVAL_DAYS),
+            };
+            card.ease += 0.1;
+            let interval = Duration::seconds((card.interval_days * 86_400.0) as i64);
+            card.due = now
+                .checked_add_signed(interval)
+                .unwrap_or(DateTime::<Utc>::MAX_UTC);
+        } else {
+            card.repetitions = 0;
+            card.interval_days = 0.0;
+            card.ease = (card.ease - 0.2).max(MIN_EASE);
+            card.due = now + RELEARN_INTERVAL;
+        }
+    }
+
+    /// Every character that has been reviewed at least once
+    pub fn characters(&self) -> impl Iterator<Item = char> + '_ {
+        self.cards.keys().copied()
+    }
+
+    /// Review state for a character, if it has been reviewed
+    pub fn card(&self, c: char) -> Option<&Card> {
+        self.cards.get(&c)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missed_character_is_due_before_correct_one() {
+        let now = Utc::now();
+        let mut scheduler = Scheduler::new();
+        scheduler.record_review_at('ㄱ', false, now);
+        scheduler.record_review_at('ㄴ', true, now);
+
+        let due = scheduler.due_characters(now + Duration::hours(1));
+        assert_eq!(due, vec!['ㄱ']);
+        assert!(scheduler.card('ㄱ').unwrap().due < scheduler.card('ㄴ').unwrap().due);
+    }
+
+    #[test]
+    fn correct_reviews_lengthen_the_interval() {
+        let now = Utc::now();
+        let mut scheduler = Scheduler::new();
+        let intervals: Vec<f32> = (0..3)
+            .map(|_| {
+                scheduler.record_review_at('가', true, now);
+                scheduler.card('가').unwrap().interval_days
+            })
+            .collect();
+
+        assert_eq!(intervals[0], 1.0);
+        assert_eq!(intervals[1], 6.0);
+        assert!(intervals[2] > 6.0);
+    }
+
+    #[test]
+    fn long_streak_stays_within_a_year() {
+        let now = Utc::now();
+        let mut scheduler = Scheduler::new();
+        for _ in 0..30 {
+            scheduler.record_review_at('나', true, now);
+        }
+
+        let card = scheduler.card('나').unwrap();
+        assert_eq!(card.interval_days, MAX_INTERVAL_DAYS);
+        assert!(card.due <= now + Duration::days(366));
+    }
+
+    #[test]
+    fn ease_never_drops_below_minimum() {
+        let now = Utc::now();
+        let mut scheduler = Scheduler::new();
+        for _ in 0..20 {
+            scheduler.record_review_at('ㅎ', false, now);
+        }
+        assert_eq!(scheduler.card('ㅎ').unwrap().ease, MIN_EASE);
+    }
+
+    #[test]
+    fn nothing_is_due_before_any_review() {
+        assert!(Scheduler::new().due_characters(Utc::now()).is_empty());
+    }
+}
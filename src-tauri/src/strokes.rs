@@ -0,0 +1,229 @@
+//! Stroke order for the basic jamo.
+//!
+//! Each stroke is a polyline in a unit box, x to the right and y downward,
+//! so the frontend can scale it to any size and animate it point by point.
+//! Only the 24 basic jamo are covered; compound jamo such as ㄲ or ㅘ are
+//! written as their parts.
+
+use serde::Serialize;
+
+/// One pen stroke, from where the pen goes down to where it lifts
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Stroke {
+    pub points: Vec<(f32, f32)>,
+}
+
+type Path = &'static [(f32, f32)];
+
+/// A circle drawn counter-clockwise from the top, as ㅇ is written
+const CIRCLE: Path = &[
+    (0.5, 0.18),
+    (0.27, 0.27),
+    (0.18, 0.5),
+    (0.27, 0.73),
+    (0.5, 0.82),
+    (0.73, 0.73),
+    (0.82, 0.5),
+    (0.73, 0.27),
+    (0.5, 0.18),
+];
+
+/// The smaller circle under the bar of ㅎ
+const SMALL_CIRCLE: Path = &[
+    (0.5, 0.47),
+    (0.36, 0.52),
+    (0.3, 0.65),
+    (0.36, 0.78),
+    (0.5, 0.83),
+    (0.64, 0.78),
+    (0.7, 0.65),
+    (0.64, 0.52),
+    (0.5, 0.47),
+];
+
+/// Strokes of each basic jamo, in writing order
+const STROKES: [(char, &[Path]); 24] = [
+    ('ㄱ', &[&[(0.2, 0.2), (0.8, 0.2), (0.8, 0.85)]]),
+    ('ㄴ', &[&[(0.2, 0.15), (0.2, 0.8), (0.85, 0.8)]]),
+    (
+        'ㄷ',
+        &[
+            &[(0.2, 0.2), (0.8, 0.2)],
+            &[(0.2, 0.2), (0.2, 0.8), (0.85, 0.8)],
+        ],
+    ),
+    (
+        'ㄹ',
+        &[
+            &[(0.2, 0.15), (0.8, 0.15), (0.8, 0.45)],
+            &[(0.2, 0.45), (0.8, 0.45)],
+            &[(0.2, 0.45), (0.2, 0.82), (0.85, 0.82)],
+        ],
+    ),
+    (
+        'ㅁ',
+        &[
+            &[(0.2, 0.2), (0.2, 0.8)],
+            &[(0.2, 0.2), (0.8, 0.2), (0.8, 0.8)],
+            &[(0.2, 0.8), (0.8, 0.8)],
+        ],
+    ),
+    (
+        'ㅂ',
+        &[
+            &[(0.2, 0.15), (0.2, 0.85)],
+            &[(0.8, 0.15), (0.8, 0.85)],
+            &[(0.2, 0.5), (0.8, 0.5)],
+            &[(0.2, 0.85), (0.8, 0.85)],
+        ],
+    ),
+    (
+        'ㅅ',
+        &[&[(0.5, 0.15), (0.15, 0.85)], &[(0.45, 0.45), (0.85, 0.85)]],
+    ),
+    ('ㅇ', &[CIRCLE]),
+    (
+        'ㅈ',
+        &[
+            &[(0.2, 0.2), (0.8, 0.2), (0.2, 0.85)],
+            &[(0.5, 0.5), (0.85, 0.85)],
+        ],
+    ),
+    (
+        'ㅊ',
+        &[
+            &[(0.45, 0.05), (0.55, 0.15)],
+            &[(0.2, 0.3), (0.8, 0.3), (0.2, 0.88)],
+            &[(0.5, 0.58), (0.85, 0.88)],
+        ],
+    ),
+    (
+        'ㅋ',
+        &[
+            &[(0.2, 0.2), (0.8, 0.2), (0.8, 0.85)],
+            &[(0.2, 0.5), (0.8, 0.5)],
+        ],
+    ),
+    (
+        'ㅌ',
+        &[
+            &[(0.2, 0.2), (0.8, 0.2)],
+            &[(0.2, 0.5), (0.8, 0.5)],
+            &[(0.2, 0.2), (0.2, 0.8), (0.85, 0.8)],
+        ],
+    ),
+    (
+        'ㅍ',
+        &[
+            &[(0.15, 0.2), (0.85, 0.2)],
+            &[(0.35, 0.2), (0.35, 0.8)],
+            &[(0.65, 0.2), (0.65, 0.8)],
+            &[(0.15, 0.8), (0.85, 0.8)],
+        ],
+    ),
+    (
+        'ㅎ',
+        &[
+            &[(0.45, 0.05), (0.55, 0.15)],
+            &[(0.2, 0.3), (0.8, 0.3)],
+            SMALL_CIRCLE,
+        ],
+    ),
+    (
+        'ㅏ',
+        &[&[(0.4, 0.1), (0.4, 0.9)], &[(0.4, 0.5), (0.7, 0.5)]],
+    ),
+    (
+        'ㅑ',
+        &[
+            &[(0.4, 0.1), (0.4, 0.9)],
+            &[(0.4, 0.38), (0.7, 0.38)],
+            &[(0.4, 0.62), (0.7, 0.62)],
+        ],
+    ),
+    (
+        'ㅓ',
+        &[&[(0.3, 0.5), (0.6, 0.5)], &[(0.6, 0.1), (0.6, 0.9)]],
+    ),
+    (
+        'ㅕ',
+        &[
+            &[(0.3, 0.38), (0.6, 0.38)],
+            &[(0.3, 0.62), (0.6, 0.62)],
+            &[(0.6, 0.1), (0.6, 0.9)],
+        ],
+    ),
+    (
+        'ㅗ',
+        &[&[(0.5, 0.35), (0.5, 0.6)], &[(0.1, 0.6), (0.9, 0.6)]],
+    ),
+    (
+        'ㅛ',
+        &[
+            &[(0.38, 0.35), (0.38, 0.6)],
+            &[(0.62, 0.35), (0.62, 0.6)],
+            &[(0.1, 0.6), (0.9, 0.6)],
+        ],
+    ),
+    (
+        'ㅜ',
+        &[&[(0.1, 0.4), (0.9, 0.4)], &[(0.5, 0.4), (0.5, 0.7)]],
+    ),
+    (
+        'ㅠ',
+        &[
+            &[(0.1, 0.4), (0.9, 0.4)],
+            &[(0.38, 0.4), (0.38, 0.7)],
+            &[(0.62, 0.4), (0.62, 0.7)],
+        ],
+    ),
+    ('ㅡ', &[&[(0.1, 0.5), (0.9, 0.5)]]),
+    ('ㅣ', &[&[(0.5, 0.1), (0.5, 0.9)]]),
+];
+
+/// Strokes of a basic jamo in writing order, or `None` for compound jamo
+/// and anything else
+pub fn stroke_order(jamo: char) -> Option<Vec<Stroke>> {
+    let (_, paths) = STROKES.iter().find(|(c, _)| *c == jamo)?;
+    Some(
+        paths
+            .iter()
+            .map(|path| Stroke {
+                points: path.to_vec(),
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn giyeok_is_one_stroke() {
+        let strokes = stroke_order('ㄱ').unwrap();
+        assert_eq!(strokes.len(), 1);
+        assert_eq!(strokes[0].points.len(), 3);
+    }
+
+    #[test]
+    fn compound_jamo_have_no_strokes_of_their_own() {
+        for c in ['ㄲ', 'ㅆ', 'ㅐ', 'ㅘ', 'ㄳ', 'A', '가'] {
+            assert_eq!(stroke_order(c), None, "{c}");
+        }
+    }
+
+    #[test]
+    fn every_stroke_fits_the_unit_box() {
+        for (jamo, paths) in STROKES {
+            for path in paths {
+                assert!(path.len() >= 2, "{jamo}");
+                assert!(
+                    path.iter()
+                        .all(|&(x, y)| (0.0..=1.0).contains(&x) && (0.0..=1.0).contains(&y)),
+                    "{jamo}"
+                );
+            }
+        }
+    }
+}
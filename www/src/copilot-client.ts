@@ -12,6 +12,8 @@ interface CommandResponse<T> {
   success: boolean
   data: T | null
   error: string | null
+  /** Machine-readable kind of failure, e.g. "cli_not_found" */
+  error_code: string | null
 }
 
 /** Copilot service status */
@@ -21,6 +23,8 @@ export interface CopilotStatus {
   cli_installed: boolean
   cli_authenticated: boolean
   message: string
+  /** The CLI died and the next request restarts it */
+  restartable: boolean
 }
 
 /** Learning context for personalized responses */
@@ -50,6 +54,7 @@ const UNAVAILABLE_STATUS: CopilotStatus = {
   cli_installed: false,
   cli_authenticated: false,
   message: 'AI assistant requires the desktop app',
+  restartable: false,
 }
 
 /**
@@ -105,8 +110,9 @@ export class CopilotClient {
     try {
       const response = await invoke<CommandResponse<CopilotStatus>>('copilot_init')
 
-      if (response.success && response.data) {
-        this.initialized = true
+      // A failed init still sends the status explaining what is missing
+      if (response.data) {
+        this.initialized = response.success
         this.available = response.data.available
         this.lastStatus = response.data
         return response.data